@@ -0,0 +1,22 @@
+/// Adds one to a number written in binary
+/// The machine uses its own alphabet, with `_` as the blank symbol, and reads and writes
+/// binary numbers
+
+alphabet = {0, 1};
+blank = _;
+encoding = binary;
+
+{1011};
+
+I = {q0};
+F = {q2};
+
+// Go to the last digit
+(q0, 0, 0, R, q0);
+(q0, 1, 1, R, q0);
+(q0, _, _, L, q1);
+
+// Carry the one to the left
+(q1, 1, 0, L, q1);
+(q1, 0, 1, H, q2);
+(q1, _, 1, H, q2);
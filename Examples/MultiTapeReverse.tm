@@ -0,0 +1,34 @@
+/// Reverses a word using a second tape
+/// The word is copied to the second tape, which is then read backwards
+/// while the first tape is rewritten from the start
+
+alphabet = {a, b};
+blank = _;
+tapes = 2;
+
+{abb};
+{};
+
+I = {q0};
+F = {q3};
+
+// Copy the word to the second tape
+(q0, [a, _], [a, a], [R, R], q0);
+(q0, [b, _], [b, b], [R, R], q0);
+(q0, [_, _], [_, _], [L, L], q1);
+
+// Go back to the start of the first tape
+(q1, [a, a], [a, a], [L, H], q1);
+(q1, [a, b], [a, b], [L, H], q1);
+(q1, [b, a], [b, a], [L, H], q1);
+(q1, [b, b], [b, b], [L, H], q1);
+(q1, [_, a], [_, a], [R, H], q2);
+(q1, [_, b], [_, b], [R, H], q2);
+(q1, [_, _], [_, _], [H, H], q3);
+
+// Write the second tape backwards on the first one
+(q2, [a, a], [a, a], [R, L], q2);
+(q2, [a, b], [b, b], [R, L], q2);
+(q2, [b, a], [a, a], [R, L], q2);
+(q2, [b, b], [b, b], [R, L], q2);
+(q2, [_, _], [_, _], [H, H], q3);
\ No newline at end of file
@@ -0,0 +1,20 @@
+/// Accepts the words that contain `aba`
+/// On every `a`, the machine can either keep looking or guess that `aba` starts there
+
+alphabet = {a, b};
+blank = _;
+nondeterministic = true;
+
+{babbaba};
+
+I = {q0};
+F = {q3};
+
+// Keep looking, or guess that `aba` starts here
+(q0, a, a, R, q0);
+(q0, b, b, R, q0);
+(q0, a, a, R, q1);
+
+// Check the rest of the word
+(q1, b, b, R, q2);
+(q2, a, a, H, q3);
\ No newline at end of file
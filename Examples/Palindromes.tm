@@ -0,0 +1,37 @@
+/// Decides whether a word over {a, b} is a palindrome
+/// The machine erases the first symbol, checks that the last one is the same and erases it too
+
+alphabet = {a, b};
+blank = _;
+
+{abbabba};
+
+I = {q0};
+accept = {yes};
+reject = {no};
+
+// Take the first symbol
+(q0, a, _, R, qa);
+(q0, b, _, R, qb);
+(q0, _, _, H, yes);
+
+// Go to the end of the word remembering the symbol
+(qa, a, a, R, qa);
+(qa, b, b, R, qa);
+(qa, _, _, L, ca);
+(qb, a, a, R, qb);
+(qb, b, b, R, qb);
+(qb, _, _, L, cb);
+
+// Check the last symbol
+(ca, a, _, L, back);
+(ca, b, b, H, no);
+(ca, _, _, H, yes);
+(cb, b, _, L, back);
+(cb, a, a, H, no);
+(cb, _, _, H, yes);
+
+// Go back to the start of the word
+(back, a, a, L, back);
+(back, b, b, L, back);
+(back, _, _, R, q0);
\ No newline at end of file
@@ -11,9 +11,9 @@ use version::{version, Version};
 
 use crate::{console_err, console_log, get_lang, Language};
 
-const QUALIFIER: &str = "org";
-const ORGANIZATION: &str = "margual56";
-const APPLICATION: &str = "Turing Machine";
+pub(crate) const QUALIFIER: &str = "org";
+pub(crate) const ORGANIZATION: &str = "margual56";
+pub(crate) const APPLICATION: &str = "Turing Machine";
 
 #[derive(Serialize, Deserialize, Copy, Clone, Debug)]
 pub struct Config {
@@ -30,8 +30,6 @@ pub struct Config {
 
     pub tape_speed: f32,
 
-    pub threshold_inf_loop: usize,
-
     pub served_survey: bool,
 }
 
@@ -44,7 +42,6 @@ impl Config {
             autosave_disabled: false,
             tape_size: 100.0,
             tape_speed: 1.0,
-            threshold_inf_loop: 100,
             served_survey: false,
         }
     }
@@ -171,15 +168,6 @@ impl Config {
         self.save();
     }
 
-    pub fn threshold_inf_loop(&self) -> usize {
-        self.threshold_inf_loop
-    }
-
-    pub fn set_threshold_inf_loop(&mut self, t: usize) {
-        self.threshold_inf_loop = t;
-        self.save();
-    }
-
     pub fn tape_size(&self) -> f32 {
         self.tape_size
     }
@@ -0,0 +1,208 @@
+//! The compact notation of busy beavers, e.g. `1RB1LB_1LA1RZ`.
+//!
+//! Every state is a letter (`A` is the initial one) and the transitions of each state are
+//! separated by `_`. A state has one transition for every symbol (`0` is the blank), written as
+//! the symbol to write, the movement and the next state. A next state that is not one of the
+//! states of the machine (usually `Z` or `H`) halts it, and `---` marks a transition that is not
+//! defined.
+
+use crate::machine::{Move, Program};
+
+/// The state used to halt the machine when exporting it
+const HALT_STATE: char = 'Z';
+
+/// Converts a busy beaver into the code of an equivalent `.tm` program
+pub fn import(notation: &str) -> Result<String, String> {
+    let notation = notation.trim();
+    let groups: Vec<&str> = notation.split('_').collect();
+    let states: Vec<char> = ('A'..='Z').take(groups.len()).collect();
+    if groups.len() > 25 {
+        return Err(String::from("a busy beaver can have at most 25 states"));
+    }
+
+    let symbol_count = groups[0].chars().count() / 3;
+    if !(2..=10).contains(&symbol_count) {
+        return Err(String::from(
+            "a busy beaver must have between 2 and 10 symbols, written as 3 characters each",
+        ));
+    }
+
+    let mut code = format!(
+        "/// Busy beaver {}\n/// with {} states and {} symbols\n\n",
+        notation,
+        states.len(),
+        symbol_count
+    );
+    code += &format!(
+        "alphabet = {{{}}};\n",
+        (0..symbol_count)
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    code += "blank = 0;\n";
+    if symbol_count == 2 {
+        // The score of a busy beaver is the number of ones it leaves on the tape
+        code += "encoding = unary;\n";
+    }
+    code += "\n{};\n\n";
+
+    let mut rules = String::new();
+    let mut halting: Vec<char> = vec![];
+    for (state, group) in states.iter().zip(&groups) {
+        let chars: Vec<char> = group.chars().collect();
+        if chars.len() != 3 * symbol_count {
+            return Err(format!(
+                "the state {} has {} characters, but every state must have {} ({} symbols)",
+                state,
+                chars.len(),
+                3 * symbol_count,
+                symbol_count
+            ));
+        }
+
+        rules += &format!("\n// {}\n", state);
+        for (read, t) in chars.chunks(3).enumerate() {
+            if t == ['-', '-', '-'] {
+                rules += &format!("// ({}, {}) is not defined\n", state, read);
+                continue;
+            }
+
+            let write = t[0]
+                .to_digit(10)
+                .filter(|d| (*d as usize) < symbol_count)
+                .ok_or_else(|| format!("invalid symbol `{}` in the state {}", t[0], state))?;
+            let movement = match t[1] {
+                'L' => Move::Left,
+                'R' => Move::Right,
+                m => return Err(format!("invalid movement `{}` in the state {}", m, state)),
+            };
+            let next = t[2];
+            if !next.is_ascii_uppercase() {
+                return Err(format!("invalid state `{}` in the state {}", next, state));
+            }
+            if !states.contains(&next) && !halting.contains(&next) {
+                halting.push(next);
+            }
+
+            rules += &format!(
+                "({}, {}, {}, {}, {});\n",
+                state, read, write, movement, next
+            );
+        }
+    }
+
+    code += "I = {A};\n";
+    code += &format!(
+        "F = {{{}}};\n",
+        halting
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    code += &rules;
+
+    Ok(code)
+}
+
+/// Converts a program into the busy beaver notation. The program must work over the symbols
+/// `0, 1, ...` with `0` as the blank, and only move left or right. Its final states become the
+/// halting state `Z`, and the initial tape is not kept (busy beavers start on a blank tape).
+pub fn export(program: &Program) -> Result<String, String> {
+    if program.tape_count() > 1 || program.nondeterministic {
+        return Err(String::from(
+            "only deterministic single-tape machines can be written as busy beavers",
+        ));
+    }
+
+    let symbol_count = program.alphabet.len();
+    if program.blank != '0'
+        || (0..symbol_count).any(|d| {
+            !program
+                .alphabet
+                .contains(&char::from_digit(d as u32, 10).unwrap_or('?'))
+        })
+    {
+        return Err(String::from(
+            "the symbols of a busy beaver must be 0, 1, ... (at most 10), with 0 as the blank",
+        ));
+    }
+
+    let mut states: Vec<&str> = vec![&program.initial_state];
+    for t in &program.transitions {
+        for s in [&t.from, &t.to] {
+            if !program.is_final(s) && !states.contains(&s.as_str()) {
+                states.push(s);
+            }
+        }
+    }
+    if states.len() > 25 {
+        return Err(String::from("a busy beaver can have at most 25 states"));
+    }
+
+    let letter = |state: &str| match states.iter().position(|s| *s == state) {
+        Some(i) => (b'A' + i as u8) as char,
+        None => HALT_STATE,
+    };
+
+    let mut groups = vec![];
+    for state in &states {
+        let mut group = String::new();
+
+        for read in 0..symbol_count {
+            let read = char::from_digit(read as u32, 10).unwrap_or('0');
+
+            match program.transition(state, &[read]) {
+                Some(t) => {
+                    if program.is_final(state) {
+                        return Err(format!(
+                            "the final state {} cannot have transitions in a busy beaver",
+                            state
+                        ));
+                    }
+
+                    let movement = match t.movement[0] {
+                        Move::Left => 'L',
+                        Move::Right => 'R',
+                        Move::Halt => {
+                            return Err(format!(
+                                "busy beavers cannot stay in the same cell (line {})",
+                                t.line
+                            ))
+                        }
+                    };
+
+                    group.push(t.write[0]);
+                    group.push(movement);
+                    group.push(letter(&t.to));
+                }
+                None => group += "---",
+            }
+        }
+
+        groups.push(group);
+    }
+
+    Ok(groups.join("_"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::Machine;
+
+    #[test]
+    fn round_trip() {
+        let code = import("1RB1LB_1LA1RZ").unwrap();
+        let program = Program::parse(&code).unwrap();
+
+        let mut machine = Machine::new(program.clone());
+        machine.run();
+        assert!(machine.is_final());
+        assert_eq!(machine.steps(), 6);
+        assert_eq!(machine.word(), "1111");
+
+        assert_eq!(export(&program).unwrap(), "1RB1LB_1LA1RZ");
+    }
+}
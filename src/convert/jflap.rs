@@ -0,0 +1,443 @@
+//! JFLAP (`.jff`) files of single-tape Turing machines.
+//!
+//! JFLAP marks blank cells with an empty symbol, so the blank of the program is exported as an
+//! empty `<read/>` or `<write/>`, and the S (stay) movement is the H movement of the `.tm` syntax.
+//! The positions of the states in the JFLAP diagram are kept in `// position` comments of the
+//! imported code, so that exporting it again does not lose the layout.
+
+use std::{collections::HashMap, io::Write};
+
+use xml::{
+    common::XmlVersion,
+    reader::{EventReader, XmlEvent},
+    writer::{EmitterConfig, EventWriter, XmlEvent as WriterEvent},
+};
+
+use crate::machine::{Move, Program};
+
+use super::{state_name, symbol_set};
+
+/// Blank symbols to try when the imported machine does not use them
+const BLANKS: [char; 4] = ['_', '#', '□', '$'];
+
+#[derive(Debug, Default)]
+struct State {
+    id: String,
+    name: String,
+    position: Option<(f32, f32)>,
+    initial: bool,
+    is_final: bool,
+}
+
+#[derive(Debug, Default)]
+struct Transition {
+    from: String,
+    to: String,
+    read: String,
+    write: String,
+    movement: String,
+}
+
+/// Converts a JFLAP Turing machine into the code of an equivalent `.tm` program
+pub fn import(xml: &str) -> Result<String, String> {
+    let mut states: Vec<State> = vec![];
+    let mut transitions: Vec<Transition> = vec![];
+    let mut machine_type = String::new();
+    let mut element = String::new();
+
+    for event in EventReader::new(xml.as_bytes()) {
+        match event.map_err(|e| format!("invalid JFLAP file: {}", e))? {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                element = name.local_name;
+                let attribute = |key: &str| {
+                    attributes
+                        .iter()
+                        .find(|a| a.name.local_name == key)
+                        .map(|a| a.value.clone())
+                };
+
+                match element.as_str() {
+                    "state" => states.push(State {
+                        id: attribute("id").unwrap_or_default(),
+                        name: attribute("name").unwrap_or_default(),
+                        ..Default::default()
+                    }),
+                    "initial" => {
+                        if let Some(s) = states.last_mut() {
+                            s.initial = true;
+                        }
+                    }
+                    "final" => {
+                        if let Some(s) = states.last_mut() {
+                            s.is_final = true;
+                        }
+                    }
+                    "transition" => transitions.push(Transition::default()),
+                    "block" => {
+                        return Err(String::from(
+                            "JFLAP building blocks are not supported, only plain Turing machines",
+                        ))
+                    }
+                    _ => {}
+                }
+            }
+            XmlEvent::Characters(text) => {
+                let text = text.trim().to_string();
+
+                match element.as_str() {
+                    "type" => machine_type = text,
+                    "tapes" if text != "1" => {
+                        return Err(format!(
+                            "only single-tape machines can be imported, this one has {} tapes",
+                            text
+                        ))
+                    }
+                    "x" | "y" => {
+                        let value: f32 = text
+                            .parse()
+                            .map_err(|_| format!("invalid position of a state: {}", text))?;
+
+                        if let Some(s) = states.last_mut() {
+                            let (x, y) = s.position.unwrap_or_default();
+                            s.position = Some(if element == "x" {
+                                (value, y)
+                            } else {
+                                (x, value)
+                            });
+                        }
+                    }
+                    "from" | "to" | "read" | "write" | "move" => {
+                        if let Some(t) = transitions.last_mut() {
+                            match element.as_str() {
+                                "from" => t.from = text,
+                                "to" => t.to = text,
+                                "read" => t.read = text,
+                                "write" => t.write = text,
+                                _ => t.movement = text,
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            XmlEvent::EndElement { .. } => element.clear(),
+            _ => {}
+        }
+    }
+
+    if machine_type != "turing" {
+        return Err(format!(
+            "the file contains a JFLAP \"{}\", not a Turing machine",
+            machine_type
+        ));
+    }
+
+    // State names of JFLAP can be anything, so they are adapted to the `.tm` syntax
+    let mut names: HashMap<&str, String> = HashMap::new();
+    let mut taken: Vec<String> = vec![];
+    for s in &states {
+        let name = state_name(&s.name, &format!("q{}", s.id), &taken);
+        taken.push(name.clone());
+        names.insert(&s.id, name);
+    }
+    let name = |id: &str| {
+        names
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("a transition uses the unknown state {}", id))
+    };
+
+    let mut symbols: Vec<char> = vec![];
+    for t in &transitions {
+        for s in [&t.read, &t.write] {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (None, _) => {}
+                (Some(c), None) if !symbols.contains(&c) => symbols.push(c),
+                (Some(_), None) => {}
+                _ => {
+                    return Err(format!(
+                        "the symbol \"{}\" has more than one character, which is not supported",
+                        s
+                    ))
+                }
+            }
+        }
+    }
+    let blank = BLANKS
+        .into_iter()
+        .find(|b| !symbols.contains(b))
+        .ok_or_else(|| String::from("the machine uses every symbol that could be the blank"))?;
+    let symbol = |s: &str| s.chars().next().unwrap_or(blank);
+
+    let initial = states
+        .iter()
+        .find(|s| s.initial)
+        .ok_or_else(|| String::from("the machine has no initial state"))?;
+
+    let mut code = String::from("/// Imported from JFLAP\n\n");
+    code += &format!("alphabet = {};\n", symbol_set(&symbols)?);
+    code += &format!("blank = {};\n\n", blank);
+    code += "{};\n\n";
+    code += &format!("I = {{{}}};\n", names[initial.id.as_str()]);
+    code += &format!(
+        "F = {{{}}};\n\n",
+        states
+            .iter()
+            .filter(|s| s.is_final)
+            .map(|s| names[s.id.as_str()].as_str())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    );
+
+    for t in &transitions {
+        let movement = match t.movement.as_str() {
+            "L" => "L",
+            "R" => "R",
+            "S" => "H",
+            m => return Err(format!("unknown movement \"{}\"", m)),
+        };
+
+        code += &format!(
+            "({}, {}, {}, {}, {});\n",
+            name(&t.from)?,
+            symbol(&t.read),
+            symbol(&t.write),
+            movement,
+            name(&t.to)?
+        );
+    }
+
+    let positions: Vec<String> = states
+        .iter()
+        .filter_map(|s| {
+            s.position
+                .map(|(x, y)| format!("// position {} = ({}, {})\n", names[s.id.as_str()], x, y))
+        })
+        .collect();
+    if !positions.is_empty() {
+        code += "\n// Positions of the states in the JFLAP diagram\n";
+        code += &positions.concat();
+    }
+
+    Ok(code)
+}
+
+/// Reads the `// position q0 = (x, y)` comments left by [`import`]
+fn positions(code: &str) -> HashMap<String, (f32, f32)> {
+    code.lines()
+        .filter_map(|line| {
+            let (state, position) = line.trim().strip_prefix("// position ")?.split_once('=')?;
+            let (x, y) = position
+                .trim()
+                .strip_prefix('(')?
+                .strip_suffix(')')?
+                .split_once(',')?;
+
+            Some((
+                state.trim().to_string(),
+                (x.trim().parse().ok()?, y.trim().parse().ok()?),
+            ))
+        })
+        .collect()
+}
+
+/// Converts a single-tape program into a JFLAP Turing machine. States without a known position
+/// are laid out in a circle.
+pub fn export(program: &Program) -> Result<String, String> {
+    if program.tape_count() > 1 {
+        return Err(String::from(
+            "only single-tape machines can be exported to JFLAP",
+        ));
+    }
+
+    let mut states: Vec<&str> = vec![&program.initial_state];
+    for t in &program.transitions {
+        for s in [&t.from, &t.to] {
+            if !states.contains(&s.as_str()) {
+                states.push(s);
+            }
+        }
+    }
+    for s in program.final_states.iter().chain(&program.accept_states) {
+        if !states.contains(&s.as_str()) {
+            states.push(s);
+        }
+    }
+
+    let known = positions(&program.code);
+    let radius = 50.0 * states.len().max(2) as f32;
+    let position = |i: usize, state: &str| {
+        known.get(state).copied().unwrap_or_else(|| {
+            let angle = i as f32 / states.len() as f32 * std::f32::consts::TAU;
+            (
+                radius * (1.0 + angle.cos()) + 50.0,
+                radius * (1.0 + angle.sin()) + 50.0,
+            )
+        })
+    };
+    let symbol = |c: char| {
+        if c == program.blank {
+            String::new()
+        } else {
+            c.to_string()
+        }
+    };
+
+    let mut out = vec![];
+    let mut w = EmitterConfig::new()
+        .perform_indent(true)
+        .create_writer(&mut out);
+
+    write(
+        &mut w,
+        WriterEvent::StartDocument {
+            version: XmlVersion::Version10,
+            encoding: Some("UTF-8"),
+            standalone: Some(false),
+        },
+    )?;
+    write(
+        &mut w,
+        WriterEvent::comment("Created with Turing Machine 2.0"),
+    )?;
+    write(&mut w, WriterEvent::start_element("structure"))?;
+    element(&mut w, "type", "turing")?;
+    write(&mut w, WriterEvent::start_element("automaton"))?;
+
+    for (i, state) in states.iter().enumerate() {
+        let id = i.to_string();
+        let (x, y) = position(i, state);
+
+        write(
+            &mut w,
+            WriterEvent::start_element("state")
+                .attr("id", &id)
+                .attr("name", state),
+        )?;
+        element(&mut w, "x", &format!("{:.1}", x))?;
+        element(&mut w, "y", &format!("{:.1}", y))?;
+        if *state == program.initial_state {
+            element(&mut w, "initial", "")?;
+        }
+        if program.is_final(state) {
+            element(&mut w, "final", "")?;
+        }
+        write(&mut w, WriterEvent::end_element())?;
+    }
+
+    let id = |state: &str| {
+        states
+            .iter()
+            .position(|s| *s == state)
+            .unwrap_or_default()
+            .to_string()
+    };
+    for t in &program.transitions {
+        write(&mut w, WriterEvent::start_element("transition"))?;
+        element(&mut w, "from", &id(&t.from))?;
+        element(&mut w, "to", &id(&t.to))?;
+        element(&mut w, "read", &symbol(t.read[0]))?;
+        element(&mut w, "write", &symbol(t.write[0]))?;
+        element(
+            &mut w,
+            "move",
+            match t.movement[0] {
+                Move::Left => "L",
+                Move::Right => "R",
+                Move::Halt => "S",
+            },
+        )?;
+        write(&mut w, WriterEvent::end_element())?;
+    }
+
+    write(&mut w, WriterEvent::end_element())?;
+    write(&mut w, WriterEvent::end_element())?;
+
+    String::from_utf8(out).map_err(|e| e.to_string())
+}
+
+fn write<'a, W: Write>(
+    writer: &mut EventWriter<W>,
+    event: impl Into<WriterEvent<'a>>,
+) -> Result<(), String> {
+    writer
+        .write(event)
+        .map_err(|e| format!("cannot write the JFLAP file: {}", e))
+}
+
+/// Writes an element that only contains text (or nothing, if the text is empty)
+fn element<W: Write>(writer: &mut EventWriter<W>, name: &str, text: &str) -> Result<(), String> {
+    write(writer, WriterEvent::start_element(name))?;
+    if !text.is_empty() {
+        write(writer, WriterEvent::characters(text))?;
+    }
+    write(writer, WriterEvent::end_element())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{machine::Machine, runner::with_input};
+
+    /// Replaces every `a` with a `b`, and stops at the blank after the word
+    const JFF: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<structure>
+    <type>turing</type>
+    <automaton>
+        <state id="0" name="q0">
+            <x>60.0</x>
+            <y>80.0</y>
+            <initial/>
+        </state>
+        <state id="1" name="q1">
+            <x>200.0</x>
+            <y>80.0</y>
+            <final/>
+        </state>
+        <transition>
+            <from>0</from>
+            <to>0</to>
+            <read>a</read>
+            <write>b</write>
+            <move>R</move>
+        </transition>
+        <transition>
+            <from>0</from>
+            <to>0</to>
+            <read>b</read>
+            <write>b</write>
+            <move>R</move>
+        </transition>
+        <transition>
+            <from>0</from>
+            <to>1</to>
+            <read/>
+            <write/>
+            <move>L</move>
+        </transition>
+    </automaton>
+</structure>"#;
+
+    #[test]
+    fn round_trip() {
+        let code = import(JFF).unwrap();
+        let program = Program::parse(&code).unwrap();
+        assert_eq!(program.blank, '_');
+
+        let mut machine = Machine::new(Program::parse(&with_input(&code, "aab")).unwrap());
+        machine.run();
+        assert!(machine.is_final());
+        assert_eq!(machine.word(), "bbb");
+
+        let exported = export(&program).unwrap();
+        assert!(exported.contains("<type>turing</type>"));
+        assert!(exported.contains("<read />") || exported.contains("<read/>"));
+        assert!(exported.contains("<x>60.0</x>"));
+
+        // The states, transitions and positions survive a second import
+        assert_eq!(import(&exported).unwrap(), code);
+    }
+}
@@ -0,0 +1,117 @@
+//! Conversions between the `.tm` syntax and the formats of other simulators
+
+mod busy_beaver;
+mod jflap;
+mod turingmachine_io;
+
+use std::path::Path;
+
+use crate::machine::{is_state, is_symbol, Program};
+
+/// A format in which a machine can be written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The code of this simulator
+    Tm,
+    /// JFLAP `.jff` files
+    Jflap,
+    /// The YAML machines of turingmachine.io
+    Yaml,
+    /// The compact notation of busy beavers, e.g. `1RB1LB_1LA1RZ`
+    BusyBeaver,
+}
+
+/// The formats of other simulators, which machines can be imported from and exported to
+pub const FOREIGN_FORMATS: [Format; 3] = [Format::Jflap, Format::Yaml, Format::BusyBeaver];
+
+impl Format {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Tm => "TuringMachine",
+            Format::Jflap => "JFLAP",
+            Format::Yaml => "turingmachine.io",
+            Format::BusyBeaver => "Busy beaver",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Tm => "tm",
+            Format::Jflap => "jff",
+            Format::Yaml => "yaml",
+            Format::BusyBeaver => "bb",
+        }
+    }
+
+    /// Guesses the format of a file from its extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "tm" => Some(Format::Tm),
+            "jff" => Some(Format::Jflap),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "bb" => Some(Format::BusyBeaver),
+            _ => None,
+        }
+    }
+}
+
+/// Converts a machine written in the format `from` into the format `to`
+pub fn convert(text: &str, from: Format, to: Format) -> Result<String, String> {
+    let code = match from {
+        Format::Tm => text.to_string(),
+        Format::Jflap => jflap::import(text)?,
+        Format::Yaml => turingmachine_io::import(text)?,
+        Format::BusyBeaver => busy_beaver::import(text)?,
+    };
+
+    match to {
+        Format::Tm => Ok(code),
+        Format::Jflap => jflap::export(&program(&code)?),
+        Format::Yaml => turingmachine_io::export(&program(&code)?),
+        Format::BusyBeaver => busy_beaver::export(&program(&code)?).map(|bb| bb + "\n"),
+    }
+}
+
+/// Parses the code of a program. Binary programs are parsed as programs over the alphabet
+/// `{0, 1}` whose blank is `0`, which is how `turing_lib` runs them.
+fn program(code: &str) -> Result<Program, String> {
+    Program::parse(code).map_err(|e| e.to_string())
+}
+
+/// Adapts the name of a state of another format to the `.tm` syntax, making sure it is not one
+/// of the names already taken
+fn state_name(name: &str, fallback: &str, taken: &[String]) -> String {
+    let mut name: String = name
+        .trim()
+        .chars()
+        .map(|c| if is_state(&c.to_string()) { c } else { '_' })
+        .collect();
+    if name.is_empty() {
+        name = fallback.to_string();
+    }
+
+    let mut unique = name.clone();
+    let mut i = 2;
+    while taken.contains(&unique) {
+        unique = format!("{}_{}", name, i);
+        i += 1;
+    }
+
+    unique
+}
+
+/// Writes the alphabet of a program, e.g. `{a, b}`
+fn symbol_set(symbols: &[char]) -> Result<String, String> {
+    if let Some(s) = symbols.iter().find(|s| !is_symbol(**s)) {
+        return Err(format!("`{}` cannot be used as a tape symbol", s));
+    }
+
+    Ok(format!(
+        "{{{}}}",
+        symbols
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    ))
+}
@@ -0,0 +1,387 @@
+//! Machines of [turingmachine.io](https://turingmachine.io), written in YAML:
+//!
+//! ```yaml
+//! input: '1011'
+//! blank: ' '
+//! start state: right
+//! table:
+//!   right:
+//!     [1, 0]: R
+//!     ' ': {L: carry}
+//!   carry:
+//!     1: {write: 0, L}
+//!     [0, ' ']: {write: 1, L: done}
+//!   done:
+//! ```
+//!
+//! Every state maps the symbols it reads to an action: a movement (`L` or `R`) that keeps the
+//! state, or a mapping with the symbol to write and the movement, whose value is the next state.
+//! States without any action, like `done`, are where the machine halts, so they become the final
+//! states of the program.
+
+use serde_yaml::{Mapping, Value};
+
+use crate::machine::{is_symbol, Move, Program};
+
+use super::{state_name, symbol_set};
+
+/// Blank symbols to use when the blank of the machine cannot be a tape symbol (e.g. a space)
+const BLANKS: [char; 4] = ['_', '#', '□', '$'];
+
+/// The text of a scalar of the YAML document (symbols and states can be numbers)
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn get<'a>(map: &'a Mapping, key: &str) -> Option<&'a Value> {
+    map.get(Value::String(key.to_string()))
+}
+
+/// Converts a machine of turingmachine.io into the code of an equivalent `.tm` program
+pub fn import(yaml: &str) -> Result<String, String> {
+    let document: Value =
+        serde_yaml::from_str(yaml).map_err(|e| format!("invalid YAML document: {}", e))?;
+
+    // The gists shared by the site wrap the machine in a `source code` string
+    let document = match document
+        .as_mapping()
+        .and_then(|m| get(m, "source code"))
+        .and_then(Value::as_str)
+    {
+        Some(source) => {
+            serde_yaml::from_str(source).map_err(|e| format!("invalid YAML source code: {}", e))?
+        }
+        None => document,
+    };
+    let machine = document
+        .as_mapping()
+        .ok_or_else(|| String::from("the document does not describe a machine"))?;
+
+    let blank = get(machine, "blank")
+        .and_then(scalar)
+        .ok_or_else(|| String::from("the machine does not declare its `blank` symbol"))?;
+    let start = get(machine, "start state")
+        .and_then(scalar)
+        .ok_or_else(|| String::from("the machine does not declare its `start state`"))?;
+    let input = get(machine, "input").and_then(scalar).unwrap_or_default();
+    let table = get(machine, "table")
+        .and_then(Value::as_mapping)
+        .ok_or_else(|| String::from("the machine does not have a transition `table`"))?;
+
+    // (state, symbols read, symbol written, movement, next state), before renaming anything
+    let mut transitions: Vec<(String, String, Option<String>, Move, String)> = vec![];
+    let mut states: Vec<String> = vec![];
+    let mut halting: Vec<String> = vec![];
+
+    for (state, actions) in table {
+        let state = scalar(state).ok_or_else(|| format!("invalid state {:?}", state))?;
+        states.push(state.clone());
+
+        let actions = match actions {
+            Value::Null => {
+                halting.push(state);
+                continue;
+            }
+            Value::Mapping(m) if m.is_empty() => {
+                halting.push(state);
+                continue;
+            }
+            Value::Mapping(m) => m,
+            _ => return Err(format!("the actions of the state {} are not valid", state)),
+        };
+
+        for (read, action) in actions {
+            let read: Vec<String> = match read {
+                Value::Sequence(symbols) => symbols.iter().filter_map(scalar).collect(),
+                s => scalar(s).into_iter().collect(),
+            };
+
+            let (write, movement, next) = match action {
+                Value::String(m) => (None, m.clone(), state.clone()),
+                Value::Mapping(m) => {
+                    let write = get(m, "write").and_then(scalar);
+                    let (movement, next) = m
+                        .iter()
+                        .filter_map(|(k, v)| Some((scalar(k)?, v)))
+                        .find(|(k, _)| k == "L" || k == "R")
+                        .ok_or_else(|| {
+                            format!("an action of the state {} has no movement", state)
+                        })?;
+                    let next = scalar(next).unwrap_or_else(|| state.clone());
+
+                    (write, movement, next)
+                }
+                _ => return Err(format!("an action of the state {} is not valid", state)),
+            };
+
+            let movement = match movement.as_str() {
+                "L" => Move::Left,
+                "R" => Move::Right,
+                m => return Err(format!("unknown movement \"{}\"", m)),
+            };
+
+            for symbol in read {
+                transitions.push((state.clone(), symbol, write.clone(), movement, next.clone()));
+            }
+        }
+    }
+
+    for (_, _, _, _, next) in &transitions {
+        if !states.contains(next) {
+            return Err(format!("the state {} is used but not declared", next));
+        }
+    }
+
+    // Every symbol must be a single character that can be written in the `.tm` syntax
+    let mut symbols: Vec<char> = vec![];
+    let mut symbol_of = |s: &str| -> Result<char, String> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => {
+                if !symbols.contains(&c) {
+                    symbols.push(c);
+                }
+                Ok(c)
+            }
+            _ => Err(format!(
+                "the symbol \"{}\" is not a single character, which is not supported",
+                s
+            )),
+        }
+    };
+    let blank_symbol = symbol_of(&blank)?;
+    let input: Vec<char> = input
+        .chars()
+        .map(|c| symbol_of(&c.to_string()))
+        .collect::<Result<_, _>>()?;
+    let mut code_transitions = vec![];
+    for (state, read, write, movement, next) in &transitions {
+        let read = symbol_of(read)?;
+        let write = match write {
+            Some(w) => symbol_of(w)?,
+            None => read,
+        };
+        code_transitions.push((state, read, write, *movement, next));
+    }
+
+    // A blank like ' ' cannot be written in the code, so it is replaced by an unused symbol
+    let blank = if is_symbol(blank_symbol) {
+        blank_symbol
+    } else {
+        BLANKS
+            .into_iter()
+            .find(|b| !symbols.contains(b))
+            .ok_or_else(|| String::from("the machine uses every symbol that could be the blank"))?
+    };
+    let symbol = |c: char| if c == blank_symbol { blank } else { c };
+    let alphabet: Vec<char> = symbols.iter().map(|c| symbol(*c)).collect();
+
+    let mut names: Vec<(String, String)> = vec![];
+    let mut taken: Vec<String> = vec![];
+    for s in &states {
+        let name = state_name(s, "q", &taken);
+        taken.push(name.clone());
+        names.push((s.clone(), name));
+    }
+    let name = |state: &str| {
+        names
+            .iter()
+            .find(|(s, _)| s == state)
+            .map(|(_, n)| n.clone())
+            .unwrap_or_default()
+    };
+
+    let mut code = match get(machine, "name").and_then(scalar) {
+        Some(n) => format!("/// {}\n/// Imported from turingmachine.io\n\n", n),
+        None => String::from("/// Imported from turingmachine.io\n\n"),
+    };
+    code += &format!("alphabet = {};\n", symbol_set(&alphabet)?);
+    code += &format!("blank = {};\n\n", blank);
+    code += &format!(
+        "{{{}}};\n\n",
+        input.iter().map(|c| symbol(*c)).collect::<String>()
+    );
+    code += &format!("I = {{{}}};\n", name(&start));
+    code += &format!(
+        "F = {{{}}};\n",
+        halting
+            .iter()
+            .map(|s| name(s))
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+
+    for state in &states {
+        let rules: Vec<String> = code_transitions
+            .iter()
+            .filter(|(s, ..)| *s == state)
+            .map(|(s, read, write, movement, next)| {
+                format!(
+                    "({}, {}, {}, {}, {});\n",
+                    name(s),
+                    symbol(*read),
+                    symbol(*write),
+                    movement,
+                    name(next)
+                )
+            })
+            .collect();
+
+        if !rules.is_empty() {
+            code += &format!("\n// {}\n", state);
+            code += &rules.concat();
+        }
+    }
+
+    Ok(code)
+}
+
+/// Quotes a symbol for YAML
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// Writes the name of a state for YAML, only quoting the names that YAML would not read as text
+fn state(s: &str) -> String {
+    let keyword = ["y", "n", "yes", "no", "true", "false", "on", "off", "null"]
+        .contains(&s.to_lowercase().as_str());
+
+    if s.starts_with(|c: char| c.is_alphabetic()) && !keyword {
+        s.to_string()
+    } else {
+        quote(s)
+    }
+}
+
+/// Converts a single-tape program into a machine of turingmachine.io
+pub fn export(program: &Program) -> Result<String, String> {
+    if program.tape_count() > 1 {
+        return Err(String::from(
+            "only single-tape machines can be exported to turingmachine.io",
+        ));
+    }
+    if program.nondeterministic {
+        return Err(String::from(
+            "nondeterministic machines cannot be exported to turingmachine.io",
+        ));
+    }
+
+    let mut states: Vec<&str> = vec![&program.initial_state];
+    for t in &program.transitions {
+        for s in [&t.from, &t.to] {
+            if !states.contains(&s.as_str()) {
+                states.push(s);
+            }
+        }
+    }
+    for s in &program.final_states {
+        if !states.contains(&s.as_str()) {
+            states.push(s);
+        }
+    }
+
+    let mut yaml = String::new();
+    if let Some(description) = &program.description {
+        for line in description.lines() {
+            yaml += &format!("# {}\n", line);
+        }
+        yaml += "\n";
+    }
+    yaml += &format!(
+        "input: {}\n",
+        quote(&program.tapes[0].iter().collect::<String>())
+    );
+    yaml += &format!("blank: {}\n", quote(&program.blank.to_string()));
+    yaml += &format!("start state: {}\n", state(&program.initial_state));
+    yaml += "table:\n";
+
+    for name in states {
+        let transitions: Vec<_> = program
+            .transitions
+            .iter()
+            .filter(|t| t.from == name)
+            .collect();
+
+        yaml += &format!("  {}:\n", state(name));
+        for t in transitions {
+            let movement = match t.movement[0] {
+                Move::Left => "L",
+                Move::Right => "R",
+                Move::Halt => {
+                    return Err(format!(
+                        "turingmachine.io cannot stay in the same cell (line {})",
+                        t.line
+                    ))
+                }
+            };
+
+            let action = match (t.write[0] == t.read[0], t.to == t.from) {
+                (true, true) => movement.to_string(),
+                (true, false) => format!("{{{}: {}}}", movement, state(&t.to)),
+                (false, true) => format!(
+                    "{{write: {}, {}}}",
+                    quote(&t.write[0].to_string()),
+                    movement
+                ),
+                (false, false) => format!(
+                    "{{write: {}, {}: {}}}",
+                    quote(&t.write[0].to_string()),
+                    movement,
+                    state(&t.to)
+                ),
+            };
+
+            yaml += &format!("    {}: {}\n", quote(&t.read[0].to_string()), action);
+        }
+    }
+
+    Ok(yaml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::Machine;
+
+    /// The binary increment of the module documentation
+    const YAML: &str = "input: '1011'
+blank: ' '
+start state: right
+table:
+  right:
+    [1, 0]: R
+    ' ': {L: carry}
+  carry:
+    1: {write: 0, L}
+    [0, ' ']: {write: 1, L: done}
+  done:
+";
+
+    fn run(code: &str) -> String {
+        let mut machine = Machine::new(Program::parse(code).unwrap());
+        machine.run();
+        assert!(machine.is_final());
+
+        machine.word()
+    }
+
+    #[test]
+    fn round_trip() {
+        let code = import(YAML).unwrap();
+        let program = Program::parse(&code).unwrap();
+        assert_eq!(program.blank, '_');
+        assert_eq!(run(&code), "1100");
+
+        let exported = export(&program).unwrap();
+        assert!(exported.contains("input: '1011'\n"));
+        assert!(exported.contains("blank: '_'\n"));
+        assert!(exported.contains("start state: right\n"));
+
+        assert_eq!(run(&import(&exported).unwrap()), "1100");
+    }
+}
@@ -0,0 +1,258 @@
+//! Checks whether two machines compute the same function, by running both of them on every tuple
+//! of arguments in a bounded range, e.g. to compare a student machine with a reference solution.
+
+use std::ops::RangeInclusive;
+
+use crate::{
+    machine::Simulator,
+    runner::{self, RunOutcome},
+};
+
+/// Steps of each run written in the traces of a counterexample
+pub const TRACE_LIMIT: usize = 200;
+
+/// A run of one of the machines on the arguments of a counterexample
+#[derive(Clone, Debug)]
+pub struct Run {
+    /// The initial tape, with the arguments written in the encoding of the machine
+    pub tape: String,
+    pub outcome: RunOutcome,
+    /// The configurations of the first [`TRACE_LIMIT`] steps, one per line
+    pub trace: Vec<String>,
+}
+
+/// Arguments for which the machines compute different results
+#[derive(Clone, Debug)]
+pub struct Counterexample {
+    pub args: Vec<u32>,
+    pub first: Run,
+    pub second: Run,
+}
+
+/// The result of checking two machines
+#[derive(Clone, Debug)]
+pub struct Equivalence {
+    /// Number of tuples of arguments on which both machines were run
+    pub checked: usize,
+    /// The first tuple (in lexicographic order) on which they differ, if any
+    pub counterexample: Option<Counterexample>,
+}
+
+/// Parses a range of arguments written like in Rust: `0..20` (20 excluded), `0..=20`, or a
+/// single number
+pub fn parse_range(range: &str) -> Result<RangeInclusive<u32>, String> {
+    let number = |s: &str| {
+        s.trim()
+            .parse::<u32>()
+            .map_err(|_| format!("{:?} is not a natural number", s.trim()))
+    };
+
+    let range = if let Some((start, end)) = range.split_once("..=") {
+        number(start)?..=number(end)?
+    } else if let Some((start, end)) = range.split_once("..") {
+        let end = number(end)?
+            .checked_sub(1)
+            .ok_or_else(|| String::from("the range is empty"))?;
+        number(start)?..=end
+    } else {
+        let n = number(range)?;
+        n..=n
+    };
+
+    if range.is_empty() {
+        return Err(String::from("the range is empty"));
+    }
+
+    Ok(range)
+}
+
+/// Writes the configuration of the machine in a line: the step, the state and every tape with
+/// the head between brackets
+fn configuration(tm: &Simulator) -> String {
+    let tapes: Vec<String> = (0..tm.tape_count())
+        .map(|k| {
+            let head = tm.tape_position(k);
+
+            (0..tm.tape_len(k).max(head + 1))
+                .map(|i| {
+                    if i == head {
+                        format!("[{}]", tm.symbol(k, i))
+                    } else {
+                        tm.symbol(k, i).to_string()
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    format!(
+        "{:>6}  {:<10} {}",
+        tm.steps(),
+        tm.current_state(),
+        tapes.join(" | ")
+    )
+}
+
+/// One of the machines being checked
+struct Candidate<'a> {
+    name: &'a str,
+    code: &'a str,
+    /// The machine with the tape of its code, to write the arguments with its encoding
+    tm: Simulator,
+}
+
+impl<'a> Candidate<'a> {
+    fn new(name: &'a str, code: &'a str) -> Result<Self, String> {
+        let (tm, _warnings) =
+            Simulator::new(code).map_err(|e| format!("the {} machine: {}", name, e))?;
+
+        Ok(Self { name, code, tm })
+    }
+
+    /// The code of the machine with the arguments written on its tape
+    fn code_with(&self, args: &[u32]) -> Result<(String, String), String> {
+        let args: Vec<String> = args.iter().map(u32::to_string).collect();
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let tape = self.tm.encode_input(&args).map_err(|e| {
+            format!(
+                "the {} machine cannot write {} with the {} encoding: {}",
+                self.name,
+                args.join(","),
+                self.tm.encoding(),
+                e
+            )
+        })?;
+
+        Ok((runner::with_input(self.code, &tape), tape))
+    }
+
+    fn run(&self, args: &[u32], max_steps: usize) -> Result<RunOutcome, String> {
+        let (code, tape) = self.code_with(args)?;
+        let (mut tm, _warnings) = Simulator::new(&code)
+            .map_err(|e| format!("the {} machine with the tape {}: {}", self.name, tape, e))?;
+
+        Ok(runner::run(&mut tm, max_steps))
+    }
+
+    /// Runs the machine again, writing down the configurations it goes through
+    fn trace(&self, args: &[u32], outcome: RunOutcome) -> Result<Run, String> {
+        let (code, tape) = self.code_with(args)?;
+        let (mut tm, _warnings) =
+            Simulator::new(&code).map_err(|e| format!("the {} machine: {}", self.name, e))?;
+
+        let mut trace = vec![configuration(&tm)];
+        for _ in 0..outcome.steps().min(TRACE_LIMIT) {
+            tm.step();
+            trace.push(configuration(&tm));
+        }
+
+        Ok(Run {
+            tape,
+            outcome,
+            trace,
+        })
+    }
+}
+
+/// Runs both machines on every tuple of `arity` arguments taken from `inputs`, in lexicographic
+/// order, until they compute different results. Not halting within `max_steps` counts as
+/// undefined, like in the tests of the exercises.
+pub fn check(
+    first: &str,
+    second: &str,
+    inputs: RangeInclusive<u32>,
+    arity: usize,
+    max_steps: usize,
+) -> Result<Equivalence, String> {
+    if arity == 0 {
+        return Err(String::from("the machines need at least one argument"));
+    }
+
+    let first = Candidate::new("first", first)?;
+    let second = Candidate::new("second", second)?;
+
+    let mut args = vec![*inputs.start(); arity];
+    let mut checked = 0;
+
+    loop {
+        let a = first.run(&args, max_steps)?;
+        let b = second.run(&args, max_steps)?;
+        checked += 1;
+
+        if !a.same_result(&b) {
+            return Ok(Equivalence {
+                checked,
+                counterexample: Some(Counterexample {
+                    first: first.trace(&args, a)?,
+                    second: second.trace(&args, b)?,
+                    args,
+                }),
+            });
+        }
+
+        // Next tuple, counting with the last argument as the least significant digit
+        match args.iter().rposition(|a| a < inputs.end()) {
+            Some(i) => {
+                args[i] += 1;
+                for a in &mut args[i + 1..] {
+                    *a = *inputs.start();
+                }
+            }
+            None => {
+                return Ok(Equivalence {
+                    checked,
+                    counterexample: None,
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INCREMENT: &str = include_str!("../Examples/BinaryIncrement.tm");
+
+    #[test]
+    fn equivalent_machines_have_no_counterexample() {
+        let renamed = INCREMENT.replace("q1", "carry");
+        let result = check(INCREMENT, &renamed, 0..=3, 2, 1000).unwrap();
+
+        assert_eq!(result.checked, 16);
+        assert!(result.counterexample.is_none());
+    }
+
+    #[test]
+    fn finds_the_first_counterexample() {
+        // Forgets the carry, which only matters when the first argument ends in a one
+        let no_carry = INCREMENT.replace("(q1, 1, 0, L, q1)", "(q1, 1, 0, H, q2)");
+        let result = check(INCREMENT, &no_carry, 0..=3, 2, 1000).unwrap();
+        let counterexample = result.counterexample.unwrap();
+
+        assert_eq!(counterexample.args, [1, 0]);
+        assert_eq!(result.checked, 5);
+        assert_eq!(counterexample.first.tape, "1_0");
+        assert!(!counterexample
+            .first
+            .outcome
+            .same_result(&counterexample.second.outcome));
+    }
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(parse_range("0..3"), Ok(0..=2));
+        assert_eq!(parse_range("2..=4"), Ok(2..=4));
+        assert_eq!(parse_range(" 1 ..= 1 "), Ok(1..=1));
+        assert_eq!(parse_range("7"), Ok(7..=7));
+    }
+
+    #[test]
+    fn rejects_empty_and_invalid_ranges() {
+        assert!(parse_range("0..0").is_err());
+        assert!(parse_range("3..2").is_err());
+        assert!(parse_range("3..=2").is_err());
+        assert!(parse_range("..3").is_err());
+        assert!(parse_range("-1").is_err());
+    }
+}
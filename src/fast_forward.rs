@@ -0,0 +1,389 @@
+//! Runs a machine to completion without animating every step. On native platforms the machine
+//! runs on a worker thread, while on the web (which has no threads) it runs a slice of steps on
+//! every frame, so that the window never freezes.
+//! The run looks for cycles like the animated one, and stops after [`MAX_STEPS`] steps so that a
+//! machine that never halts does not keep running forever.
+//! The tests of an exercise, and the comparison of a student code with the reference solution,
+//! run in the background in the same way, with a test per frame on the web.
+
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc,
+};
+
+#[cfg(not(target_family = "wasm"))]
+use std::thread::{self, JoinHandle};
+
+use crate::{
+    machine::{Coverage, CycleDetector, Simulator},
+    runner::{self, ExerciseTest, TestComparison},
+};
+
+/// Steps run between two checks of the cancel button, or at most on every frame on the web
+const STEPS_PER_SLICE: usize = 20_000;
+
+/// Time a slice can take on the web before giving the frame back to the browser
+#[cfg(target_family = "wasm")]
+const SLICE_MILLISECONDS: f64 = 8.0;
+
+/// Steps between two readings of the clock on the web
+#[cfg(target_family = "wasm")]
+const CLOCK_STEPS: usize = 64;
+
+/// Steps of a run watched for cycles. Watching a step is much slower than running it, so cycles
+/// that start later are not found, and the run goes on until [`MAX_STEPS`].
+const WATCHED_STEPS: usize = 100_000;
+
+/// Steps after which a run stops without halting
+pub const MAX_STEPS: usize = 100_000_000;
+
+/// The machine run in the background, and what was found out while running it
+pub struct Run {
+    pub tm: Simulator,
+    /// The transitions and states used, including the ones before the run
+    pub coverage: Coverage,
+    /// The cycle detector of the animated machine, which kept watching during the run
+    pub cycle: CycleDetector,
+    /// Whether the run stopped because it reached [`MAX_STEPS`]
+    pub step_limit: bool,
+}
+
+/// A machine running to completion in the background
+pub struct FastForward {
+    steps: Arc<AtomicUsize>,
+    cancelled: Arc<AtomicBool>,
+    /// Time (in seconds, as given by egui) when the run started
+    start: f64,
+    #[cfg(not(target_family = "wasm"))]
+    worker: Option<JoinHandle<Run>>,
+    #[cfg(target_family = "wasm")]
+    run: Option<Run>,
+}
+
+impl FastForward {
+    /// Starts running the machine from its current configuration, adding the transitions and
+    /// states it uses to `coverage` and watching for cycles with `cycle`
+    pub fn start(tm: Simulator, coverage: Coverage, cycle: CycleDetector, now: f64) -> Self {
+        let steps = Arc::new(AtomicUsize::new(0));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let run = Run {
+            tm,
+            coverage,
+            cycle,
+            step_limit: false,
+        };
+
+        #[cfg(not(target_family = "wasm"))]
+        {
+            let worker = {
+                let steps = Arc::clone(&steps);
+                let cancelled = Arc::clone(&cancelled);
+                let mut run = run;
+
+                thread::spawn(move || {
+                    while !run_slice(&mut run, &steps, &cancelled) {}
+                    run
+                })
+            };
+
+            Self {
+                steps,
+                cancelled,
+                start: now,
+                worker: Some(worker),
+            }
+        }
+
+        #[cfg(target_family = "wasm")]
+        Self {
+            steps,
+            cancelled,
+            start: now,
+            run: Some(run),
+        }
+    }
+
+    /// Number of steps run so far
+    pub fn steps(&self) -> usize {
+        self.steps.load(Ordering::Relaxed)
+    }
+
+    /// Average number of steps per second since the run started
+    pub fn steps_per_second(&self, now: f64) -> f64 {
+        let elapsed = now - self.start;
+
+        if elapsed > 0.0 {
+            self.steps() as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    /// Stops the run. The machine keeps the configuration it reached, which is returned by the
+    /// next call to [`FastForward::poll`].
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns the machine and what was found out about it once the run is over, because it
+    /// halted, got into a cycle, reached [`MAX_STEPS`] or was cancelled.
+    /// On the web, this is also what runs the next slice of steps, so it must be called on every
+    /// frame.
+    pub fn poll(&mut self) -> Option<Run> {
+        #[cfg(not(target_family = "wasm"))]
+        {
+            if !self.worker.as_ref()?.is_finished() {
+                return None;
+            }
+
+            match self.worker.take()?.join() {
+                Ok(run) => Some(run),
+                // The machine would have panicked on the main thread as well
+                Err(e) => std::panic::resume_unwind(e),
+            }
+        }
+
+        #[cfg(target_family = "wasm")]
+        {
+            let run = self.run.as_mut()?;
+
+            if run_slice(run, &self.steps, &self.cancelled) {
+                self.run.take()
+            } else {
+                None
+            }
+        }
+    }
+}
+
+impl Drop for FastForward {
+    fn drop(&mut self) {
+        // A dropped run must not keep a worker thread busy forever
+        self.cancel();
+    }
+}
+
+/// The tests of an exercise running in the background, to find out if a machine solves it
+pub struct TestRun {
+    #[cfg(not(target_family = "wasm"))]
+    worker: Option<JoinHandle<bool>>,
+    /// The code, the tests that have not run yet and whether the previous ones passed
+    #[cfg(target_family = "wasm")]
+    state: Option<(String, Vec<ExerciseTest>, bool)>,
+}
+
+impl TestRun {
+    /// Starts running the tests on the code, each with [`runner::DEFAULT_STEP_LIMIT`] steps
+    pub fn start(code: String, tests: Vec<ExerciseTest>) -> Self {
+        #[cfg(not(target_family = "wasm"))]
+        {
+            let worker = thread::spawn(move || tests.iter().all(|test| passes(&code, test)));
+
+            Self {
+                worker: Some(worker),
+            }
+        }
+
+        #[cfg(target_family = "wasm")]
+        Self {
+            state: Some((code, tests, true)),
+        }
+    }
+
+    /// Returns whether the code passes every test once they have all run.
+    /// On the web, this is also what runs the next test, so it must be called on every frame.
+    pub fn poll(&mut self) -> Option<bool> {
+        #[cfg(not(target_family = "wasm"))]
+        {
+            if !self.worker.as_ref()?.is_finished() {
+                return None;
+            }
+
+            match self.worker.take()?.join() {
+                Ok(passed) => Some(passed),
+                Err(e) => std::panic::resume_unwind(e),
+            }
+        }
+
+        #[cfg(target_family = "wasm")]
+        {
+            let (code, tests, passed) = self.state.as_mut()?;
+
+            match tests.pop() {
+                Some(test) if *passed => {
+                    *passed = passes(code, &test);
+                    None
+                }
+                _ => self.state.take().map(|(_, _, passed)| passed),
+            }
+        }
+    }
+}
+
+/// The comparison of the code of a student with the reference solution running in the
+/// background, so that slow or non-halting submissions do not freeze the window
+pub struct ComparisonRun {
+    #[cfg(not(target_family = "wasm"))]
+    worker: Option<JoinHandle<Vec<TestComparison>>>,
+    /// The student code, the solution, the tests that have not run yet (last one first) and the
+    /// comparisons of the previous ones
+    #[cfg(target_family = "wasm")]
+    state: Option<(String, String, Vec<ExerciseTest>, Vec<TestComparison>)>,
+}
+
+impl ComparisonRun {
+    /// Starts running the tests on both programs, each with [`runner::DEFAULT_STEP_LIMIT`] steps
+    pub fn start(student: String, solution: String, tests: Vec<ExerciseTest>) -> Self {
+        #[cfg(not(target_family = "wasm"))]
+        {
+            let worker = thread::spawn(move || {
+                runner::compare(&student, &solution, &tests, runner::DEFAULT_STEP_LIMIT)
+            });
+
+            Self {
+                worker: Some(worker),
+            }
+        }
+
+        #[cfg(target_family = "wasm")]
+        {
+            let mut tests = tests;
+            tests.reverse();
+
+            Self {
+                state: Some((student, solution, tests, vec![])),
+            }
+        }
+    }
+
+    /// Returns the comparisons once every test has run.
+    /// On the web, this is also what runs the next test, so it must be called on every frame.
+    pub fn poll(&mut self) -> Option<Vec<TestComparison>> {
+        #[cfg(not(target_family = "wasm"))]
+        {
+            if !self.worker.as_ref()?.is_finished() {
+                return None;
+            }
+
+            match self.worker.take()?.join() {
+                Ok(comparison) => Some(comparison),
+                Err(e) => std::panic::resume_unwind(e),
+            }
+        }
+
+        #[cfg(target_family = "wasm")]
+        {
+            let (student, solution, tests, comparison) = self.state.as_mut()?;
+
+            match tests.pop() {
+                Some(test) => {
+                    comparison.extend(runner::compare(
+                        student,
+                        solution,
+                        &[test],
+                        runner::DEFAULT_STEP_LIMIT,
+                    ));
+                    None
+                }
+                None => self.state.take().map(|(_, _, _, comparison)| comparison),
+            }
+        }
+    }
+}
+
+/// Returns whether the code passes the test within [`runner::DEFAULT_STEP_LIMIT`] steps
+fn passes(code: &str, test: &ExerciseTest) -> bool {
+    runner::run_test(code, test, runner::DEFAULT_STEP_LIMIT)
+        .is_ok_and(|outcome| runner::passes(test, &outcome))
+}
+
+/// Runs a slice of steps. Returns whether the run is over.
+fn run_slice(run: &mut Run, steps: &AtomicUsize, cancelled: &AtomicBool) -> bool {
+    let over = run_steps(run, steps, cancelled);
+
+    // The detector must see every step, so after the unwatched ones it starts again from the
+    // configuration the machine reached
+    if over && steps.load(Ordering::Relaxed) > WATCHED_STEPS {
+        run.cycle = CycleDetector::new(&run.tm);
+    }
+
+    over
+}
+
+fn run_steps(run: &mut Run, steps: &AtomicUsize, cancelled: &AtomicBool) -> bool {
+    let Run {
+        tm,
+        coverage,
+        cycle,
+        step_limit,
+    } = run;
+    let deadline = Deadline::start();
+
+    for i in 0..STEPS_PER_SLICE {
+        if cancelled.load(Ordering::Relaxed) || cycle.cycle().is_some() {
+            return true;
+        }
+
+        if deadline.passed(i) {
+            return false;
+        }
+
+        if stopped(tm) {
+            coverage.finish(tm);
+            return true;
+        }
+
+        let done = steps.load(Ordering::Relaxed);
+        if done >= MAX_STEPS {
+            *step_limit = true;
+            return true;
+        }
+
+        coverage.observe(tm);
+        tm.step();
+        if done < WATCHED_STEPS {
+            cycle.observe(tm);
+        }
+        steps.fetch_add(1, Ordering::Relaxed);
+    }
+
+    false
+}
+
+/// When a slice must end on the web, so that the page keeps drawing its frames even if every step
+/// is slow (e.g. while watching the cycles of a long tape). Native slices run on a worker thread,
+/// so they only end after [`STEPS_PER_SLICE`] steps.
+struct Deadline {
+    /// Time (in milliseconds) when the slice must end
+    #[cfg(target_family = "wasm")]
+    end: f64,
+}
+
+impl Deadline {
+    fn start() -> Self {
+        Self {
+            #[cfg(target_family = "wasm")]
+            end: js_sys::Date::now() + SLICE_MILLISECONDS,
+        }
+    }
+
+    /// Whether the slice must end after running `steps` steps. Reading the clock is slow too, so
+    /// it is only read every few steps.
+    fn passed(&self, steps: usize) -> bool {
+        #[cfg(target_family = "wasm")]
+        {
+            steps % CLOCK_STEPS == 0 && js_sys::Date::now() > self.end
+        }
+
+        #[cfg(not(target_family = "wasm"))]
+        {
+            let _ = steps;
+            false
+        }
+    }
+}
+
+/// Returns whether the machine cannot make any more progress
+fn stopped(tm: &Simulator) -> bool {
+    tm.halted() || tm.is_undefined() || tm.is_final() && tm.current_instruction().is_none()
+}
@@ -0,0 +1,174 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::runner::{passes, run_test, ExerciseTest, RunOutcome};
+
+/// The result of grading a single submission
+#[derive(Serialize, Debug, Clone)]
+pub struct GradeEntry {
+    pub file: String,
+    pub compiled: bool,
+    pub tests_passed: usize,
+    pub tests_total: usize,
+    /// Steps used across all the tests
+    pub steps: usize,
+    pub errors: Vec<String>,
+}
+
+/// Parses an exercise given as `chapter.exercise` (both starting at 1) into zero-based indices
+pub fn parse_exercise_index(exercise: &str) -> Option<(usize, usize)> {
+    let (chapter, exercise) = exercise.split_once('.')?;
+
+    Some((
+        chapter.trim().parse::<usize>().ok()?.checked_sub(1)?,
+        exercise.trim().parse::<usize>().ok()?.checked_sub(1)?,
+    ))
+}
+
+fn describe_expected(test: &ExerciseTest) -> String {
+    match (test.accept, &test.expected_word, test.expected) {
+        (Some(true), _, _) => String::from("accepted"),
+        (Some(false), _, _) => String::from("rejected"),
+        (None, Some(word), _) => format!("\"{}\"", word),
+        (None, None, Some(v)) => v.to_string(),
+        (None, None, None) => String::from("undefined"),
+    }
+}
+
+fn describe(outcome: &RunOutcome) -> String {
+    match outcome {
+        RunOutcome::Halted { value, .. } => value.to_string(),
+        RunOutcome::Word { word, .. } => format!("\"{}\"", word),
+        RunOutcome::Accepted { .. } => String::from("accepted"),
+        RunOutcome::Rejected { .. } => String::from("rejected"),
+        RunOutcome::Undefined { .. } | RunOutcome::StepLimit { .. } => String::from("undefined"),
+    }
+}
+
+/// Compiles and runs the code against every test
+pub fn grade_submission(
+    file: &str,
+    code: &str,
+    tests: &[ExerciseTest],
+    max_steps: usize,
+) -> GradeEntry {
+    let mut entry = GradeEntry {
+        file: String::from(file),
+        compiled: true,
+        tests_passed: 0,
+        tests_total: tests.len(),
+        steps: 0,
+        errors: vec![],
+    };
+
+    for test in tests {
+        match run_test(code, test, max_steps) {
+            Ok(outcome) => {
+                entry.steps += outcome.steps();
+
+                if passes(test, &outcome) {
+                    entry.tests_passed += 1;
+                } else if let RunOutcome::StepLimit { steps } = outcome {
+                    entry.errors.push(format!(
+                        "input {}: expected {}, did not halt after {} steps",
+                        test.input,
+                        describe_expected(test),
+                        steps
+                    ));
+                } else {
+                    entry.errors.push(format!(
+                        "input {}: expected {}, got {}",
+                        test.input,
+                        describe_expected(test),
+                        describe(&outcome)
+                    ));
+                }
+            }
+            Err(e) => {
+                entry.compiled = false;
+                entry.errors.push(format!(
+                    "line {}: {}",
+                    e.position().start.0,
+                    e.get_message_expected()
+                ));
+                // The code is the same for every test, so it will not compile for the others either
+                break;
+            }
+        }
+    }
+
+    entry
+}
+
+/// Grades every `.tm` file of the directory (or the file itself, if `path` is a file)
+pub fn grade_path(
+    path: &Path,
+    tests: &[ExerciseTest],
+    max_steps: usize,
+) -> io::Result<Vec<GradeEntry>> {
+    let mut files: Vec<PathBuf> = if path.is_dir() {
+        fs::read_dir(path)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "tm"))
+            .collect()
+    } else {
+        vec![path.to_path_buf()]
+    };
+    files.sort();
+
+    Ok(files
+        .iter()
+        .map(|file| {
+            let name = file
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            match fs::read_to_string(file) {
+                Ok(code) => grade_submission(&name, &code, tests, max_steps),
+                Err(e) => GradeEntry {
+                    file: name,
+                    compiled: false,
+                    tests_passed: 0,
+                    tests_total: tests.len(),
+                    steps: 0,
+                    errors: vec![format!("cannot read file: {}", e)],
+                },
+            }
+        })
+        .collect())
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+
+pub fn to_csv(entries: &[GradeEntry]) -> String {
+    let mut csv = String::from("file,compiled,tests_passed,tests_total,steps,errors\n");
+
+    for e in entries {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            csv_field(&e.file),
+            e.compiled,
+            e.tests_passed,
+            e.tests_total,
+            e.steps,
+            csv_field(&e.errors.join("; "))
+        ));
+    }
+
+    csv
+}
+
+pub fn to_json(entries: &[GradeEntry]) -> String {
+    serde_json::to_string_pretty(entries).unwrap_or_default()
+}
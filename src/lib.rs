@@ -1,14 +1,28 @@
 #[cfg(not(target_family = "wasm"))]
 mod config;
+pub mod convert;
+pub mod equivalence;
+mod fast_forward;
+#[cfg(not(target_family = "wasm"))]
+pub mod grader;
+pub mod machine;
+pub mod profiler;
+mod progress;
+#[cfg(not(target_family = "wasm"))]
+pub mod render;
+pub mod runner;
+pub mod space_time;
 mod turing_widget;
 mod window;
 pub mod windows;
 
-pub use turing_widget::TuringWidget;
+pub use fast_forward::{ComparisonRun, FastForward, TestRun};
+pub use turing_widget::{CellHeat, TapeOverlays, TuringWidget};
 pub use window::{Language, MyApp};
 
 #[cfg(not(target_family = "wasm"))]
 pub use config::Config;
+pub use progress::{ExerciseStatus, Progress};
 
 pub fn get_lang() -> Language {
     match sys_locale::get_locale() {
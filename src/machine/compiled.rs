@@ -0,0 +1,402 @@
+//! A compiled form of a [`Program`] for long headless runs (e.g. busy beavers), where the
+//! transitions are looked up in a dense table indexed by state and symbols, the tapes are packed
+//! into bits and no step allocates. It behaves exactly like [`Machine::run`](super::Machine::run).
+
+use super::{is_extended, Encoding, MachineOutput, Move, OneWay, Program};
+
+/// Marks the entries of the table without a transition
+const NONE: u32 = u32::MAX;
+
+/// Maximum number of entries of the transition table (states × combinations of symbols)
+const MAX_TABLE_SIZE: usize = 1 << 26;
+
+const FINAL: u8 = 1;
+const ACCEPT: u8 = 2;
+const REJECT: u8 = 4;
+
+/// A tape that stores every cell in a few bits (the index of its symbol, 0 being the blank) and
+/// grows in both directions
+#[derive(Debug, Clone)]
+struct PackedTape {
+    /// Cells at positions `0, 1, 2, ...`
+    right: Vec<u64>,
+    /// Cells at positions `-1, -2, -3, ...`
+    left: Vec<u64>,
+    head: i64,
+}
+
+impl PackedTape {
+    fn new(cells: &[u8], bits: u32) -> Self {
+        let mut tape = Self {
+            right: vec![],
+            left: vec![],
+            head: 0,
+        };
+        for (i, c) in cells.iter().enumerate() {
+            tape.set(i as i64, *c, bits);
+        }
+
+        tape
+    }
+
+    /// Returns the word and the offset of a position in the half of the tape that contains it
+    #[inline]
+    fn locate(position: i64, bits: u32) -> (bool, usize, u32) {
+        let per_word = 64 / bits as usize;
+        let (right, index) = if position >= 0 {
+            (true, position as usize)
+        } else {
+            (false, (-1 - position) as usize)
+        };
+
+        (right, index / per_word, (index % per_word) as u32 * bits)
+    }
+
+    #[inline]
+    fn get(&self, position: i64, bits: u32) -> u8 {
+        let (right, word, shift) = Self::locate(position, bits);
+        let half = if right { &self.right } else { &self.left };
+
+        match half.get(word) {
+            Some(w) => ((w >> shift) & ((1 << bits) - 1)) as u8,
+            None => 0,
+        }
+    }
+
+    #[inline]
+    fn set(&mut self, position: i64, symbol: u8, bits: u32) {
+        let (right, word, shift) = Self::locate(position, bits);
+        let half = if right {
+            &mut self.right
+        } else {
+            &mut self.left
+        };
+
+        if word >= half.len() {
+            // Blank cells outside of the tape do not need to be stored
+            if symbol == 0 {
+                return;
+            }
+            half.resize((word + 1).next_power_of_two(), 0);
+        }
+
+        let mask = ((1u64 << bits) - 1) << shift;
+        half[word] = (half[word] & !mask) | ((symbol as u64) << shift);
+    }
+
+    /// The first and last positions that could hold a symbol other than the blank
+    fn bounds(&self, bits: u32) -> (i64, i64) {
+        let per_word = (64 / bits) as i64;
+
+        (
+            -(self.left.len() as i64) * per_word,
+            self.right.len() as i64 * per_word - 1,
+        )
+    }
+}
+
+/// A deterministic program compiled for speed, together with its current configuration
+#[derive(Debug, Clone)]
+pub struct CompiledMachine {
+    states: Vec<String>,
+    /// The symbols by index, the blank being the first one
+    symbols: Vec<char>,
+    /// Bits used by every cell of the tapes
+    bits: u32,
+    /// Number of combinations of symbols that the heads can read
+    combinations: usize,
+    /// The transition (by index) for every state and combination of symbols, or [`NONE`]
+    table: Vec<u32>,
+    /// Whether each state is final, accept or reject
+    kinds: Vec<u8>,
+    /// Symbols written by each transition, one per tape
+    writes: Vec<u8>,
+    /// Movements of each transition, one per tape
+    moves: Vec<i8>,
+    /// Next state of each transition
+    next: Vec<u32>,
+    /// Whether each transition only halts the machine: it stays in the same state and cells
+    halts: Vec<bool>,
+    acceptor: bool,
+    one_way: Option<OneWay>,
+    encoding: Option<Encoding>,
+
+    tapes: Vec<PackedTape>,
+    state: u32,
+    previous_state: u32,
+    steps: usize,
+    fell_off: bool,
+}
+
+impl CompiledMachine {
+    /// Compiles the program. Nondeterministic programs cannot be compiled, since they have
+    /// several transitions for the same state and symbols.
+    pub fn new(program: &Program) -> Result<Self, String> {
+        if program.nondeterministic {
+            return Err(String::from(
+                "nondeterministic machines cannot be compiled, explore them instead",
+            ));
+        }
+
+        let mut symbols = vec![program.blank];
+        symbols.extend(program.alphabet.iter().filter(|c| **c != program.blank));
+        if symbols.len() > 256 {
+            return Err(format!(
+                "the alphabet has {} symbols, but at most 256 can be compiled",
+                symbols.len()
+            ));
+        }
+        let bits = match symbols.len() {
+            0..=2 => 1,
+            3..=4 => 2,
+            5..=16 => 4,
+            _ => 8,
+        };
+        let symbol = |c: char| symbols.iter().position(|s| *s == c).unwrap_or(0) as u8;
+
+        let mut states: Vec<String> = vec![program.initial_state.clone()];
+        for s in program
+            .transitions
+            .iter()
+            .flat_map(|t| [&t.from, &t.to])
+            .chain(&program.final_states)
+            .chain(&program.accept_states)
+            .chain(&program.reject_states)
+        {
+            if !states.contains(s) {
+                states.push(s.clone());
+            }
+        }
+        let state = |s: &str| states.iter().position(|n| n == s).unwrap_or(0) as u32;
+
+        let tape_count = program.tape_count();
+        let combinations = symbols
+            .len()
+            .checked_pow(tape_count as u32)
+            .filter(|c| c.saturating_mul(states.len()) <= MAX_TABLE_SIZE)
+            .ok_or_else(|| {
+                format!(
+                    "the machine is too large to be compiled ({} states, {} symbols, {} tapes)",
+                    states.len(),
+                    symbols.len(),
+                    tape_count
+                )
+            })?;
+
+        let mut table = vec![NONE; states.len() * combinations];
+        let mut writes = vec![];
+        let mut moves = vec![];
+        let mut next = vec![];
+        let mut halts = vec![];
+        for (i, t) in program.transitions.iter().enumerate() {
+            let read = t
+                .read
+                .iter()
+                .rev()
+                .fold(0, |c, s| c * symbols.len() + symbol(*s) as usize);
+            let entry = &mut table[state(&t.from) as usize * combinations + read];
+            if *entry == NONE {
+                *entry = i as u32;
+            }
+
+            writes.extend(t.write.iter().map(|s| symbol(*s)));
+            moves.extend(t.movement.iter().map(|m| match m {
+                Move::Left => -1,
+                Move::Right => 1,
+                Move::Halt => 0,
+            }));
+            next.push(state(&t.to));
+            halts.push(
+                t.movement.iter().all(|m| *m == Move::Halt) && t.read == t.write && t.from == t.to,
+            );
+        }
+
+        let kinds = states
+            .iter()
+            .map(|s| {
+                let mut kind = 0;
+                if program.is_final(s) {
+                    kind |= FINAL;
+                }
+                if program.accept_states.contains(s) {
+                    kind |= ACCEPT;
+                }
+                if program.reject_states.contains(s) {
+                    kind |= REJECT;
+                }
+                kind
+            })
+            .collect();
+
+        let tapes = program
+            .tapes
+            .iter()
+            .map(|t| PackedTape::new(&t.iter().map(|c| symbol(*c)).collect::<Vec<u8>>(), bits))
+            .collect();
+
+        Ok(Self {
+            states,
+            bits,
+            combinations,
+            table,
+            kinds,
+            writes,
+            moves,
+            next,
+            halts,
+            acceptor: program.is_acceptor(),
+            one_way: program.one_way,
+            // Binary machines compute the number of ones on the tape, like `turing_lib`
+            encoding: program
+                .encoding
+                .or_else(|| (!is_extended(&program.code)).then_some(Encoding::Unary)),
+            symbols,
+            tapes,
+            state: 0,
+            previous_state: NONE,
+            steps: 0,
+            fell_off: false,
+        })
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn state(&self) -> &str {
+        &self.states[self.state as usize]
+    }
+
+    /// Returns whether a head tried to move past the left end of a one-way tape that does not
+    /// allow it
+    pub fn fell_off(&self) -> bool {
+        self.fell_off
+    }
+
+    /// The transition (by index) for the current state and symbols, or [`NONE`]
+    #[inline]
+    fn current(&self) -> u32 {
+        let n = self.symbols.len();
+        let read = self
+            .tapes
+            .iter()
+            .rev()
+            .fold(0, |c, t| c * n + t.get(t.head, self.bits) as usize);
+
+        self.table[self.state as usize * self.combinations + read]
+    }
+
+    /// Returns whether the machine must stop before applying the given transition, with the same
+    /// rules as [`Machine::halted`](super::Machine::halted) and [`Machine::step`](super::Machine::step)
+    #[inline]
+    fn stops(&self, transition: u32) -> bool {
+        let kind = self.kinds[self.state as usize];
+
+        if self.fell_off || transition == NONE || self.acceptor && kind & (ACCEPT | REJECT) != 0 {
+            return true;
+        }
+
+        kind & FINAL != 0 && self.halts[transition as usize] && self.previous_state == self.state
+    }
+
+    /// Runs at most `max_steps` steps, or until the machine halts. Returns whether it halted.
+    pub fn run(&mut self, max_steps: usize) -> bool {
+        let k = self.tapes.len();
+        let limit = self.steps.saturating_add(max_steps);
+
+        while self.steps < limit {
+            let transition = self.current();
+            if self.stops(transition) {
+                return true;
+            }
+            let t = transition as usize;
+
+            let moves = &self.moves[t * k..(t + 1) * k];
+            if self.one_way == Some(OneWay::Error)
+                && self
+                    .tapes
+                    .iter()
+                    .zip(moves)
+                    .any(|(tape, m)| tape.head == 0 && *m < 0)
+            {
+                self.fell_off = true;
+                return true;
+            }
+
+            for (i, tape) in self.tapes.iter_mut().enumerate() {
+                tape.set(tape.head, self.writes[t * k + i], self.bits);
+                tape.head += moves[i] as i64;
+                if tape.head < 0 && self.one_way.is_some() {
+                    tape.head = 0;
+                }
+            }
+
+            self.previous_state = self.state;
+            self.state = self.next[t];
+            self.steps += 1;
+        }
+
+        self.halted()
+    }
+
+    /// Returns whether the machine cannot make any more progress
+    pub fn halted(&self) -> bool {
+        self.stops(self.current())
+    }
+
+    /// Position of the head of a tape, measured from the first cell of the initial tape
+    pub fn head(&self, tape: usize) -> i64 {
+        self.tapes[tape].head
+    }
+
+    /// The symbol at a position of a tape (the blank outside of the used tape)
+    pub fn symbol(&self, tape: usize, position: i64) -> char {
+        self.symbols[self.tapes[tape].get(position, self.bits) as usize]
+    }
+
+    /// Returns the contents of a tape without the blanks at both ends
+    pub fn tape_word(&self, tape: usize) -> String {
+        let tape = &self.tapes[tape];
+        let (start, end) = tape.bounds(self.bits);
+        let cells: Vec<u8> = (start..=end).map(|p| tape.get(p, self.bits)).collect();
+
+        match (
+            cells.iter().position(|c| *c != 0),
+            cells.iter().rposition(|c| *c != 0),
+        ) {
+            (Some(start), Some(end)) => cells[start..=end]
+                .iter()
+                .map(|c| self.symbols[*c as usize])
+                .collect(),
+            _ => String::new(),
+        }
+    }
+
+    /// Returns the contents of every tape without the blanks at both ends
+    pub fn words(&self) -> Vec<String> {
+        (0..self.tapes.len()).map(|k| self.tape_word(k)).collect()
+    }
+
+    /// The result computed so far, like [`Simulator::tape_value`](super::Simulator::tape_value)
+    pub fn output(&self) -> MachineOutput {
+        let kind = self.kinds[self.state as usize];
+
+        if self.acceptor {
+            if !self.fell_off && kind & ACCEPT != 0 {
+                return MachineOutput::Accepted(self.steps);
+            } else if self.fell_off || kind & REJECT != 0 || self.current() == NONE {
+                return MachineOutput::Rejected(self.steps);
+            }
+        }
+
+        if kind & FINAL != 0 {
+            let word = self.tape_word(0);
+            match self.encoding.and_then(|e| e.decode(&word)) {
+                Some(value) => MachineOutput::Value((self.steps, value)),
+                None => MachineOutput::Word((self.steps, word)),
+            }
+        } else {
+            MachineOutput::Undefined(self.steps)
+        }
+    }
+}
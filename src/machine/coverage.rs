@@ -0,0 +1,167 @@
+//! How often every transition and state of a machine is used, either in a single run or added up
+//! over several runs (e.g. the tests of an exercise), to find the parts of the code that are never
+//! exercised.
+
+use std::collections::HashMap;
+
+use super::{find_line, Simulator};
+
+/// How many times a transition was applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransitionUsage {
+    pub instruction: String,
+    /// Line of the code where it is written (starting at 1), if it is known
+    pub line: Option<usize>,
+    pub count: usize,
+}
+
+/// How many times a state was visited: the steps taken from it, plus the runs that stopped in it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateUsage {
+    pub state: String,
+    pub count: usize,
+}
+
+/// The usage of every transition and state of a machine
+#[derive(Debug, Clone, Default)]
+pub struct Coverage {
+    /// Every transition of the code, in the order they are written
+    pub transitions: Vec<TransitionUsage>,
+    /// Every state of the code, in the order they first appear
+    pub states: Vec<StateUsage>,
+    /// Number of runs that were observed until they stopped
+    pub runs: usize,
+    /// Index in `transitions` of the instructions of binary machines, by state and symbol read
+    binary: HashMap<(String, bool), usize>,
+    /// Index in `states` of every state
+    state_index: HashMap<String, usize>,
+}
+
+impl Coverage {
+    /// Lists the transitions and states of the machine, none of them used yet
+    pub fn new(tm: &Simulator) -> Self {
+        let mut coverage = Self::default();
+        coverage.add_state(tm.current_state());
+
+        match tm {
+            Simulator::Binary(m) => {
+                let mut instructions: Vec<_> = m.instructions.values().collect();
+                instructions.sort_by_key(|i| (i.from_state.clone(), i.from_value));
+
+                let mut transitions: Vec<_> = instructions
+                    .into_iter()
+                    .map(|i| {
+                        let instruction = i.to_string();
+                        let line = find_line(tm.code(), &instruction);
+                        ((i.from_state.clone(), i.from_value), instruction, line, i)
+                    })
+                    .collect();
+                // Instructions of the composed libraries have no line, so they go last
+                transitions.sort_by_key(|t| t.2.unwrap_or(usize::MAX));
+
+                for (key, instruction, line, i) in transitions {
+                    coverage.add_state(&i.from_state);
+                    coverage.add_state(&i.to_state);
+                    coverage.binary.insert(key, coverage.transitions.len());
+                    coverage.transitions.push(TransitionUsage {
+                        instruction,
+                        line,
+                        count: 0,
+                    });
+                }
+
+                for state in &m.final_states {
+                    coverage.add_state(state);
+                }
+            }
+            Simulator::Extended(m) => {
+                let program = m.program();
+
+                for t in &program.transitions {
+                    coverage.add_state(&t.from);
+                    coverage.add_state(&t.to);
+                    coverage.transitions.push(TransitionUsage {
+                        instruction: t.to_string(),
+                        line: Some(t.line),
+                        count: 0,
+                    });
+                }
+
+                for state in program
+                    .final_states
+                    .iter()
+                    .chain(&program.accept_states)
+                    .chain(&program.reject_states)
+                {
+                    coverage.add_state(state);
+                }
+            }
+        }
+
+        coverage
+    }
+
+    fn add_state(&mut self, state: &str) {
+        if !self.state_index.contains_key(state) {
+            self.state_index
+                .insert(state.to_string(), self.states.len());
+            self.states.push(StateUsage {
+                state: state.to_string(),
+                count: 0,
+            });
+        }
+    }
+
+    fn visit(&mut self, state: &str) {
+        if let Some(i) = self.state_index.get(state) {
+            self.states[*i].count += 1;
+        }
+    }
+
+    /// Counts the current state and the transition that the next step applies. Must be called
+    /// before every step of a machine that has not stopped.
+    pub fn observe(&mut self, tm: &Simulator) {
+        self.visit(tm.current_state());
+
+        let index = match tm {
+            Simulator::Binary(m) => m
+                .get_current_instruction()
+                .and_then(|i| self.binary.get(&(i.from_state, i.from_value)).copied()),
+            Simulator::Extended(m) => m.next_index(),
+        };
+
+        if let Some(t) = index.and_then(|i| self.transitions.get_mut(i)) {
+            t.count += 1;
+        }
+    }
+
+    /// Counts the state where a run stopped. Must be called once at the end of every run.
+    pub fn finish(&mut self, tm: &Simulator) {
+        self.visit(tm.current_state());
+        self.runs += 1;
+    }
+
+    /// Number of transitions applied at least once
+    pub fn used_transitions(&self) -> usize {
+        self.transitions.iter().filter(|t| t.count > 0).count()
+    }
+
+    /// Number of states visited at least once
+    pub fn used_states(&self) -> usize {
+        self.states.iter().filter(|s| s.count > 0).count()
+    }
+
+    /// The lines of the code (starting at 1) of the transitions that were never applied
+    pub fn unused_lines(&self) -> Vec<usize> {
+        let mut lines: Vec<usize> = self
+            .transitions
+            .iter()
+            .filter(|t| t.count == 0)
+            .filter_map(|t| t.line)
+            .collect();
+        lines.sort_unstable();
+        lines.dedup();
+
+        lines
+    }
+}
@@ -0,0 +1,506 @@
+//! Exact detection of machines that never halt, by watching the configurations they go through.
+//!
+//! Two kinds of cycles are found:
+//! - A configuration (state, head positions and contents of the tapes) that repeats. Since the
+//!   machine is deterministic, it will go through the same configurations forever.
+//! - A translated cycle, where the machine drifts across the blank part of the tape. If the
+//!   machine is twice in the same state with only blanks on one side of the head, and the cells
+//!   it visited in between are the same (relative to the head), it will keep repeating that
+//!   stretch of the run, moved a few cells further every time. For example, a machine that writes
+//!   `1` and moves right forever. These are only looked for in single-tape machines.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
+};
+
+use super::Simulator;
+
+/// Configurations remembered (by their hash) to find repeats. Cycles that start after this many
+/// steps are only found if they are translated.
+const MAX_CONFIGURATIONS: usize = 100_000;
+
+/// Steps remembered to explain the cycles. Longer cycles are only explained in part.
+const MAX_TRACE: usize = 100_000;
+
+/// Moments at the edge of the tape remembered to find translated cycles
+const MAX_EDGES: usize = 256;
+
+/// The configuration of a machine, with the positions measured from the cell where each head
+/// started, so that they do not change when a tape grows to the left
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Configuration {
+    pub state: String,
+    pub tapes: Vec<TapeSnapshot>,
+}
+
+/// The contents of a tape without the blanks at both ends
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TapeSnapshot {
+    /// Position of the first symbol of `cells`
+    pub start: i64,
+    pub cells: Vec<char>,
+    /// Position of the head
+    pub head: i64,
+}
+
+impl TapeSnapshot {
+    /// Returns the symbol at the given position (blank outside of the contents)
+    pub fn get(&self, position: i64, blank: char) -> char {
+        usize::try_from(position - self.start)
+            .ok()
+            .and_then(|i| self.cells.get(i))
+            .copied()
+            .unwrap_or(blank)
+    }
+
+    /// The contents around the head, from `from` to `to` (both included)
+    pub fn window(&self, from: i64, to: i64, blank: char) -> String {
+        (from..=to).map(|p| self.get(p, blank)).collect()
+    }
+}
+
+/// A proof that a machine never halts
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cycle {
+    /// The configuration of the step `start` was reached again at the step `end`
+    Repeat {
+        start: usize,
+        end: usize,
+        configuration: Configuration,
+    },
+    /// The machine was in the same state at the steps `start` and `end`, with only blanks on one
+    /// side of the head and the same cells on the other side, so it repeats the run between them
+    /// forever, moving `shift` cells every time
+    Translated {
+        start: usize,
+        end: usize,
+        shift: i64,
+        state: String,
+        /// The cells read between `start` and `end`, as they were at `start`
+        cells: String,
+    },
+}
+
+impl Cycle {
+    /// The step where the cycle starts
+    pub fn start(&self) -> usize {
+        match self {
+            Cycle::Repeat { start, .. } | Cycle::Translated { start, .. } => *start,
+        }
+    }
+
+    /// The step where the machine went back to the start of the cycle
+    pub fn end(&self) -> usize {
+        match self {
+            Cycle::Repeat { end, .. } | Cycle::Translated { end, .. } => *end,
+        }
+    }
+
+    /// Number of steps of every repetition
+    pub fn period(&self) -> usize {
+        self.end() - self.start()
+    }
+}
+
+/// A transition applied by the machine, as written in the code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleTransition {
+    pub instruction: String,
+    /// Line of the code where it is written (starting at 1), if it is known
+    pub line: Option<usize>,
+}
+
+/// A cycle together with what the machine does in every repetition, to explain it
+#[derive(Debug, Clone, PartialEq)]
+pub struct CycleReport {
+    pub cycle: Cycle,
+    /// The states the machine goes through, in the order they are first reached
+    pub states: Vec<String>,
+    /// The transitions the machine applies, in the order they are first applied
+    pub transitions: Vec<CycleTransition>,
+    /// The configurations at the start and at the end of the cycle
+    pub first: Configuration,
+    pub last: Configuration,
+    /// Whether the cycle is too long to remember all of its steps, so that only the states and
+    /// transitions of its last steps are known
+    pub partial: bool,
+}
+
+/// What the machine did in a step
+#[derive(Debug, Clone)]
+struct Visit {
+    step: usize,
+    state: String,
+    /// The transition applied from this step to the next one
+    transition: Option<CycleTransition>,
+}
+
+/// A moment where the head had only blanks on one side
+#[derive(Debug, Clone)]
+struct Edge {
+    step: usize,
+    state: String,
+    tape: TapeSnapshot,
+    /// Whether the blanks were on the right of the head (otherwise, on the left)
+    right: bool,
+    /// The farthest the head has gone to the other side since then
+    reach: i64,
+}
+
+/// Watches a machine step by step until it finds a cycle
+#[derive(Debug, Clone)]
+pub struct CycleDetector {
+    blank: char,
+    step: usize,
+    /// Position of the first cell of each tape, which decreases when the tape grows to the left
+    origins: Vec<i64>,
+    /// The position and length of each tape in the previous step
+    previous: Vec<(usize, usize)>,
+    /// The hash of every configuration remembered, with the first step where it was reached
+    seen: HashMap<u64, usize>,
+    /// A configuration whose hash was reached before, with the step where it was reached again and
+    /// the step where it must be reached once more if it is really in a cycle
+    candidate: Option<(Configuration, usize, usize)>,
+    edges: VecDeque<Edge>,
+    trace: VecDeque<Visit>,
+    /// The line of every instruction applied, since binary machines look for it in the code
+    lines: HashMap<String, Option<usize>>,
+    report: Option<CycleReport>,
+}
+
+impl CycleDetector {
+    /// Starts watching the machine from its current configuration
+    pub fn new(tm: &Simulator) -> Self {
+        let count = tm.tape_count();
+        let mut detector = Self {
+            blank: tm.blank(),
+            step: tm.steps(),
+            origins: vec![0; count],
+            previous: (0..count)
+                .map(|k| (tm.tape_position(k), tm.tape_len(k)))
+                .collect(),
+            seen: HashMap::new(),
+            candidate: None,
+            edges: VecDeque::new(),
+            trace: VecDeque::new(),
+            lines: HashMap::new(),
+            report: None,
+        };
+        detector.record(tm);
+
+        detector
+    }
+
+    /// The cycle found so far, if any
+    pub fn cycle(&self) -> Option<&Cycle> {
+        self.report.as_ref().map(|r| &r.cycle)
+    }
+
+    /// The cycle found so far with the states and transitions it goes through, if any
+    pub fn report(&self) -> Option<&CycleReport> {
+        self.report.as_ref()
+    }
+
+    /// The blank of the machine, to read the snapshots of its tapes
+    pub fn blank(&self) -> char {
+        self.blank
+    }
+
+    /// Must be called after every step of the machine. Returns the cycle once it is found.
+    /// A machine that halted (or could not move) is not in a cycle, even if its last step did not
+    /// change anything.
+    pub fn observe(&mut self, tm: &Simulator) -> Option<&Cycle> {
+        // A machine that can still move made the step, so the steps are counted here instead of
+        // asking the machine, which is slow for binary machines
+        if self.report.is_none() && !tm.halted() && !tm.is_undefined() {
+            self.step += 1;
+
+            for k in 0..tm.tape_count() {
+                let (position, len) = (tm.tape_position(k), tm.tape_len(k));
+                let (previous_position, previous_len) = self.previous[k];
+
+                // When a tape grows to the left, its cells move to the right
+                if position == 0 && previous_position == 0 && len > previous_len {
+                    self.origins[k] -= (len - previous_len) as i64;
+                }
+                self.previous[k] = (position, len);
+            }
+
+            self.record(tm);
+        }
+
+        self.cycle()
+    }
+
+    fn snapshot(&self, tm: &Simulator, tape: usize) -> TapeSnapshot {
+        let len = tm.tape_len(tape);
+        let origin = self.origins[tape];
+        let head = origin + tm.tape_position(tape) as i64;
+        let used = |i: &usize| tm.symbol(tape, *i) != self.blank;
+
+        match ((0..len).find(used), (0..len).rfind(used)) {
+            (Some(start), Some(end)) => TapeSnapshot {
+                start: origin + start as i64,
+                cells: (start..=end).map(|i| tm.symbol(tape, i)).collect(),
+                head,
+            },
+            _ => TapeSnapshot {
+                start: 0,
+                cells: vec![],
+                head,
+            },
+        }
+    }
+
+    /// Looks for a cycle that ends in the current configuration, and remembers it
+    fn record(&mut self, tm: &Simulator) {
+        let configuration = Configuration {
+            state: tm.current_state().to_string(),
+            tapes: (0..tm.tape_count()).map(|k| self.snapshot(tm, k)).collect(),
+        };
+
+        // Only the hashes of the configurations are remembered, so a configuration whose hash was
+        // seen before is a repeat only if the machine reaches it again after as many steps
+        if let Some((candidate, start, end)) = &self.candidate {
+            if self.step >= *end {
+                if *candidate == configuration {
+                    let cycle = Cycle::Repeat {
+                        start: *start,
+                        end: self.step,
+                        configuration: configuration.clone(),
+                    };
+                    self.report = Some(self.explain(cycle, configuration.clone(), configuration));
+                    return;
+                }
+                self.candidate = None;
+            }
+        }
+
+        let hash = fingerprint(&configuration);
+        if self.candidate.is_none() {
+            if let Some(start) = self.seen.get(&hash) {
+                let period = self.step - start;
+                self.candidate = Some((configuration.clone(), self.step, self.step + period));
+            }
+        }
+
+        if configuration.tapes.len() == 1 {
+            if let Some((cycle, first)) = self.translated(&configuration) {
+                self.report = Some(self.explain(cycle, first, configuration));
+                return;
+            }
+        }
+
+        if self.trace.len() == MAX_TRACE {
+            self.trace.pop_front();
+        }
+        let transition = tm.current_instruction().map(|instruction| {
+            let line = *self
+                .lines
+                .entry(instruction.clone())
+                .or_insert_with(|| tm.current_line());
+
+            CycleTransition { instruction, line }
+        });
+        self.trace.push_back(Visit {
+            step: self.step,
+            state: configuration.state.clone(),
+            transition,
+        });
+
+        if self.seen.len() < MAX_CONFIGURATIONS {
+            self.seen.entry(hash).or_insert(self.step);
+        }
+    }
+
+    /// Collects the states and transitions of the steps of the cycle that are in the trace
+    fn explain(&self, cycle: Cycle, first: Configuration, last: Configuration) -> CycleReport {
+        let mut states: Vec<String> = vec![];
+        let mut transitions: Vec<CycleTransition> = vec![];
+
+        for visit in self.trace.iter().filter(|v| v.step >= cycle.start()) {
+            if !states.contains(&visit.state) {
+                states.push(visit.state.clone());
+            }
+            if let Some(t) = &visit.transition {
+                if !transitions.contains(t) {
+                    transitions.push(t.clone());
+                }
+            }
+        }
+
+        CycleReport {
+            partial: self.trace.front().is_some_and(|v| v.step > cycle.start()),
+            cycle,
+            states,
+            transitions,
+            first,
+            last,
+        }
+    }
+
+    /// Looks for a translated cycle that ends in the current configuration of a single-tape
+    /// machine, and remembers the configuration if the head is at an edge of the tape. Returns the
+    /// cycle and the configuration where it started.
+    fn translated(&mut self, configuration: &Configuration) -> Option<(Cycle, Configuration)> {
+        let tape = &configuration.tapes[0];
+        let head = tape.head;
+
+        for edge in self.edges.iter_mut() {
+            edge.reach = if edge.right {
+                edge.reach.min(head)
+            } else {
+                edge.reach.max(head)
+            };
+        }
+
+        let last = tape.start + tape.cells.len() as i64 - 1;
+        let sides = [
+            (true, tape.cells.is_empty() || head >= last),
+            (false, tape.cells.is_empty() || head <= tape.start),
+        ];
+
+        for (right, at_edge) in sides {
+            if !at_edge {
+                continue;
+            }
+
+            for edge in self.edges.iter().filter(|e| e.right == right) {
+                // The head must move into the blanks, so that the cells that enter the window
+                // are blanks too
+                let shift = head - edge.tape.head;
+                let outwards = if right { shift > 0 } else { shift < 0 };
+                if !outwards || edge.state != configuration.state {
+                    continue;
+                }
+
+                // The cells between the head and the farthest point it reached, at both moments
+                let (before, now) = if right {
+                    (
+                        edge.tape.window(edge.reach, edge.tape.head, self.blank),
+                        tape.window(edge.reach + shift, head, self.blank),
+                    )
+                } else {
+                    (
+                        edge.tape.window(edge.tape.head, edge.reach, self.blank),
+                        tape.window(head, edge.reach + shift, self.blank),
+                    )
+                };
+
+                if before == now {
+                    let cycle = Cycle::Translated {
+                        start: edge.step,
+                        end: self.step,
+                        shift,
+                        state: configuration.state.clone(),
+                        cells: before,
+                    };
+                    let first = Configuration {
+                        state: edge.state.clone(),
+                        tapes: vec![edge.tape.clone()],
+                    };
+
+                    return Some((cycle, first));
+                }
+            }
+
+            if self.edges.len() == MAX_EDGES {
+                self.edges.pop_front();
+            }
+            self.edges.push_back(Edge {
+                step: self.step,
+                state: configuration.state.clone(),
+                tape: tape.clone(),
+                right,
+                reach: head,
+            });
+        }
+
+        None
+    }
+}
+
+/// A 64-bit hash of a configuration
+fn fingerprint(configuration: &Configuration) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    configuration.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs the machine until it halts, a cycle is found or the step limit is reached
+    fn run(code: &str) -> (Simulator, Option<Cycle>) {
+        let (mut tm, _) = Simulator::new(code).unwrap();
+        let mut detector = CycleDetector::new(&tm);
+
+        while !tm.halted() && tm.steps() < 10_000 {
+            tm.step();
+            if let Some(cycle) = detector.observe(&tm) {
+                return (tm, Some(cycle.clone()));
+            }
+        }
+
+        (tm, None)
+    }
+
+    #[test]
+    fn finds_repeats() {
+        let (_, cycle) = run("{11};
+            I = {q0};
+            F = {q2};
+            (q0, 1, 1, R, q1);
+            (q1, 1, 1, L, q0);");
+
+        match cycle {
+            Some(Cycle::Repeat { start, end, .. }) => assert_eq!(end - start, 2),
+            other => panic!("expected a repeat, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn finds_right_drifts() {
+        let (_, cycle) = run("{1};
+            I = {q0};
+            F = {q2};
+            (q0, 1, 1, R, q0);
+            (q0, 0, 1, R, q0);");
+
+        match cycle {
+            Some(Cycle::Translated { shift, .. }) => assert!(shift > 0),
+            other => panic!("expected a drift to the right, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn finds_left_drifts() {
+        let (_, cycle) = run("{1};
+            I = {q0};
+            F = {q2};
+            (q0, 1, 1, L, q0);
+            (q0, 0, 1, L, q0);");
+
+        match cycle {
+            Some(Cycle::Translated { shift, .. }) => assert!(shift < 0),
+            other => panic!("expected a drift to the left, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn ignores_machines_that_erase_towards_the_input() {
+        let (tm, cycle) = run("{1111};
+            I = {q0};
+            F = {q2};
+            (q0, 1, 1, R, q0);
+            (q0, 0, 0, L, q1);
+            (q1, 1, 0, L, q1);
+            (q1, 0, 0, H, q2);");
+
+        assert_eq!(cycle, None);
+        assert!(tm.halted());
+        assert_eq!(tm.current_state(), "q2");
+    }
+}
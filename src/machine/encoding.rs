@@ -0,0 +1,162 @@
+//! How the values of a program are written on the tape: both to build the initial tape from a
+//! list of arguments and to read the result once the machine halts.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// A number `n` is written as `n + 1` ones, and the result is the number of ones on the tape
+    Unary,
+    /// Numbers in base 2
+    Binary,
+    /// Numbers in base 10, one digit per cell
+    Decimal,
+    /// Words are written and read as they are
+    Raw,
+}
+
+pub const ENCODINGS: [Encoding; 4] = [
+    Encoding::Unary,
+    Encoding::Binary,
+    Encoding::Decimal,
+    Encoding::Raw,
+];
+
+impl Encoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Unary => "unary",
+            Encoding::Binary => "binary",
+            Encoding::Decimal => "decimal",
+            Encoding::Raw => "raw",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ENCODINGS.into_iter().find(|e| e.name() == name.trim())
+    }
+
+    /// Symbols that must be in the alphabet to use the encoding
+    pub fn symbols(&self) -> &'static [char] {
+        match self {
+            Encoding::Unary => &['1'],
+            Encoding::Binary => &['0', '1'],
+            Encoding::Decimal => &['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'],
+            Encoding::Raw => &[],
+        }
+    }
+
+    /// Writes the arguments (e.g. `["5", "3"]`) on a tape, separated by `separator`
+    pub fn encode(&self, args: &[&str], separator: char) -> Result<String, String> {
+        let values = args
+            .iter()
+            .map(|arg| {
+                let arg = arg.trim();
+                if *self == Encoding::Raw {
+                    return Ok(arg.to_string());
+                }
+
+                let n = arg
+                    .parse::<u32>()
+                    .map_err(|_| format!("`{}` is not a natural number", arg))?;
+
+                Ok(match self {
+                    Encoding::Unary => "1".repeat(n as usize + 1),
+                    Encoding::Binary => format!("{:b}", n),
+                    _ => n.to_string(),
+                })
+            })
+            .collect::<Result<Vec<String>, String>>()?;
+
+        Ok(values.join(&separator.to_string()))
+    }
+
+    /// Reads the number written on the tape, or `None` if the encoding does not represent numbers
+    /// or the word is not a valid number
+    pub fn decode(&self, word: &str) -> Option<u32> {
+        match self {
+            Encoding::Unary => Some(word.chars().filter(|c| *c == '1').count() as u32),
+            Encoding::Binary => u32::from_str_radix(word, 2).ok(),
+            Encoding::Decimal => word.parse().ok(),
+            Encoding::Raw => None,
+        }
+    }
+
+    /// Reads every value written on the tape, separated by `separator`. Values that cannot be
+    /// decoded are returned as they are.
+    pub fn decode_all(&self, word: &str, separator: char) -> Vec<String> {
+        word.split(separator)
+            .filter(|w| !w.is_empty())
+            .map(|w| match self.decode(w) {
+                Some(n) => n.to_string(),
+                None => w.to_string(),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unary_writes_one_more_one() {
+        assert_eq!(Encoding::Unary.encode(&["0"], '_').unwrap(), "1");
+        assert_eq!(Encoding::Unary.encode(&["3"], '_').unwrap(), "1111");
+    }
+
+    #[test]
+    fn encodes_several_arguments() {
+        assert_eq!(
+            Encoding::Unary.encode(&["2", " 0 ", "1"], '0').unwrap(),
+            "11101011"
+        );
+        assert_eq!(Encoding::Binary.encode(&["0", "5"], '_').unwrap(), "0_101");
+        assert_eq!(Encoding::Decimal.encode(&["10", "7"], '#').unwrap(), "10#7");
+        assert_eq!(Encoding::Raw.encode(&["ab", "ba"], '_').unwrap(), "ab_ba");
+    }
+
+    #[test]
+    fn rejects_arguments_that_are_not_natural_numbers() {
+        assert!(Encoding::Unary.encode(&["-1"], '_').is_err());
+        assert!(Encoding::Binary.encode(&["2", "x"], '_').is_err());
+        assert!(Encoding::Decimal.encode(&[""], '_').is_err());
+    }
+
+    #[test]
+    fn decodes_the_output() {
+        // The result of a unary machine is the number of ones, so an empty tape is a zero
+        assert_eq!(Encoding::Unary.decode("111"), Some(3));
+        assert_eq!(Encoding::Unary.decode(""), Some(0));
+        assert_eq!(Encoding::Binary.decode("101"), Some(5));
+        assert_eq!(Encoding::Binary.decode("0"), Some(0));
+        assert_eq!(Encoding::Binary.decode("12"), None);
+        assert_eq!(Encoding::Decimal.decode("042"), Some(42));
+        assert_eq!(Encoding::Decimal.decode("4a"), None);
+        assert_eq!(Encoding::Raw.decode("1"), None);
+    }
+
+    #[test]
+    fn decodes_every_value() {
+        assert_eq!(
+            Encoding::Unary.decode_all("11_1__111", '_'),
+            ["2", "1", "3"]
+        );
+        assert_eq!(Encoding::Binary.decode_all("10_x", '_'), ["2", "x"]);
+        assert_eq!(Encoding::Raw.decode_all("ab_b", '_'), ["ab", "b"]);
+    }
+
+    #[test]
+    fn round_trips_binary_and_decimal() {
+        for encoding in [Encoding::Binary, Encoding::Decimal] {
+            let word = encoding.encode(&["0", "9", "1234"], '_').unwrap();
+            assert_eq!(encoding.decode_all(&word, '_'), ["0", "9", "1234"]);
+        }
+    }
+}
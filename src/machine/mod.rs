@@ -0,0 +1,504 @@
+//! Machines that go beyond what `turing_lib` can express. Programs that only use the binary
+//! alphabet keep running on `turing_lib`, while programs that use the extended syntax (e.g.
+//! `alphabet = {a, b, #}; blank = _;`) run on the simulator of this module.
+//! [`Simulator`] hides which of the two is being used.
+
+mod compiled;
+mod coverage;
+mod cycle;
+mod encoding;
+mod nondeterministic;
+mod parser;
+mod runtime;
+mod single_tape;
+
+use std::fmt;
+
+use turing_lib::{CompilerError, CompilerWarning, Library, TuringMachine, TuringOutput};
+
+pub use compiled::CompiledMachine;
+pub use coverage::{Coverage, StateUsage, TransitionUsage};
+pub use cycle::{Configuration, Cycle, CycleDetector, CycleReport, CycleTransition, TapeSnapshot};
+pub use encoding::{Encoding, ENCODINGS};
+pub use nondeterministic::{
+    BranchStatus, ComputationTree, Node, DEFAULT_MAX_DEPTH, DEFAULT_MAX_NODES,
+};
+pub use parser::{
+    is_extended, is_state, is_symbol, Move, OneWay, ParseError, Program, Transition, DEFAULT_BLANK,
+    MAX_TAPES,
+};
+pub use runtime::{Machine, Tape, TapeEdit, Verdict};
+
+/// The part of the code where a compilation error was found, as `(line, column)` pairs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorSpan {
+    pub start: (usize, usize),
+    pub end: Option<(usize, usize)>,
+}
+
+#[derive(Debug, Clone)]
+pub enum CompileError {
+    Binary(CompilerError),
+    Extended(ParseError),
+}
+
+impl CompileError {
+    /// The line of code that contains the error
+    pub fn code(&self) -> String {
+        match self {
+            CompileError::Binary(e) => e.code().to_string(),
+            CompileError::Extended(e) => e.code.clone(),
+        }
+    }
+
+    pub fn position(&self) -> ErrorSpan {
+        match self {
+            CompileError::Binary(e) => {
+                let position = e.position();
+
+                ErrorSpan {
+                    start: position.start,
+                    end: position.end,
+                }
+            }
+            CompileError::Extended(e) => ErrorSpan {
+                start: (e.line, e.columns.0),
+                end: Some((e.line, e.columns.1)),
+            },
+        }
+    }
+
+    pub fn get_message_expected(&self) -> String {
+        match self {
+            CompileError::Binary(e) => e.get_message_expected(),
+            CompileError::Extended(e) => e.message.clone(),
+        }
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::Binary(e) => write!(
+                f,
+                "line {}:{}: {}\n\t{}",
+                e.position().start.0,
+                e.position().start.1,
+                e.get_message_expected(),
+                e.code()
+            ),
+            CompileError::Extended(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<CompilerError> for CompileError {
+    fn from(e: CompilerError) -> Self {
+        CompileError::Binary(e)
+    }
+}
+
+impl From<ParseError> for CompileError {
+    fn from(e: ParseError) -> Self {
+        CompileError::Extended(e)
+    }
+}
+
+/// Returns the line of the code (starting at 1) where an instruction is written. `turing_lib`
+/// does not keep the lines, so the instruction is looked for in the code, ignoring whitespace.
+fn find_line(code: &str, instruction: &str) -> Option<usize> {
+    let strip = |s: &str| -> String { s.chars().filter(|c| !c.is_whitespace()).collect() };
+    let instruction = strip(instruction);
+    let instruction = instruction.trim_end_matches(';');
+
+    code.lines()
+        .position(|l| strip(l).contains(instruction))
+        .map(|i| i + 1)
+}
+
+/// The result computed by a machine
+#[derive(Debug, Clone, PartialEq)]
+pub enum MachineOutput {
+    /// The machine is not in a final state (after the given number of steps)
+    Undefined(usize),
+    /// The machine computed this number (e.g. the ones on the tape of a binary machine)
+    Value((usize, u32)),
+    /// A machine with its own alphabet left this word on the tape, which is not a number in
+    /// its encoding
+    Word((usize, String)),
+    /// An acceptor accepted its input (after the given number of steps)
+    Accepted(usize),
+    /// An acceptor rejected its input (after the given number of steps)
+    Rejected(usize),
+}
+
+impl From<TuringOutput> for MachineOutput {
+    fn from(output: TuringOutput) -> Self {
+        match output {
+            TuringOutput::Undefined(steps) => MachineOutput::Undefined(steps),
+            TuringOutput::Defined((steps, value)) => MachineOutput::Value((steps, value)),
+        }
+    }
+}
+
+impl fmt::Display for MachineOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MachineOutput::Undefined(_) => write!(f, "Undefined"),
+            MachineOutput::Value((_, value)) => write!(f, "{}", value),
+            MachineOutput::Word((_, word)) => write!(f, "{}", word),
+            MachineOutput::Accepted(_) => write!(f, "ACCEPTED"),
+            MachineOutput::Rejected(_) => write!(f, "REJECTED"),
+        }
+    }
+}
+
+/// A machine running either on `turing_lib` or on the extended simulator
+#[derive(Debug, Clone)]
+pub enum Simulator {
+    Binary(TuringMachine),
+    Extended(Machine),
+}
+
+impl Simulator {
+    /// Compiles the code, using the extended simulator if the code needs it
+    pub fn new(code: &str) -> Result<(Self, Vec<CompilerWarning>), CompileError> {
+        if is_extended(code) {
+            Ok((
+                Simulator::Extended(Machine::new(Program::parse(code)?)),
+                vec![],
+            ))
+        } else {
+            let (tm, warnings) = TuringMachine::new(code)?;
+            Ok((Simulator::Binary(tm), warnings))
+        }
+    }
+
+    pub fn step(&mut self) {
+        match self {
+            Simulator::Binary(tm) => {
+                tm.step();
+            }
+            Simulator::Extended(m) => {
+                m.step();
+            }
+        }
+    }
+
+    /// Number of tapes of the machine
+    pub fn tape_count(&self) -> usize {
+        match self {
+            Simulator::Binary(_) => 1,
+            Simulator::Extended(m) => m.tapes().len(),
+        }
+    }
+
+    /// Position of the head on the given tape
+    pub fn tape_position(&self, tape: usize) -> usize {
+        match self {
+            Simulator::Binary(tm) => tm.tape_position,
+            Simulator::Extended(m) => m.tapes()[tape].head(),
+        }
+    }
+
+    /// Number of cells of the given tape that have been used
+    pub fn tape_len(&self, tape: usize) -> usize {
+        match self {
+            Simulator::Binary(tm) => tm.tape.len(),
+            Simulator::Extended(m) => m.tapes()[tape].cells().len(),
+        }
+    }
+
+    /// Number of steps run so far
+    pub fn steps(&self) -> usize {
+        match self {
+            Simulator::Binary(tm) => match tm.tape_value() {
+                TuringOutput::Undefined(steps) | TuringOutput::Defined((steps, _)) => steps,
+            },
+            Simulator::Extended(m) => m.steps(),
+        }
+    }
+
+    /// The symbol of the blank cells
+    pub fn blank(&self) -> char {
+        match self {
+            Simulator::Binary(_) => '0',
+            Simulator::Extended(m) => m.program().blank,
+        }
+    }
+
+    /// Returns the symbol in a cell of the given tape (the blank outside of the used tape)
+    pub fn symbol(&self, tape: usize, i: usize) -> char {
+        match self {
+            Simulator::Binary(tm) => {
+                if tm.get(i).unwrap_or(false) {
+                    '1'
+                } else {
+                    '0'
+                }
+            }
+            Simulator::Extended(m) => m.get(tape, i),
+        }
+    }
+
+    /// The symbols that can be written on the tapes, including the blank
+    pub fn alphabet(&self) -> Vec<char> {
+        match self {
+            Simulator::Binary(_) => vec!['0', '1'],
+            Simulator::Extended(m) => m.program().alphabet.clone(),
+        }
+    }
+
+    /// Changes a tape by hand (see [`TapeEdit`])
+    pub fn edit_tape(&mut self, tape: usize, edit: TapeEdit) {
+        match self {
+            Simulator::Binary(tm) => {
+                edit.apply(&mut tm.tape, &mut tm.tape_position, false, |s| s == '1')
+            }
+            Simulator::Extended(m) => m.edit_tape(tape, edit),
+        }
+    }
+
+    /// The contents of every tape written as in the code (`{...};`), where the head starts on the
+    /// first cell. The blank cells at the ends are left out, except the ones between the head and
+    /// the symbols, so a blank tape is empty.
+    pub fn tape_inputs(&self) -> Vec<String> {
+        let blank = self.blank();
+
+        (0..self.tape_count())
+            .map(|k| {
+                let head = self.tape_position(k);
+                let cells: Vec<char> = (0..self.tape_len(k)).map(|i| self.symbol(k, i)).collect();
+                let start = cells.iter().position(|c| *c != blank);
+                let end = cells.iter().rposition(|c| *c != blank);
+
+                match (start, end) {
+                    (Some(start), Some(end)) => {
+                        cells[start.min(head)..=end.max(head)].iter().collect()
+                    }
+                    _ => String::new(),
+                }
+            })
+            .collect()
+    }
+
+    /// The contents of every tape, without the blanks at both ends
+    pub fn tape_contents(&self) -> Vec<String> {
+        match self {
+            Simulator::Binary(tm) => {
+                vec![tm.tape.iter().map(|b| if *b { '1' } else { '0' }).collect()]
+            }
+            Simulator::Extended(m) => m.words(),
+        }
+    }
+
+    pub fn current_state(&self) -> &str {
+        match self {
+            Simulator::Binary(tm) => &tm.current_state,
+            Simulator::Extended(m) => m.state(),
+        }
+    }
+
+    /// The instruction that will be executed in the next step, as written in the code
+    pub fn current_instruction(&self) -> Option<String> {
+        match self {
+            Simulator::Binary(tm) => tm.get_current_instruction().map(|i| i.to_string()),
+            Simulator::Extended(m) => m.current_transition().map(|t| t.to_string()),
+        }
+    }
+
+    /// The line of the code (starting at 1) where the instruction of the next step is written
+    pub fn current_line(&self) -> Option<usize> {
+        match self {
+            Simulator::Binary(_) => find_line(self.code(), &self.current_instruction()?),
+            Simulator::Extended(m) => m.current_transition().map(|t| t.line),
+        }
+    }
+
+    /// Returns whether the machine is in a final state
+    pub fn is_final(&self) -> bool {
+        match self {
+            Simulator::Binary(tm) => tm.finished(),
+            Simulator::Extended(m) => m.is_final(),
+        }
+    }
+
+    /// Returns whether the machine is in a final state and cannot make any more progress,
+    /// either because there is no instruction for the current symbol or because it just halted
+    pub fn halted(&self) -> bool {
+        match self {
+            Simulator::Binary(tm) => {
+                tm.finished()
+                    && match tm.get_current_instruction() {
+                        Some(ins) => {
+                            tm.previous_state.clone().unwrap_or_default() == tm.current_state
+                                && ins.movement == turing_lib::Movement::HALT
+                                && ins.from_value == ins.to_value
+                                && ins.from_state == ins.to_state
+                        }
+                        None => true,
+                    }
+            }
+            Simulator::Extended(m) => m.halted(),
+        }
+    }
+
+    pub fn is_undefined(&self) -> bool {
+        match self {
+            Simulator::Binary(tm) => tm.is_undefined(),
+            Simulator::Extended(m) => m.is_undefined(),
+        }
+    }
+
+    pub fn reset_frequencies(&mut self) {
+        match self {
+            Simulator::Binary(tm) => tm.reset_frequencies(),
+            Simulator::Extended(m) => m.reset_frequencies(),
+        }
+    }
+
+    /// The description of the machine (the triple comment at the top of the code)
+    pub fn description(&self) -> Option<&String> {
+        match self {
+            Simulator::Binary(tm) => tm.description.as_ref(),
+            Simulator::Extended(m) => m.program().description.as_ref(),
+        }
+    }
+
+    pub fn code(&self) -> &str {
+        match self {
+            Simulator::Binary(tm) => &tm.code,
+            Simulator::Extended(m) => &m.program().code,
+        }
+    }
+
+    /// The composed libraries. Only binary machines can use composition.
+    pub fn libraries(&self) -> &[Library] {
+        match self {
+            Simulator::Binary(tm) => &tm.composed_libs,
+            Simulator::Extended(_) => &[],
+        }
+    }
+
+    /// The program of the extended simulator, if it is the one being used
+    pub fn program(&self) -> Option<&Program> {
+        match self {
+            Simulator::Binary(_) => None,
+            Simulator::Extended(m) => Some(m.program()),
+        }
+    }
+
+    /// Returns whether the machine decides a language (it declares accept or reject states)
+    pub fn is_acceptor(&self) -> bool {
+        self.program().is_some_and(|p| p.is_acceptor())
+    }
+
+    /// Whether the tapes are bounded on the left. Binary machines always have infinite tapes.
+    pub fn one_way(&self) -> Option<OneWay> {
+        self.program().and_then(|p| p.one_way)
+    }
+
+    /// Returns whether the machine stopped because a head tried to move past the left end of a
+    /// one-way tape
+    pub fn fell_off(&self) -> bool {
+        match self {
+            Simulator::Binary(_) => false,
+            Simulator::Extended(m) => m.fell_off(),
+        }
+    }
+
+    /// Returns whether the machine declares several transitions for the same state and symbols
+    pub fn is_nondeterministic(&self) -> bool {
+        self.program().is_some_and(|p| p.nondeterministic)
+    }
+
+    /// Makes the next steps follow a branch of the computation tree (see [`ComputationTree::path`])
+    pub fn follow(&mut self, path: Vec<usize>) {
+        if let Simulator::Extended(m) = self {
+            m.follow(path);
+        }
+    }
+
+    /// Builds the code of an equivalent machine with a single tape, or `None` if it already has one
+    pub fn to_single_tape(&self) -> Option<Result<String, String>> {
+        match self {
+            Simulator::Extended(m) if m.tapes().len() > 1 => Some(m.program().to_single_tape()),
+            _ => None,
+        }
+    }
+
+    /// The result computed so far
+    pub fn tape_value(&self) -> MachineOutput {
+        match self {
+            Simulator::Binary(tm) => tm.tape_value().into(),
+            Simulator::Extended(m) => {
+                if let Some(verdict) = m.verdict() {
+                    match verdict {
+                        Verdict::Accepted => MachineOutput::Accepted(m.steps()),
+                        Verdict::Rejected => MachineOutput::Rejected(m.steps()),
+                    }
+                } else if m.is_final() {
+                    let word = m.word();
+                    match m.program().encoding.and_then(|e| e.decode(&word)) {
+                        Some(value) => MachineOutput::Value((m.steps(), value)),
+                        None => MachineOutput::Word((m.steps(), word)),
+                    }
+                } else {
+                    MachineOutput::Undefined(m.steps())
+                }
+            }
+        }
+    }
+
+    /// The values written on the (first) tape: the number of ones between zeros for binary
+    /// machines, and the words between blanks otherwise, decoded with the encoding of the program
+    pub fn values(&self) -> Vec<String> {
+        match self {
+            Simulator::Binary(tm) => tm.values().iter().map(|v| v.to_string()).collect(),
+            Simulator::Extended(m) => match m.program().encoding {
+                Some(e) => e.decode_all(&m.word(), m.program().blank),
+                None => m.values(),
+            },
+        }
+    }
+
+    /// How the arguments and the result of the machine are written on the tape. Binary machines
+    /// always use unary numbers, and machines without an encoding use raw words.
+    pub fn encoding(&self) -> Encoding {
+        match self {
+            Simulator::Binary(_) => Encoding::Unary,
+            Simulator::Extended(m) => m.program().encoding.unwrap_or(Encoding::Raw),
+        }
+    }
+
+    /// Writes the arguments (e.g. `["5", "3"]`) with the encoding of the machine, returning the
+    /// contents of the initial tape
+    pub fn encode_input(&self, args: &[&str]) -> Result<String, String> {
+        let separator = match self {
+            Simulator::Binary(_) => '0',
+            Simulator::Extended(m) => m.program().blank,
+        };
+
+        self.encoding().encode(args, separator)
+    }
+
+    /// Runs the machine until it halts or becomes undefined and returns the result
+    pub fn final_result(&mut self) -> MachineOutput {
+        match self {
+            Simulator::Binary(tm) => tm.final_result().into(),
+            Simulator::Extended(m) => {
+                m.run();
+                self.tape_value()
+            }
+        }
+    }
+}
+
+impl fmt::Display for Simulator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Simulator::Binary(tm) => write!(f, "{}", tm.to_string()),
+            Simulator::Extended(m) => write!(f, "{}", m),
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! Breadth-first exploration of the computations of a nondeterministic machine. Every
+//! configuration is a node of the tree, and its children are the configurations reached by each
+//! of the transitions that can be applied.
+
+use std::collections::VecDeque;
+
+use super::{
+    parser::Program,
+    runtime::{Machine, Verdict},
+};
+
+/// Default maximum depth (number of steps) of the exploration
+pub const DEFAULT_MAX_DEPTH: usize = 100;
+
+/// Default maximum number of nodes of the tree
+pub const DEFAULT_MAX_NODES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchStatus {
+    /// The branch continues in the children of the node
+    Expanded,
+    /// The branch halted in a final state, or an acceptor accepted
+    Accepted,
+    /// The branch got stuck in a state that is not final, fell off its tape, or an acceptor
+    /// rejected
+    Rejected,
+    /// The branch reached the maximum depth, or the exploration stopped before expanding it
+    Unexplored,
+}
+
+/// A configuration of the machine in the computation tree
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    /// Index of the transition that leads from the parent to this node
+    pub transition: Option<usize>,
+    pub depth: usize,
+    pub state: String,
+    /// The contents of the first tape
+    pub word: String,
+    pub status: BranchStatus,
+}
+
+/// The computations explored for a nondeterministic machine
+#[derive(Debug, Clone)]
+pub struct ComputationTree {
+    /// The nodes of the tree, the first one is the initial configuration
+    pub nodes: Vec<Node>,
+    /// The first accepting node found, which is also one of the closest to the root
+    pub accepted: Option<usize>,
+    /// Whether some branches were not explored completely because of the limits
+    pub truncated: bool,
+    /// Whether some branches reached the maximum depth
+    pub depth_limited: bool,
+    /// Whether some branches were not expanded because the tree reached the maximum number of
+    /// nodes
+    pub nodes_limited: bool,
+}
+
+impl ComputationTree {
+    /// Explores every branch breadth-first, until one accepts or the limits are reached
+    pub fn explore(program: &Program, max_depth: usize, max_nodes: usize) -> Self {
+        let machine = Machine::new(program.clone());
+        let mut tree = Self {
+            nodes: vec![Node {
+                parent: None,
+                children: vec![],
+                transition: None,
+                depth: 0,
+                state: machine.state().to_string(),
+                word: machine.word(),
+                status: BranchStatus::Unexplored,
+            }],
+            accepted: None,
+            truncated: false,
+            depth_limited: false,
+            nodes_limited: false,
+        };
+        let mut queue = VecDeque::from([(0, machine)]);
+
+        while let Some((id, machine)) = queue.pop_front() {
+            if machine.verdict() == Some(Verdict::Rejected) || machine.fell_off() {
+                tree.nodes[id].status = BranchStatus::Rejected;
+                continue;
+            }
+
+            if machine.halted() {
+                tree.nodes[id].status = BranchStatus::Accepted;
+                tree.accepted = Some(id);
+                break;
+            }
+
+            let choices = machine.choices();
+            if choices.is_empty() {
+                tree.nodes[id].status = BranchStatus::Rejected;
+                continue;
+            }
+
+            if tree.nodes[id].depth >= max_depth {
+                tree.truncated = true;
+                tree.depth_limited = true;
+                continue;
+            }
+
+            if tree.nodes.len() + choices.len() > max_nodes {
+                tree.truncated = true;
+                tree.nodes_limited = true;
+                continue;
+            }
+
+            tree.nodes[id].status = BranchStatus::Expanded;
+            for index in choices.iter().copied() {
+                let mut child = machine.clone();
+                child.apply(index);
+
+                let child_id = tree.nodes.len();
+                tree.nodes.push(Node {
+                    parent: Some(id),
+                    children: vec![],
+                    transition: Some(index),
+                    depth: tree.nodes[id].depth + 1,
+                    state: child.state().to_string(),
+                    word: child.word(),
+                    status: BranchStatus::Unexplored,
+                });
+                tree.nodes[id].children.push(child_id);
+                queue.push_back((child_id, child));
+            }
+        }
+
+        // The branches left in the queue were not explored because one of them accepted
+        if tree.accepted.is_some() && !queue.is_empty() {
+            tree.truncated = true;
+        }
+
+        tree
+    }
+
+    /// Returns the transitions (by index) that lead from the root to the given node
+    pub fn path(&self, node: usize) -> Vec<usize> {
+        let mut path = vec![];
+        let mut current = Some(node);
+
+        while let Some(id) = current {
+            if let Some(t) = self.nodes[id].transition {
+                path.push(t);
+            }
+            current = self.nodes[id].parent;
+        }
+
+        path.reverse();
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::with_input;
+
+    /// Accepts the words that contain `aba`, guessing where it starts
+    const CODE: &str = include_str!("../../Examples/Nondeterministic.tm");
+
+    fn explore(input: &str, max_depth: usize, max_nodes: usize) -> ComputationTree {
+        let program = Program::parse(&with_input(CODE, input)).unwrap();
+        ComputationTree::explore(&program, max_depth, max_nodes)
+    }
+
+    #[test]
+    fn finds_the_accepting_branch() {
+        let tree = explore("babbaba", DEFAULT_MAX_DEPTH, DEFAULT_MAX_NODES);
+        let accepted = tree.accepted.expect("a branch accepts");
+
+        assert_eq!(tree.nodes[accepted].status, BranchStatus::Accepted);
+        assert_eq!(tree.nodes[accepted].state, "q3");
+        assert_eq!(tree.nodes[accepted].depth, 7);
+        // Keep looking until the `a` at position 4, then check `aba` from there
+        assert_eq!(tree.path(accepted), vec![1, 0, 1, 1, 2, 3, 4]);
+        assert!(!tree.depth_limited);
+        assert!(!tree.nodes_limited);
+    }
+
+    #[test]
+    fn rejects_when_every_branch_rejects() {
+        let tree = explore("abba", DEFAULT_MAX_DEPTH, DEFAULT_MAX_NODES);
+
+        assert_eq!(tree.accepted, None);
+        assert!(!tree.truncated);
+        assert!(tree
+            .nodes
+            .iter()
+            .all(|n| matches!(n.status, BranchStatus::Expanded | BranchStatus::Rejected)));
+    }
+
+    #[test]
+    fn stops_at_the_maximum_depth() {
+        let tree = explore("babbaba", 3, DEFAULT_MAX_NODES);
+
+        assert_eq!(tree.accepted, None);
+        assert!(tree.truncated);
+        assert!(tree.depth_limited);
+        assert!(!tree.nodes_limited);
+        assert!(tree.nodes.iter().all(|n| n.depth <= 3));
+    }
+
+    #[test]
+    fn stops_at_the_maximum_number_of_nodes() {
+        let tree = explore("babbaba", DEFAULT_MAX_DEPTH, 3);
+
+        assert_eq!(tree.accepted, None);
+        assert!(tree.truncated);
+        assert!(tree.nodes_limited);
+        assert!(!tree.depth_limited);
+        assert!(tree.nodes.len() <= 3);
+    }
+}
@@ -0,0 +1,749 @@
+use std::{collections::HashMap, fmt};
+
+use super::{Encoding, ENCODINGS};
+
+/// Directives that are only understood by the extended simulator. A program that uses any of
+/// them cannot be compiled by `turing_lib`.
+const EXTENDED_DIRECTIVES: [&str; 8] = [
+    "alphabet",
+    "blank",
+    "tapes",
+    "nondeterministic",
+    "accept",
+    "reject",
+    "encoding",
+    "one_way",
+];
+
+/// The symbol used for blank cells when the program declares an alphabet but no blank
+pub const DEFAULT_BLANK: char = '_';
+
+/// Maximum number of tapes of a machine
+pub const MAX_TAPES: usize = 8;
+
+/// Characters that separate the parts of a statement and thus cannot be tape symbols
+const RESERVED: [char; 8] = [',', ';', '(', ')', '{', '}', '=', '/'];
+
+/// What happens when a head moves left from the first cell of a tape that is bounded on the left
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OneWay {
+    /// The head stays in the first cell
+    Stay,
+    /// The machine stops with an error
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Move {
+    Left,
+    Right,
+    Halt,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Move::Left => write!(f, "L"),
+            Move::Right => write!(f, "R"),
+            Move::Halt => write!(f, "H"),
+        }
+    }
+}
+
+/// A transition of the machine. Each of `read`, `write` and `movement` has one element per tape.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub from: String,
+    pub read: Vec<char>,
+    pub write: Vec<char>,
+    pub movement: Vec<Move>,
+    pub to: String,
+    /// Line of the code where the transition is declared (starting at 1)
+    pub line: usize,
+}
+
+/// Writes a single element as it is, and several of them as a list: `[a, b]`
+fn write_tuple<T: fmt::Display>(f: &mut fmt::Formatter<'_>, elements: &[T]) -> fmt::Result {
+    if elements.len() == 1 {
+        return write!(f, "{}", elements[0]);
+    }
+
+    write!(f, "[")?;
+    for (i, e) in elements.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", e)?;
+    }
+    write!(f, "]")
+}
+
+impl fmt::Display for Transition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, ", self.from)?;
+        write_tuple(f, &self.read)?;
+        write!(f, ", ")?;
+        write_tuple(f, &self.write)?;
+        write!(f, ", ")?;
+        write_tuple(f, &self.movement)?;
+        write!(f, ", {})", self.to)
+    }
+}
+
+/// An error found while parsing a program with the extended syntax
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// Line of the error, starting at 1
+    pub line: usize,
+    /// Columns of the line where the error starts and ends, starting at 1
+    pub columns: (usize, usize),
+    /// The line of code that contains the error
+    pub code: String,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}:{}: {}\n\t{}",
+            self.line, self.columns.0, self.message, self.code
+        )
+    }
+}
+
+/// A program written with the extended syntax, i.e. one that declares its own alphabet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    /// The triple comment at the top of the code
+    pub description: Option<String>,
+    pub code: String,
+    /// Every symbol that can appear on the tape, including the blank
+    pub alphabet: Vec<char>,
+    pub blank: char,
+    /// The initial contents of each tape
+    pub tapes: Vec<Vec<char>>,
+    pub initial_state: String,
+    pub final_states: Vec<String>,
+    /// States that make the machine stop and accept its input. A machine that declares accept or
+    /// reject states is an acceptor: it decides whether the input belongs to a language.
+    pub accept_states: Vec<String>,
+    /// States that make the machine stop and reject its input
+    pub reject_states: Vec<String>,
+    pub transitions: Vec<Transition>,
+    /// Whether several transitions can be declared for the same state and symbols
+    pub nondeterministic: bool,
+    /// How the arguments are written on the tape and the result is read. Without it, the
+    /// result is the word left on the tape.
+    pub encoding: Option<Encoding>,
+    /// Whether the tapes are bounded on the left, and what happens when a head tries to move
+    /// past the first cell. Without it, the tapes are infinite in both directions.
+    pub one_way: Option<OneWay>,
+    /// Indices in `transitions` of the transitions for each state and symbols read,
+    /// in the order they are declared
+    index: HashMap<(String, Vec<char>), Vec<usize>>,
+}
+
+/// A statement of the program (the text up to a `;`) and where it starts
+struct Statement<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+/// Returns whether the code uses any of the directives of the extended syntax
+pub fn is_extended(code: &str) -> bool {
+    statements(code).iter().any(|s| {
+        s.text
+            .split_once('=')
+            .is_some_and(|(name, _)| EXTENDED_DIRECTIVES.contains(&name.trim()))
+    })
+}
+
+/// Splits the code into statements, skipping comments
+fn statements(code: &str) -> Vec<Statement<'_>> {
+    let mut result = vec![];
+    let mut start = None;
+    let mut in_comment = false;
+    let mut chars = code.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if in_comment {
+            in_comment = c != '\n';
+        } else if c == '/' && chars.peek().is_some_and(|(_, n)| *n == '/') {
+            in_comment = true;
+        } else if c == ';' {
+            if let Some(s) = start.take() {
+                result.push(Statement {
+                    text: &code[s..i],
+                    offset: s,
+                });
+            }
+        } else if start.is_none() && !c.is_whitespace() {
+            start = Some(i);
+        }
+    }
+
+    if let Some(s) = start {
+        result.push(Statement {
+            text: &code[s..],
+            offset: s,
+        });
+    }
+
+    result
+}
+
+/// Splits the text by commas that are not inside brackets
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(text[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(text[start..].trim());
+
+    parts
+}
+
+/// Parses either a single element or a list of them between brackets
+fn parse_tuple<T>(text: &str, parse: impl Fn(&str) -> Option<T>) -> Option<Vec<T>> {
+    match text.strip_prefix('[') {
+        Some(inner) => inner
+            .strip_suffix(']')?
+            .split(',')
+            .map(|e| parse(e.trim()))
+            .collect(),
+        None => Some(vec![parse(text)?]),
+    }
+}
+
+/// Removes the comments of a statement that spans several lines
+fn strip_comments(text: &str) -> String {
+    text.lines()
+        .map(|l| l.split("//").next().unwrap_or_default())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+impl Program {
+    /// Parses a program written with the extended syntax
+    pub fn parse(code: &str) -> Result<Self, ParseError> {
+        let error = |offset: usize, len: usize, message: String| {
+            let line = code[..offset].matches('\n').count() + 1;
+            let line_start = code[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+            let column = code[line_start..offset].chars().count() + 1;
+
+            ParseError {
+                line,
+                columns: (column, column + len.max(1)),
+                code: code.lines().nth(line - 1).unwrap_or_default().to_string(),
+                message,
+            }
+        };
+
+        let description = code
+            .lines()
+            .map(|l| l.trim())
+            .take_while(|l| l.starts_with("///"))
+            .map(|l| l.trim_start_matches('/').trim())
+            .collect::<Vec<&str>>();
+
+        let mut alphabet: Option<(usize, Vec<char>)> = None;
+        let mut blank: Option<char> = None;
+        let mut tapes: Vec<(usize, Vec<char>)> = vec![];
+        let mut tape_count: Option<usize> = None;
+        let mut nondeterministic = false;
+        let mut encoding: Option<(usize, usize, Encoding)> = None;
+        let mut one_way: Option<OneWay> = None;
+        let mut initial_state: Option<String> = None;
+        let mut final_states: Vec<String> = vec![];
+        let mut accept_states: Vec<String> = vec![];
+        let mut reject_states: Vec<String> = vec![];
+        let mut transitions: Vec<Transition> = vec![];
+        // Offset and length of the statement of each transition, to point at it in the errors
+        let mut spans: Vec<(usize, usize)> = vec![];
+
+        for statement in statements(code) {
+            let text = strip_comments(statement.text);
+            let text = text.trim();
+            let offset = statement.offset;
+            let line = code[..offset].matches('\n').count() + 1;
+
+            if let Some(inner) = text.strip_prefix('{') {
+                let inner = inner.strip_suffix('}').ok_or_else(|| {
+                    error(
+                        offset,
+                        text.len(),
+                        String::from("expected `}` closing the tape"),
+                    )
+                })?;
+
+                // Each tape statement declares the next tape
+                tapes.push((
+                    offset,
+                    inner
+                        .chars()
+                        .filter(|c| !c.is_whitespace() && *c != ',')
+                        .collect(),
+                ));
+            } else if let Some(inner) = text.strip_prefix('(') {
+                let inner = inner.strip_suffix(')').ok_or_else(|| {
+                    error(
+                        offset,
+                        text.len(),
+                        String::from("expected `)` closing the transition"),
+                    )
+                })?;
+
+                let parts: Vec<&str> = split_top_level(inner);
+                if parts.len() != 5 {
+                    return Err(error(
+                        offset,
+                        text.len(),
+                        format!(
+                            "a transition has 5 parts (state, read, write, movement, state), found {}",
+                            parts.len()
+                        ),
+                    ));
+                }
+
+                let symbols = |s: &str| -> Result<Vec<char>, ParseError> {
+                    parse_tuple(s, parse_symbol).ok_or_else(|| {
+                        error(offset, text.len(), format!("`{}` is not a valid symbol", s))
+                    })
+                };
+
+                let movement = parse_tuple(parts[3], |m| match m {
+                    "L" => Some(Move::Left),
+                    "R" => Some(Move::Right),
+                    "H" => Some(Move::Halt),
+                    _ => None,
+                })
+                .ok_or_else(|| {
+                    error(
+                        offset,
+                        text.len(),
+                        format!("unknown movement `{}`, expected L, R or H", parts[3]),
+                    )
+                })?;
+
+                for state in [parts[0], parts[4]] {
+                    if !is_state(state) {
+                        return Err(error(
+                            offset,
+                            text.len(),
+                            format!("`{}` is not a valid state name", state),
+                        ));
+                    }
+                }
+
+                transitions.push(Transition {
+                    from: parts[0].to_string(),
+                    read: symbols(parts[1])?,
+                    write: symbols(parts[2])?,
+                    movement,
+                    to: parts[4].to_string(),
+                    line,
+                });
+                spans.push((offset, text.len()));
+            } else if let Some((name, value)) = text.split_once('=') {
+                let value = value.trim();
+
+                match name.trim() {
+                    "alphabet" => {
+                        let symbols = parse_set(value).ok_or_else(|| {
+                            error(
+                                offset,
+                                text.len(),
+                                String::from("expected a set of symbols, e.g. `{a, b, #}`"),
+                            )
+                        })?;
+
+                        alphabet = Some((
+                            offset,
+                            symbols
+                                .iter()
+                                .map(|s| {
+                                    parse_symbol(s).ok_or_else(|| {
+                                        error(
+                                            offset,
+                                            text.len(),
+                                            format!("`{}` is not a valid symbol", s),
+                                        )
+                                    })
+                                })
+                                .collect::<Result<Vec<char>, ParseError>>()?,
+                        ));
+                    }
+                    "blank" => {
+                        blank = Some(parse_symbol(value).ok_or_else(|| {
+                            error(
+                                offset,
+                                text.len(),
+                                format!("`{}` is not a valid symbol", value),
+                            )
+                        })?);
+                    }
+                    "I" => {
+                        let states = parse_set(value).unwrap_or_default();
+                        if states.len() != 1 || !is_state(states[0]) {
+                            return Err(error(
+                                offset,
+                                text.len(),
+                                String::from("expected a single initial state, e.g. `I = {q0};`"),
+                            ));
+                        }
+
+                        initial_state = Some(states[0].to_string());
+                    }
+                    "F" => {
+                        let states = parse_set(value).ok_or_else(|| {
+                            error(
+                                offset,
+                                text.len(),
+                                String::from("expected a set of final states, e.g. `F = {q2};`"),
+                            )
+                        })?;
+
+                        final_states = states.iter().map(|s| s.to_string()).collect();
+                    }
+                    directive @ ("accept" | "reject") => {
+                        let states = parse_set(value)
+                            .filter(|states| states.iter().all(|s| is_state(s)))
+                            .ok_or_else(|| {
+                                error(
+                                    offset,
+                                    text.len(),
+                                    format!(
+                                        "expected a set of states, e.g. `{} = {{q2}};`",
+                                        directive
+                                    ),
+                                )
+                            })?
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect();
+
+                        if directive == "accept" {
+                            accept_states = states;
+                        } else {
+                            reject_states = states;
+                        }
+                    }
+                    "tapes" => {
+                        let count = value
+                            .parse::<usize>()
+                            .ok()
+                            .filter(|n| (1..=MAX_TAPES).contains(n))
+                            .ok_or_else(|| {
+                                error(
+                                    offset,
+                                    text.len(),
+                                    format!(
+                                        "expected a number of tapes between 1 and {}",
+                                        MAX_TAPES
+                                    ),
+                                )
+                            })?;
+
+                        tape_count = Some(count);
+                    }
+                    "nondeterministic" => {
+                        nondeterministic = match value {
+                            "true" => true,
+                            "false" => false,
+                            _ => {
+                                return Err(error(
+                                    offset,
+                                    text.len(),
+                                    String::from("expected `true` or `false`"),
+                                ))
+                            }
+                        };
+                    }
+                    "encoding" => {
+                        let e = Encoding::from_name(value).ok_or_else(|| {
+                            error(
+                                offset,
+                                text.len(),
+                                format!(
+                                    "unknown encoding `{}`, expected one of: {}",
+                                    value,
+                                    ENCODINGS.map(|e| e.name()).join(", ")
+                                ),
+                            )
+                        })?;
+
+                        encoding = Some((offset, text.len(), e));
+                    }
+                    "one_way" => {
+                        one_way = Some(match value {
+                            "stay" => OneWay::Stay,
+                            "error" => OneWay::Error,
+                            _ => {
+                                return Err(error(
+                                    offset,
+                                    text.len(),
+                                    String::from("expected `stay` or `error`"),
+                                ))
+                            }
+                        });
+                    }
+                    "compose" => {
+                        return Err(error(
+                            offset,
+                            text.len(),
+                            String::from(
+                                "composition is only available for single-tape binary machines",
+                            ),
+                        ))
+                    }
+                    other => {
+                        return Err(error(
+                            offset,
+                            other.len(),
+                            format!("unknown directive `{}`", other),
+                        ))
+                    }
+                }
+            } else {
+                return Err(error(
+                    offset,
+                    text.len(),
+                    String::from("expected a tape, a directive or a transition"),
+                ));
+            }
+        }
+
+        // Without an alphabet, the machine works over the binary alphabet like `turing_lib`
+        let (alphabet, blank) = match alphabet {
+            Some((_, mut alphabet)) => {
+                let blank = blank.unwrap_or(DEFAULT_BLANK);
+                if !alphabet.contains(&blank) {
+                    alphabet.push(blank);
+                }
+
+                (alphabet, blank)
+            }
+            None => {
+                let blank = blank.unwrap_or('0');
+                let mut alphabet = vec!['0', '1'];
+                if !alphabet.contains(&blank) {
+                    alphabet.push(blank);
+                }
+
+                (alphabet, blank)
+            }
+        };
+
+        let initial_state = initial_state.ok_or_else(|| {
+            error(
+                0,
+                0,
+                String::from("the initial state must be declared, e.g. `I = {q0};`"),
+            )
+        })?;
+
+        let tape_count = tape_count.unwrap_or(tapes.len().max(1));
+        if tapes.len() > tape_count {
+            return Err(error(
+                tapes[tape_count].0,
+                1,
+                format!(
+                    "the machine has {} tapes, but {} are declared",
+                    tape_count,
+                    tapes.len()
+                ),
+            ));
+        }
+
+        for (offset, tape) in &tapes {
+            if let Some(s) = tape.iter().find(|s| !alphabet.contains(s)) {
+                return Err(error(
+                    *offset,
+                    1,
+                    format!("the symbol `{}` of the tape is not in the alphabet", s),
+                ));
+            }
+        }
+
+        if let Some((offset, len, e)) = encoding {
+            if let Some(s) = e.symbols().iter().find(|s| !alphabet.contains(s)) {
+                return Err(error(
+                    offset,
+                    len,
+                    format!(
+                        "the {} encoding needs the symbol `{}` in the alphabet",
+                        e, s
+                    ),
+                ));
+            }
+
+            if e != Encoding::Unary && e.symbols().contains(&blank) {
+                return Err(error(
+                    offset,
+                    len,
+                    format!(
+                        "the {} encoding separates the values with blanks, so the blank cannot be `{}`",
+                        e, blank
+                    ),
+                ));
+            }
+        }
+
+        if let Some(state) = accept_states.iter().find(|s| reject_states.contains(s)) {
+            return Err(error(
+                0,
+                0,
+                format!("the state {} cannot both accept and reject", state),
+            ));
+        }
+
+        let mut tapes: Vec<Vec<char>> = tapes.into_iter().map(|(_, t)| t).collect();
+        tapes.resize(tape_count, vec![]);
+
+        let mut index: HashMap<(String, Vec<char>), Vec<usize>> = HashMap::new();
+        for (i, (t, &(offset, len))) in transitions.iter().zip(&spans).enumerate() {
+            if t.read.len() != tape_count
+                || t.write.len() != tape_count
+                || t.movement.len() != tape_count
+            {
+                return Err(error(
+                    offset,
+                    len,
+                    format!(
+                        "the machine has {} tapes, so the transition must read, write and move {} symbols",
+                        tape_count, tape_count
+                    ),
+                ));
+            }
+
+            if let Some(s) = t
+                .read
+                .iter()
+                .chain(t.write.iter())
+                .find(|s| !alphabet.contains(s))
+            {
+                return Err(error(
+                    offset,
+                    len,
+                    format!("the symbol `{}` is not in the alphabet", s),
+                ));
+            }
+
+            let choices = index.entry((t.from.clone(), t.read.clone())).or_default();
+            if let (Some(previous), false) = (choices.first(), nondeterministic) {
+                return Err(error(
+                    offset,
+                    len,
+                    format!(
+                        "conflicting transitions for state {} reading {:?} (the other one is at line {}). Declare `nondeterministic = true;` to allow them",
+                        t.from, t.read, transitions[*previous].line
+                    ),
+                ));
+            }
+            choices.push(i);
+        }
+
+        Ok(Self {
+            description: if description.is_empty() {
+                None
+            } else {
+                Some(description.join("\n"))
+            },
+            code: code.to_string(),
+            alphabet,
+            blank,
+            tapes,
+            initial_state,
+            final_states,
+            accept_states,
+            reject_states,
+            transitions,
+            nondeterministic,
+            encoding: encoding.map(|(_, _, e)| e),
+            one_way,
+            index,
+        })
+    }
+
+    /// Returns the transition for the given state and symbols, if any
+    pub fn transition(&self, state: &str, symbols: &[char]) -> Option<&Transition> {
+        self.transition_index(state, symbols)
+            .map(|i| &self.transitions[i])
+    }
+
+    /// Returns the index in `transitions` of the transition for the given state and symbols.
+    /// If the machine is nondeterministic, this is the first one declared.
+    pub fn transition_index(&self, state: &str, symbols: &[char]) -> Option<usize> {
+        self.choices(state, symbols).first().copied()
+    }
+
+    /// Returns the indices in `transitions` of every transition for the given state and symbols
+    pub fn choices(&self, state: &str, symbols: &[char]) -> &[usize] {
+        self.index
+            .get(&(state.to_string(), symbols.to_vec()))
+            .map(|c| c.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Number of tapes of the machine
+    pub fn tape_count(&self) -> usize {
+        self.tapes.len()
+    }
+
+    /// Returns whether the state is final. Accept states are also final.
+    pub fn is_final(&self, state: &str) -> bool {
+        self.final_states
+            .iter()
+            .chain(self.accept_states.iter())
+            .any(|s| s == state)
+    }
+
+    /// Returns whether the machine decides a language instead of computing a result
+    pub fn is_acceptor(&self) -> bool {
+        !self.accept_states.is_empty() || !self.reject_states.is_empty()
+    }
+}
+
+/// Parses a set such as `{a, b, c}` into its (trimmed) elements
+fn parse_set(value: &str) -> Option<Vec<&str>> {
+    let inner = value.strip_prefix('{')?.strip_suffix('}')?;
+
+    Some(
+        inner
+            .split(',')
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .collect(),
+    )
+}
+
+fn parse_symbol(s: &str) -> Option<char> {
+    let mut chars = s.trim().chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if is_symbol(c) => Some(c),
+        _ => None,
+    }
+}
+
+/// Returns whether the character can be a tape symbol
+pub fn is_symbol(c: char) -> bool {
+    !c.is_whitespace() && !RESERVED.contains(&c)
+}
+
+/// Returns whether the text can be the name of a state
+pub fn is_state(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
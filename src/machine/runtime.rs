@@ -0,0 +1,418 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    sync::Arc,
+};
+
+use super::parser::{Move, OneWay, Program, Transition};
+
+/// A tape that grows in both directions as needed, or only to the right if it is one-way
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Tape {
+    cells: Vec<char>,
+    /// Position of the head in `cells`
+    head: usize,
+}
+
+impl Tape {
+    fn new(mut cells: Vec<char>, blank: char) -> Self {
+        if cells.is_empty() {
+            cells.push(blank);
+        }
+
+        Self { cells, head: 0 }
+    }
+
+    pub fn cells(&self) -> &[char] {
+        &self.cells
+    }
+
+    pub fn head(&self) -> usize {
+        self.head
+    }
+
+    pub fn read(&self) -> char {
+        self.cells[self.head]
+    }
+
+    /// Returns whether moving the head to the left would go past the left end of a one-way tape
+    fn at_left_end(&self, movement: Move) -> bool {
+        movement == Move::Left && self.head == 0
+    }
+
+    fn apply(&mut self, write: char, movement: Move, blank: char, one_way: bool) {
+        self.cells[self.head] = write;
+
+        match movement {
+            Move::Left => {
+                if self.head == 0 {
+                    if !one_way {
+                        self.cells.insert(0, blank);
+                    }
+                } else {
+                    self.head -= 1;
+                }
+            }
+            Move::Right => {
+                self.head += 1;
+                if self.head == self.cells.len() {
+                    self.cells.push(blank);
+                }
+            }
+            Move::Halt => {}
+        }
+    }
+
+    /// Returns the contents of the tape without the blanks at both ends
+    pub fn word(&self, blank: char) -> String {
+        let start = self.cells.iter().position(|c| *c != blank);
+        let end = self.cells.iter().rposition(|c| *c != blank);
+
+        match (start, end) {
+            (Some(start), Some(end)) => self.cells[start..=end].iter().collect(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// A change made by hand to a tape, e.g. to try another input before running the machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapeEdit {
+    /// Writes a symbol in a cell
+    Write(usize, char),
+    /// Inserts a blank cell before a cell
+    Insert(usize),
+    /// Removes a cell
+    Remove(usize),
+    /// Moves the head to a cell
+    MoveHead(usize),
+}
+
+impl TapeEdit {
+    /// Applies the edit to the cells of a tape and the position of its head. Cells past the end
+    /// of the tape are added as blanks, the head stays on its cell when others are inserted or
+    /// removed, and the last cell of a tape is never removed.
+    pub(super) fn apply<T: Clone>(
+        self,
+        cells: &mut Vec<T>,
+        head: &mut usize,
+        blank: T,
+        symbol: impl Fn(char) -> T,
+    ) {
+        match self {
+            TapeEdit::Write(i, s) => {
+                if i >= cells.len() {
+                    cells.resize(i + 1, blank);
+                }
+                cells[i] = symbol(s);
+            }
+            TapeEdit::Insert(i) => {
+                cells.insert(i.min(cells.len()), blank);
+                if i <= *head {
+                    *head += 1;
+                }
+            }
+            TapeEdit::Remove(i) => {
+                if i < cells.len() && cells.len() > 1 {
+                    cells.remove(i);
+                    if i < *head || *head == cells.len() {
+                        *head -= 1;
+                    }
+                }
+            }
+            TapeEdit::MoveHead(i) => {
+                if i >= cells.len() {
+                    cells.resize(i + 1, blank);
+                }
+                *head = i;
+            }
+        }
+    }
+}
+
+/// The decision of an acceptor about its input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Accepted,
+    Rejected,
+}
+
+/// A running machine of the extended syntax
+#[derive(Debug, Clone)]
+pub struct Machine {
+    program: Arc<Program>,
+    tapes: Vec<Tape>,
+    state: String,
+    previous_state: Option<String>,
+    steps: usize,
+    /// How many times each transition (by index) has been used
+    frequencies: HashMap<usize, usize>,
+    /// Transitions (by index) to take in the next steps instead of the first one declared,
+    /// used to replay a branch of a nondeterministic machine
+    path: VecDeque<usize>,
+    /// Whether a head tried to move past the left end of a one-way tape that does not allow it
+    fell_off: bool,
+}
+
+impl Machine {
+    pub fn new(program: Program) -> Self {
+        Self {
+            state: program.initial_state.clone(),
+            tapes: program
+                .tapes
+                .iter()
+                .map(|t| Tape::new(t.clone(), program.blank))
+                .collect(),
+            program: Arc::new(program),
+            previous_state: None,
+            steps: 0,
+            frequencies: HashMap::new(),
+            path: VecDeque::new(),
+            fell_off: false,
+        }
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn tapes(&self) -> &[Tape] {
+        &self.tapes
+    }
+
+    pub fn state(&self) -> &str {
+        &self.state
+    }
+
+    pub fn previous_state(&self) -> Option<&String> {
+        self.previous_state.as_ref()
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Returns the symbol at the given position of a tape (blank outside of it)
+    pub fn get(&self, tape: usize, i: usize) -> char {
+        self.tapes[tape]
+            .cells
+            .get(i)
+            .copied()
+            .unwrap_or(self.program.blank)
+    }
+
+    /// The symbols under the heads
+    pub fn read(&self) -> Vec<char> {
+        self.tapes.iter().map(|t| t.read()).collect()
+    }
+
+    /// Returns the index of the transition that will be applied in the next step, if any
+    pub(super) fn next_index(&self) -> Option<usize> {
+        let read = self.read();
+
+        match self.path.front() {
+            Some(i) if self.program.choices(&self.state, &read).contains(i) => Some(*i),
+            _ => self.program.transition_index(&self.state, &read),
+        }
+    }
+
+    /// Returns the transition that will be applied in the next step, if any
+    pub fn current_transition(&self) -> Option<&Transition> {
+        self.next_index().map(|i| &self.program.transitions[i])
+    }
+
+    /// Returns the indices of every transition that can be applied in the next step
+    pub fn choices(&self) -> &[usize] {
+        self.program.choices(&self.state, &self.read())
+    }
+
+    /// Makes the next steps take the given transitions (by index), as long as they can be applied
+    pub fn follow(&mut self, path: Vec<usize>) {
+        self.path = path.into();
+    }
+
+    /// Returns whether the machine is in a final state
+    pub fn is_final(&self) -> bool {
+        self.program.is_final(&self.state)
+    }
+
+    /// Returns whether the machine stopped because a head tried to move past the left end of a
+    /// one-way tape (see [`OneWay::Error`])
+    pub fn fell_off(&self) -> bool {
+        self.fell_off
+    }
+
+    /// The decision of an acceptor, once it reaches an accept or reject state. An acceptor that
+    /// gets stuck in any other state (or falls off its tape) rejects its input.
+    pub fn verdict(&self) -> Option<Verdict> {
+        if !self.program.is_acceptor() {
+            return None;
+        }
+
+        if self.fell_off {
+            Some(Verdict::Rejected)
+        } else if self.program.accept_states.contains(&self.state) {
+            Some(Verdict::Accepted)
+        } else if self.program.reject_states.contains(&self.state)
+            || self.current_transition().is_none()
+        {
+            Some(Verdict::Rejected)
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether the machine is in a final state and cannot make any more progress,
+    /// either because there is no transition for the current symbols or because it just halted.
+    /// Acceptors halt as soon as they reach a decision.
+    pub fn halted(&self) -> bool {
+        if self.verdict().is_some() || self.fell_off {
+            return true;
+        }
+
+        self.is_final()
+            && match self.current_transition() {
+                Some(t) => {
+                    t.movement.iter().all(|m| *m == Move::Halt)
+                        && t.read == t.write
+                        && t.from == t.to
+                        && self.previous_state.as_ref() == Some(&self.state)
+                }
+                None => true,
+            }
+    }
+
+    /// Returns whether the machine is stuck in a state that is not final, or fell off its tape
+    pub fn is_undefined(&self) -> bool {
+        self.verdict().is_none()
+            && (self.fell_off || !self.is_final() && self.current_transition().is_none())
+    }
+
+    /// Applies the transition for the current state and symbols. Returns `false` if there is none
+    /// or the machine already reached a decision.
+    pub fn step(&mut self) -> bool {
+        if self.verdict().is_some() || self.fell_off {
+            return false;
+        }
+
+        let index = match self.next_index() {
+            Some(i) => i,
+            None => return false,
+        };
+
+        if self.path.pop_front() != Some(index) {
+            // The path no longer matches the machine, so it is dropped
+            self.path.clear();
+        }
+
+        self.apply(index);
+        true
+    }
+
+    /// Applies the transition with the given index, which should be one of [`Machine::choices`].
+    /// Used to explore the branches of a nondeterministic machine.
+    ///
+    /// If a head would move past the left end of a one-way tape that does not allow it, the
+    /// transition is not applied and the machine stops (see [`Machine::fell_off`]).
+    pub fn apply(&mut self, index: usize) {
+        let transition = &self.program.transitions[index];
+
+        if self.program.one_way == Some(OneWay::Error)
+            && self
+                .tapes
+                .iter()
+                .zip(&transition.movement)
+                .any(|(tape, m)| tape.at_left_end(*m))
+        {
+            self.fell_off = true;
+            return;
+        }
+
+        for (k, tape) in self.tapes.iter_mut().enumerate() {
+            tape.apply(
+                transition.write[k],
+                transition.movement[k],
+                self.program.blank,
+                self.program.one_way.is_some(),
+            );
+        }
+
+        self.previous_state = Some(std::mem::replace(&mut self.state, transition.to.clone()));
+        self.steps += 1;
+        *self.frequencies.entry(index).or_default() += 1;
+    }
+
+    /// Runs the machine until it halts or becomes undefined
+    pub fn run(&mut self) {
+        while !self.halted() && self.step() {}
+    }
+
+    /// Returns the contents of the first tape, where the result is read from,
+    /// without the blanks at both ends
+    pub fn word(&self) -> String {
+        self.tapes[0].word(self.program.blank)
+    }
+
+    /// Returns the contents of every tape without the blanks at both ends
+    pub fn words(&self) -> Vec<String> {
+        self.tapes
+            .iter()
+            .map(|t| t.word(self.program.blank))
+            .collect()
+    }
+
+    /// Returns the words of the first tape that are separated by blanks
+    pub fn values(&self) -> Vec<String> {
+        self.word()
+            .split(self.program.blank)
+            .filter(|w| !w.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    pub fn reset_frequencies(&mut self) {
+        self.frequencies.clear();
+    }
+
+    /// Changes a tape by hand (see [`TapeEdit`])
+    pub fn edit_tape(&mut self, tape: usize, edit: TapeEdit) {
+        let blank = self.program.blank;
+        let tape = &mut self.tapes[tape];
+
+        edit.apply(&mut tape.cells, &mut tape.head, blank, |s| s);
+    }
+}
+
+impl fmt::Display for Tape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cells: Vec<String> = self
+            .cells
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                if i == self.head {
+                    format!("[{}]", c)
+                } else {
+                    c.to_string()
+                }
+            })
+            .collect();
+
+        write!(f, "{}", cells.join(" "))
+    }
+}
+
+impl fmt::Display for Machine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.tapes.len() == 1 {
+            return write!(f, "{}: {}", self.state, self.tapes[0]);
+        }
+
+        write!(f, "{}:", self.state)?;
+        for (k, tape) in self.tapes.iter().enumerate() {
+            write!(f, "\n\ttape {}: {}", k + 1, tape)?;
+        }
+
+        Ok(())
+    }
+}
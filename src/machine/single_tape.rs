@@ -0,0 +1,494 @@
+//! Conversion of a multi-tape machine into an equivalent single-tape one, using the classic
+//! construction: each cell of the single tape holds a symbol of every tape and marks where the
+//! heads are. Every step of the original machine is simulated by sweeping right to read the
+//! symbols under the heads and then sweeping left to write them and move the marks.
+
+use std::collections::{HashSet, VecDeque};
+
+use super::parser::{Move, Program};
+
+/// Maximum number of symbols of the single-tape machine
+const MAX_SYMBOLS: usize = 2048;
+
+/// Maximum number of transitions of the single-tape machine
+const MAX_TRANSITIONS: usize = 200_000;
+
+/// Characters used for the symbols of the single-tape machine
+fn symbol_chars() -> impl Iterator<Item = char> {
+    (0xC0..=0x24F)
+        .filter(|c| *c != 0xD7 && *c != 0xF7)
+        .chain((0x391..=0x3A9).filter(|c| *c != 0x3A2))
+        .chain(0x3B1..=0x3C9)
+        .chain(0x410..=0x44F)
+        .chain(0x4E00..=0x9FFF)
+        .filter_map(char::from_u32)
+}
+
+/// A cell of the single tape: the symbol of each tape (as an index in the alphabet)
+/// and which heads are on it
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Cell {
+    symbols: Vec<usize>,
+    heads: Vec<bool>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum State {
+    /// Sweeping right, collecting the symbols under the heads
+    Read(String, Vec<Option<usize>>),
+    /// Sweeping left, applying the transition to the tapes that are set in the mask
+    Write(String, Vec<usize>, Vec<bool>),
+    /// Placing the head of a tape in the next cell, then coming back
+    PlaceHead(String, Vec<usize>, Vec<bool>, usize, Move),
+    /// One cell to the left of where the leftmost head was
+    Back(String),
+    /// Looking to the right for the leftmost head
+    Seek(String),
+    /// The original machine stopped in this state
+    End(String),
+}
+
+struct Converter<'a> {
+    program: &'a Program,
+    symbols: Vec<char>,
+    k: usize,
+}
+
+impl<'a> Converter<'a> {
+    fn index(&self, symbol: char) -> usize {
+        self.program
+            .alphabet
+            .iter()
+            .position(|s| *s == symbol)
+            .unwrap_or(0)
+    }
+
+    fn encode(&self, cell: &Cell) -> char {
+        let base = 2 * self.program.alphabet.len();
+        let index = (0..self.k).rev().fold(0, |acc, j| {
+            acc * base + cell.symbols[j] * 2 + cell.heads[j] as usize
+        });
+
+        self.symbols[index]
+    }
+
+    fn cells(&self) -> Vec<Cell> {
+        let base = 2 * self.program.alphabet.len();
+
+        (0..self.symbols.len())
+            .map(|mut index| {
+                let mut cell = Cell {
+                    symbols: vec![],
+                    heads: vec![],
+                };
+
+                for _ in 0..self.k {
+                    let digit = index % base;
+                    index /= base;
+                    cell.symbols.push(digit / 2);
+                    cell.heads.push(digit % 2 == 1);
+                }
+
+                cell
+            })
+            .collect()
+    }
+
+    fn describe(&self, cell: &Cell) -> String {
+        let parts: Vec<String> = (0..self.k)
+            .map(|j| {
+                format!(
+                    "{}{}",
+                    self.program.alphabet[cell.symbols[j]],
+                    if cell.heads[j] { "^" } else { "" }
+                )
+            })
+            .collect();
+
+        format!("({})", parts.join(", "))
+    }
+
+    fn digits(symbols: impl Iterator<Item = Option<usize>>) -> String {
+        symbols
+            .map(|s| match s {
+                Some(i) => std::char::from_digit(i as u32, 36).unwrap_or('z'),
+                None => 'x',
+            })
+            .collect()
+    }
+
+    fn mask(mask: &[bool]) -> String {
+        mask.iter().map(|b| if *b { '1' } else { '0' }).collect()
+    }
+
+    fn name(&self, state: &State) -> String {
+        match state {
+            State::Read(q, partial) if partial.iter().all(|s| s.is_none()) => q.clone(),
+            State::Read(q, partial) => {
+                format!("{}_r{}", q, Self::digits(partial.iter().copied()))
+            }
+            State::Write(q, tuple, mask) => format!(
+                "{}_w{}_{}",
+                q,
+                Self::digits(tuple.iter().map(|s| Some(*s))),
+                Self::mask(mask)
+            ),
+            State::PlaceHead(q, tuple, mask, j, movement) => format!(
+                "{}_w{}_{}_{}{}",
+                q,
+                Self::digits(tuple.iter().map(|s| Some(*s))),
+                Self::mask(mask),
+                movement,
+                j
+            ),
+            State::Back(q) => format!("{}_back", q),
+            State::Seek(q) => format!("{}_seek", q),
+            State::End(q) => format!("{}_end", q),
+        }
+    }
+
+    /// Returns the transitions (read, write, movement, next state) of the given state
+    fn transitions(&self, state: &State, cells: &[Cell]) -> Vec<(Cell, Cell, Move, State)> {
+        let mut result = vec![];
+
+        for cell in cells {
+            let has_head = cell.heads.iter().any(|h| *h);
+
+            let (write, movement, next) = match state {
+                State::Read(q, partial) => {
+                    let mut partial = partial.clone();
+                    for (j, symbol) in partial.iter_mut().enumerate() {
+                        if cell.heads[j] && symbol.is_none() {
+                            *symbol = Some(cell.symbols[j]);
+                        }
+                    }
+
+                    if partial.iter().all(|s| s.is_some()) {
+                        let tuple: Vec<usize> = partial.iter().flatten().copied().collect();
+                        let read: Vec<char> =
+                            tuple.iter().map(|i| self.program.alphabet[*i]).collect();
+
+                        let next = match self.program.transition(q, &read) {
+                            // The original machine halts on a final state with a transition
+                            // that changes nothing
+                            Some(t)
+                                if self.program.is_final(q)
+                                    && t.to == *q
+                                    && t.read == t.write
+                                    && t.movement.iter().all(|m| *m == Move::Halt) =>
+                            {
+                                State::End(q.clone())
+                            }
+                            Some(_) => State::Write(q.clone(), tuple, vec![false; self.k]),
+                            None => State::End(q.clone()),
+                        };
+
+                        (cell.clone(), Move::Halt, next)
+                    } else {
+                        (cell.clone(), Move::Right, State::Read(q.clone(), partial))
+                    }
+                }
+                State::Write(q, tuple, mask) => {
+                    let read: Vec<char> = tuple.iter().map(|i| self.program.alphabet[*i]).collect();
+                    let transition = match self.program.transition(q, &read) {
+                        Some(t) => t,
+                        None => continue,
+                    };
+
+                    match (0..self.k).find(|j| cell.heads[*j] && !mask[*j]) {
+                        Some(j) => {
+                            let mut write = cell.clone();
+                            write.symbols[j] = self.index(transition.write[j]);
+
+                            let mut mask = mask.clone();
+                            mask[j] = true;
+
+                            match transition.movement[j] {
+                                Move::Halt => (
+                                    write,
+                                    Move::Halt,
+                                    State::Write(q.clone(), tuple.clone(), mask),
+                                ),
+                                movement => {
+                                    write.heads[j] = false;
+                                    (
+                                        write,
+                                        movement,
+                                        State::PlaceHead(
+                                            q.clone(),
+                                            tuple.clone(),
+                                            mask,
+                                            j,
+                                            movement,
+                                        ),
+                                    )
+                                }
+                            }
+                        }
+                        None if mask.iter().all(|m| *m) => {
+                            (cell.clone(), Move::Left, State::Back(transition.to.clone()))
+                        }
+                        None => (cell.clone(), Move::Left, state.clone()),
+                    }
+                }
+                State::PlaceHead(q, tuple, mask, j, movement) => {
+                    let mut write = cell.clone();
+                    write.heads[*j] = true;
+
+                    let back = if *movement == Move::Left {
+                        Move::Right
+                    } else {
+                        Move::Left
+                    };
+
+                    (
+                        write,
+                        back,
+                        State::Write(q.clone(), tuple.clone(), mask.clone()),
+                    )
+                }
+                State::Back(q) if has_head => (
+                    cell.clone(),
+                    Move::Halt,
+                    State::Read(q.clone(), vec![None; self.k]),
+                ),
+                State::Back(q) => (cell.clone(), Move::Right, State::Seek(q.clone())),
+                State::Seek(q) if has_head => (
+                    cell.clone(),
+                    Move::Halt,
+                    State::Read(q.clone(), vec![None; self.k]),
+                ),
+                State::Seek(_) => (cell.clone(), Move::Right, state.clone()),
+                State::End(_) => return vec![],
+            };
+
+            result.push((cell.clone(), write, movement, next));
+        }
+
+        result
+    }
+}
+
+impl Program {
+    /// Builds the code of a single-tape machine that simulates this one. The result of the
+    /// original machine can be read from the first symbol of every cell.
+    pub fn to_single_tape(&self) -> Result<String, String> {
+        if self.nondeterministic {
+            return Err(String::from(
+                "nondeterministic machines cannot be converted to a single tape",
+            ));
+        }
+
+        if self.one_way.is_some() {
+            return Err(String::from(
+                "machines with one-way tapes cannot be converted to a single tape",
+            ));
+        }
+
+        let k = self.tape_count();
+        let symbol_count = (2 * self.alphabet.len()).pow(k as u32);
+
+        if self.alphabet.len() > 36 {
+            return Err(String::from(
+                "the alphabet is too large to be converted to a single tape",
+            ));
+        }
+
+        let symbols: Vec<char> = symbol_chars().take(symbol_count).collect();
+        if symbol_count > MAX_SYMBOLS || symbols.len() < symbol_count {
+            return Err(format!(
+                "the single-tape machine would need {} symbols, but at most {} are supported",
+                symbol_count, MAX_SYMBOLS
+            ));
+        }
+
+        let converter = Converter {
+            program: self,
+            symbols,
+            k,
+        };
+        let cells = converter.cells();
+
+        // Explore the states that can be reached from the initial one
+        let initial = State::Read(self.initial_state.clone(), vec![None; k]);
+        let mut visited: HashSet<State> = HashSet::from([initial.clone()]);
+        let mut queue = VecDeque::from([initial]);
+        let mut lines = vec![];
+        let mut finals = vec![];
+
+        while let Some(state) = queue.pop_front() {
+            if let State::End(q) = &state {
+                if self.is_final(q) {
+                    finals.push(converter.name(&state));
+                }
+            }
+
+            let transitions = converter.transitions(&state, &cells);
+            if !transitions.is_empty() {
+                lines.push(String::new());
+            }
+
+            for (read, write, movement, next) in transitions {
+                lines.push(format!(
+                    "({}, {}, {}, {}, {});",
+                    converter.name(&state),
+                    converter.encode(&read),
+                    converter.encode(&write),
+                    movement,
+                    converter.name(&next)
+                ));
+
+                if visited.insert(next.clone()) {
+                    queue.push_back(next);
+                }
+            }
+
+            if lines.len() > MAX_TRANSITIONS {
+                return Err(format!(
+                    "the single-tape machine would need more than {} transitions",
+                    MAX_TRANSITIONS
+                ));
+            }
+        }
+
+        // The generated names must not clash with the states of the original machine
+        let original: HashSet<&String> = self
+            .transitions
+            .iter()
+            .flat_map(|t| [&t.from, &t.to])
+            .collect();
+        if let Some(clash) = visited
+            .iter()
+            .filter(|s| !matches!(s, State::Read(_, p) if p.iter().all(|s| s.is_none())))
+            .map(|s| converter.name(s))
+            .find(|name| original.contains(name))
+        {
+            return Err(format!(
+                "the state {} clashes with the names of the generated states, rename it",
+                clash
+            ));
+        }
+
+        let length = self.tapes.iter().map(|t| t.len()).max().unwrap_or(0).max(1);
+        let tape: String = (0..length)
+            .map(|i| {
+                converter.encode(&Cell {
+                    symbols: self
+                        .tapes
+                        .iter()
+                        .map(|t| converter.index(t.get(i).copied().unwrap_or(self.blank)))
+                        .collect(),
+                    heads: vec![i == 0; k],
+                })
+            })
+            .collect();
+
+        let blank = converter.encode(&Cell {
+            symbols: vec![converter.index(self.blank); k],
+            heads: vec![false; k],
+        });
+
+        let mut code = String::new();
+        if let Some(description) = &self.description {
+            for line in description.lines() {
+                code.push_str(&format!("/// {}\n", line));
+            }
+        }
+        code.push_str(&format!(
+            "/// Simulation on a single tape of a machine with {} tapes\n\n",
+            k
+        ));
+
+        code.push_str(&format!(
+            "// Each symbol stands for the symbols of the {} tapes in a cell, `^` marks a head:\n",
+            k
+        ));
+        for cell in &cells {
+            code.push_str(&format!(
+                "// {} = {}\n",
+                converter.encode(cell),
+                converter.describe(cell)
+            ));
+        }
+
+        let alphabet: Vec<String> = cells
+            .iter()
+            .map(|c| converter.encode(c).to_string())
+            .collect();
+        code.push_str(&format!("\nalphabet = {{{}}};\n", alphabet.join(", ")));
+        code.push_str(&format!("blank = {};\n\n", blank));
+        code.push_str(&format!("{{{}}};\n\n", tape));
+        code.push_str(&format!("I = {{{}}};\n", self.initial_state));
+        code.push_str(&format!("F = {{{}}};\n", finals.join(", ")));
+        // The states where the original machine decides keep their names, so the simulation
+        // stops as soon as it enters them
+        if !self.accept_states.is_empty() {
+            code.push_str(&format!(
+                "accept = {{{}}};\n",
+                self.accept_states.join(", ")
+            ));
+        }
+        if !self.reject_states.is_empty() {
+            code.push_str(&format!(
+                "reject = {{{}}};\n",
+                self.reject_states.join(", ")
+            ));
+        }
+        code.push_str(&lines.join("\n"));
+        code.push('\n');
+
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::machine::Machine;
+
+    /// Runs the program until it halts, returning its final state and the cells of its first tape
+    fn run(program: Program) -> (String, Vec<char>) {
+        let mut machine = Machine::new(program);
+        machine.run();
+        assert!(machine.is_final());
+
+        (
+            machine.state().to_string(),
+            machine.tapes()[0].cells().to_vec(),
+        )
+    }
+
+    #[test]
+    fn simulates_multi_tape_machines() {
+        let program = Program::parse(include_str!("../../Examples/MultiTapeReverse.tm")).unwrap();
+        let code = program.to_single_tape().unwrap();
+
+        // The comments of the code describe the symbol of the first tape in every cell
+        let first_symbol: HashMap<char, char> = code
+            .lines()
+            .filter_map(|l| {
+                let (cell, tapes) = l.strip_prefix("// ")?.split_once(" = (")?;
+                Some((cell.chars().next()?, tapes.chars().next()?))
+            })
+            .collect();
+
+        let (state, cells) = run(program.clone());
+        let (single_state, single_cells) = run(Program::parse(&code).unwrap());
+
+        let blank = program.blank;
+        let word = |cells: Vec<char>| -> String {
+            cells
+                .into_iter()
+                .collect::<String>()
+                .trim_matches(blank)
+                .to_string()
+        };
+        let single_cells = single_cells.iter().map(|c| first_symbol[c]).collect();
+
+        assert_eq!(word(cells), "bba");
+        assert_eq!(word(single_cells), "bba");
+        assert_eq!(single_state, format!("{}_end", state));
+    }
+}
@@ -3,7 +3,7 @@
 
 #[cfg(not(target_arch = "wasm32"))]
 use {
-    clap::Parser as clap_parser,
+    clap::{Parser as clap_parser, Subcommand, ValueEnum},
     log::{debug, error},
     std::{fs, io, path::PathBuf},
     turing_lib::TuringMachine,
@@ -18,9 +18,13 @@ use turing_machine::MyApp;
     author,
     version,
     about,
-    long_about = "Note: When playing, all the keybindings of mpv can be used, and `q` is reserved for exiting the program"
+    long_about = "Note: When playing, all the keybindings of mpv can be used, and `q` is reserved for exiting the program",
+    args_conflicts_with_subcommands = true
 )]
 pub struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Option: Specify a file with the instructions.
     #[clap(help = "Specify a file with instructions.")]
     file: Option<PathBuf>,
@@ -44,10 +48,305 @@ pub struct Cli {
     )]
     interactive: bool,
 
+    /// Option: --input: the arguments of the machine, written on the initial tape with its encoding.
+    /// Note: this option is only available in the CLI mode.
+    #[clap(
+        long,
+        help = "Arguments written on the initial tape with the encoding of the machine, e.g. `5,3`.\nNote: this option is only available in the CLI mode."
+    )]
+    input: Option<String>,
+
+    /// Option: --fast: run the machine with the compiled engine, reporting the steps per second.
+    /// Note: this option is only available in the CLI mode.
+    #[clap(
+        long,
+        default_value_t = false,
+        help = "Run the machine with the compiled engine, which is much faster for long runs, and report the steps per second.\nNote: this option is only available in the CLI mode."
+    )]
+    fast: bool,
+
+    /// Option: --max-steps: the steps after which the machine is considered not to halt.
+    /// Note: this option is only available in the CLI mode.
+    #[clap(
+        long,
+        help = "Steps after which the machine is stopped and considered not to halt. By default, the machine runs until it halts.\nNote: this option is only available in the CLI mode."
+    )]
+    max_steps: Option<usize>,
+
+    /// Option: --max-nodes: the configurations explored for nondeterministic machines.
+    /// Note: this option is only available in the CLI mode.
+    #[clap(
+        long,
+        default_value_t = turing_machine::machine::DEFAULT_MAX_NODES,
+        help = "Configurations explored for nondeterministic machines before giving up.\nNote: this option is only available in the CLI mode."
+    )]
+    max_nodes: usize,
+
     #[clap(flatten)]
     verbose: clap_verbosity_flag::Verbosity,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Grade student submissions against the tests of a workbook exercise
+    Grade {
+        /// The workbook that contains the exercise
+        #[clap(long)]
+        workbook: PathBuf,
+
+        /// The exercise to grade, as `chapter.exercise` (starting at 1)
+        #[clap(long)]
+        exercise: String,
+
+        /// A `.tm` file or a directory with the submissions
+        submissions: PathBuf,
+
+        /// Maximum number of steps of each test run
+        #[clap(long, default_value_t = turing_machine::runner::DEFAULT_STEP_LIMIT)]
+        max_steps: usize,
+
+        /// Format of the gradebook
+        #[clap(long, value_enum, default_value_t = GradebookFormat::Csv)]
+        format: GradebookFormat,
+
+        /// Write the gradebook to this file instead of the standard output
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Convert a machine between the `.tm` syntax and the formats of other simulators
+    Convert {
+        /// The file to convert, or the notation itself for busy beavers (e.g. `1RB1LB_1LA1RZ`)
+        input: String,
+
+        /// Format of the input, guessed from its extension by default
+        #[clap(long, value_enum)]
+        from: Option<MachineFormat>,
+
+        /// Format of the result, guessed from the extension of the output file by default
+        /// (`.tm` when writing to the standard output)
+        #[clap(long, value_enum)]
+        to: Option<MachineFormat>,
+
+        /// Write the result to this file instead of the standard output
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Run a machine in the command line, converting it from the format of another simulator
+    Run {
+        /// The file of the machine, or the notation itself for busy beavers (e.g. `1RB1LB_1LA1RZ`)
+        machine: String,
+
+        /// Format of the machine, guessed from the extension of the file by default
+        #[clap(long, value_enum)]
+        from: Option<MachineFormat>,
+
+        /// Print the machine step by step
+        #[clap(long, short, default_value_t = false)]
+        interactive: bool,
+
+        /// Arguments written on the initial tape with the encoding of the machine, e.g. `5,3`
+        #[clap(long)]
+        input: Option<String>,
+
+        /// Run the machine with the compiled engine and report the steps per second
+        #[clap(long, default_value_t = false)]
+        fast: bool,
+
+        /// Steps after which the machine is stopped and considered not to halt. By default, the
+        /// machine runs until it halts.
+        #[clap(long)]
+        max_steps: Option<usize>,
+
+        /// Configurations explored for nondeterministic machines before giving up
+        #[clap(long, default_value_t = turing_machine::machine::DEFAULT_MAX_NODES)]
+        max_nodes: usize,
+    },
+    /// Check whether two machines compute the same function on every input of a range
+    Equiv {
+        /// The first machine, e.g. the reference solution
+        first: String,
+
+        /// The second machine, e.g. a student submission
+        second: String,
+
+        /// The values of each argument, as `0..20` (20 excluded), `0..=20` or a single number
+        #[clap(long, default_value = "0..20")]
+        inputs: String,
+
+        /// Number of arguments of the machines
+        #[clap(long, default_value_t = 1)]
+        arity: usize,
+
+        /// Maximum number of steps of each run, after which the result is undefined
+        #[clap(long, default_value_t = turing_machine::runner::DEFAULT_STEP_LIMIT)]
+        max_steps: usize,
+    },
+    /// Measure the steps and cells used by a machine for a range of input sizes, as CSV, and fit
+    /// growth curves to them
+    Profile {
+        /// The machine to profile
+        machine: String,
+
+        /// The sizes of the inputs, as `0..20` (20 excluded), `0..=20` or a single number
+        #[clap(long, default_value = "0..20")]
+        sizes: String,
+
+        /// Number of arguments of the machine, all of them equal to the size
+        #[clap(long, default_value_t = 1)]
+        arity: usize,
+
+        /// Maximum number of steps of each run, after which the run is left out of the fits
+        #[clap(long, default_value_t = turing_machine::runner::DEFAULT_STEP_LIMIT)]
+        max_steps: usize,
+
+        /// Write the CSV to this file instead of the standard output
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Show how often every transition and state of a machine is used, in a single run or added
+    /// up over the tests of a workbook exercise
+    Coverage {
+        /// The machine to run
+        machine: String,
+
+        /// The workbook whose exercise tests are run, instead of a single run
+        #[clap(long, requires = "exercise")]
+        workbook: Option<PathBuf>,
+
+        /// The exercise whose tests are run, as `chapter.exercise` (starting at 1)
+        #[clap(long, requires = "workbook")]
+        exercise: Option<String>,
+
+        /// Arguments written on the initial tape with the encoding of the machine, e.g. `5,3`
+        #[clap(long, conflicts_with = "workbook")]
+        input: Option<String>,
+
+        /// Maximum number of steps of each run
+        #[clap(long, default_value_t = turing_machine::runner::DEFAULT_STEP_LIMIT)]
+        max_steps: usize,
+    },
+    /// Draw the space-time diagram of a run as a PNG: one row per step, one column per cell and
+    /// the head in red
+    Diagram {
+        /// The machine to run
+        machine: String,
+
+        /// The format of the machine, guessed from its extension by default
+        #[clap(long, value_enum)]
+        from: Option<MachineFormat>,
+
+        /// Arguments written on the initial tape with the encoding of the machine, e.g. `5,3`
+        #[clap(long)]
+        input: Option<String>,
+
+        /// Maximum number of steps to draw
+        #[clap(long, default_value_t = 1000)]
+        max_steps: usize,
+
+        /// The tape to draw (starting at 1)
+        #[clap(long, default_value_t = 1)]
+        tape: usize,
+
+        /// Size in pixels of every cell and step
+        #[clap(long, default_value_t = 4)]
+        scale: u32,
+
+        /// The PNG file to write
+        #[clap(long, short)]
+        output: PathBuf,
+    },
+    /// Render a run as an animated GIF or as numbered PNG frames, with the tapes drawn as in the
+    /// simulator, e.g. to embed it in slides
+    Render {
+        /// The machine to run
+        machine: String,
+
+        /// The format of the machine, guessed from its extension by default
+        #[clap(long, value_enum)]
+        from: Option<MachineFormat>,
+
+        /// Arguments written on the initial tape with the encoding of the machine, e.g. `5,3`
+        #[clap(long)]
+        input: Option<String>,
+
+        /// The first step drawn
+        #[clap(long, default_value_t = 0)]
+        first_step: usize,
+
+        /// The last step drawn, if the machine does not stop before
+        #[clap(long, default_value_t = 100)]
+        last_step: usize,
+
+        /// Milliseconds that every frame of the GIF is shown
+        #[clap(long, default_value_t = 500)]
+        delay: u32,
+
+        /// Size in pixels of every cell
+        #[clap(long, default_value_t = 40)]
+        cell_size: u32,
+
+        /// Do not draw the state and the next instruction below the tapes
+        #[clap(long)]
+        no_state: bool,
+
+        /// The animated GIF to write
+        #[clap(long, required_unless_present = "frames")]
+        gif: Option<PathBuf>,
+
+        /// The directory where every frame is written as a numbered PNG
+        #[clap(long)]
+        frames: Option<PathBuf>,
+    },
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum GradebookFormat {
+    Csv,
+    Json,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum MachineFormat {
+    /// The `.tm` syntax of this simulator
+    Tm,
+    /// JFLAP `.jff` files
+    Jflap,
+    /// The YAML machines of turingmachine.io
+    Yaml,
+    /// The compact notation of busy beavers, e.g. `1RB1LB_1LA1RZ`
+    #[value(name = "bb")]
+    BusyBeaver,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<MachineFormat> for turing_machine::convert::Format {
+    fn from(format: MachineFormat) -> Self {
+        match format {
+            MachineFormat::Tm => Self::Tm,
+            MachineFormat::Jflap => Self::Jflap,
+            MachineFormat::Yaml => Self::Yaml,
+            MachineFormat::BusyBeaver => Self::BusyBeaver,
+        }
+    }
+}
+
+/// Exit code of the CLI when an acceptor (or a branch of a nondeterministic machine) accepts
+#[cfg(not(target_arch = "wasm32"))]
+const EXIT_ACCEPTED: i32 = 0;
+/// Exit code of the CLI when an acceptor rejects, or no branch of a nondeterministic machine accepts
+#[cfg(not(target_arch = "wasm32"))]
+const EXIT_REJECTED: i32 = 2;
+/// Exit code of the CLI when an acceptor stops without a decision, a machine does not halt within
+/// the step limit, or the exploration of a nondeterministic machine is cut short by its limits
+#[cfg(not(target_arch = "wasm32"))]
+const EXIT_UNDECIDED: i32 = 3;
+/// Exit code of `equiv` when the machines compute different results for some input
+#[cfg(not(target_arch = "wasm32"))]
+const EXIT_DIFFERENT: i32 = 4;
+
 // when compiling to web using trunk.
 #[cfg(target_arch = "wasm32")]
 fn main() {
@@ -79,10 +378,111 @@ fn main() {
         .filter_level(args.verbose.log_level_filter())
         .init();
 
-    if args.cli {
+    if let Some(command) = args.command {
+        match command {
+            Command::Grade {
+                workbook,
+                exercise,
+                submissions,
+                max_steps,
+                format,
+                output,
+            } => grade(workbook, &exercise, submissions, max_steps, format, output),
+            Command::Convert {
+                input,
+                from,
+                to,
+                output,
+            } => convert(&input, from, to, output),
+            Command::Run {
+                machine,
+                from,
+                interactive,
+                input,
+                fast,
+                max_steps,
+                max_nodes,
+            } => run_machine_cli(
+                read_tm(&machine, from),
+                interactive,
+                input,
+                fast,
+                max_steps,
+                max_nodes,
+            ),
+            Command::Equiv {
+                first,
+                second,
+                inputs,
+                arity,
+                max_steps,
+            } => equiv(&first, &second, &inputs, arity, max_steps),
+            Command::Profile {
+                machine,
+                sizes,
+                arity,
+                max_steps,
+                output,
+            } => profile(&machine, &sizes, arity, max_steps, output),
+            Command::Coverage {
+                machine,
+                workbook,
+                exercise,
+                input,
+                max_steps,
+            } => coverage(&machine, workbook.zip(exercise), input, max_steps),
+            Command::Diagram {
+                machine,
+                from,
+                input,
+                max_steps,
+                tape,
+                scale,
+                output,
+            } => diagram(
+                &read_tm(&machine, from),
+                input,
+                max_steps,
+                tape,
+                scale,
+                &output,
+            ),
+            Command::Render {
+                machine,
+                from,
+                input,
+                first_step,
+                last_step,
+                delay,
+                cell_size,
+                no_state,
+                gif,
+                frames,
+            } => render(
+                &read_tm(&machine, from),
+                input,
+                first_step..=last_step,
+                delay,
+                turing_machine::render::Options {
+                    cell_size,
+                    state: !no_state,
+                },
+                gif,
+                frames,
+            ),
+        }
+    } else if args.cli {
         if let Some(file) = args.file {
             debug!("The machine will run in GUI mode");
-            run_machine_cli(file, args.interactive);
+            let code = fs::read_to_string(&file).expect("cannot read file");
+            run_machine_cli(
+                code,
+                args.interactive,
+                args.input,
+                args.fast,
+                args.max_steps,
+                args.max_nodes,
+            );
         } else {
             error!("No file provided, exiting...");
             std::process::exit(1);
@@ -145,39 +545,131 @@ fn run_machine_gui(file: Option<PathBuf>) {
     };
 }
 
+/// Compiles the machine and, with an input, writes it on its first tape with the encoding of
+/// the machine. Exits when the code does not compile or the input cannot be encoded.
 #[cfg(not(target_arch = "wasm32"))]
-fn run_machine_cli(file: PathBuf, interactive: bool) {
-    use turing_lib::TuringOutput;
+fn machine_with_input(code: &str, input: Option<&str>) -> turing_machine::machine::Simulator {
+    use turing_machine::{
+        machine::{CompileError, Simulator},
+        runner,
+    };
 
-    let u_code = fs::read_to_string(&file).expect("cannot read file");
-    let mut tm = match TuringMachine::new(&u_code) {
-        Ok((t, warnings)) => {
+    let compile = |code: &str| match Simulator::new(code) {
+        Ok((tm, warnings)) => {
             for w in warnings {
                 println!("\tWarning: {:?}", w);
             }
 
-            t
+            tm
         }
-        Err(e) => {
+        Err(CompileError::Binary(e)) => {
             TuringMachine::handle_error(e);
             std::process::exit(1);
         }
+        Err(e) => {
+            error!("Cannot compile the machine: {}", e);
+            std::process::exit(1);
+        }
     };
+    let tm = compile(code);
 
-    if !interactive {
-        let res = tm.final_result();
-        match res {
-            TuringOutput::Undefined(steps) => {
-                println!("After {} steps, the result is: Undefined", steps);
+    match input {
+        Some(input) => {
+            let args: Vec<&str> = input.split(',').collect();
+            match tm.encode_input(&args) {
+                Ok(tape) => compile(&runner::with_input(code, &tape)),
+                Err(e) => {
+                    error!(
+                        "Cannot write the input with the {} encoding: {}",
+                        tm.encoding(),
+                        e
+                    );
+                    std::process::exit(1);
+                }
             }
-            TuringOutput::Defined((steps, value)) => {
-                println!("After {} steps, the result is: {}", steps, value);
+        }
+        None => tm,
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn run_machine_cli(
+    code: String,
+    interactive: bool,
+    input: Option<String>,
+    fast: bool,
+    max_steps: Option<usize>,
+    max_nodes: usize,
+) {
+    use turing_machine::{
+        machine::{ComputationTree, DEFAULT_MAX_DEPTH},
+        runner::{self, RunOutcome},
+    };
+
+    let mut tm = machine_with_input(&code, input.as_deref());
+
+    if let (false, Some(program)) = (interactive, tm.program().filter(|p| p.nondeterministic)) {
+        // Every branch must stop somewhere, so the exploration has a depth limit by default
+        let max_depth = max_steps.unwrap_or(DEFAULT_MAX_DEPTH);
+        let tree = ComputationTree::explore(program, max_depth, max_nodes);
+
+        match tree.accepted {
+            Some(node) => {
+                println!(
+                    "A branch accepts after {} steps, following the transitions:",
+                    tree.nodes[node].depth
+                );
+                for t in tree.path(node) {
+                    println!("\t{}", program.transitions[t]);
+                }
+                println!("The tape contains: {}", tree.nodes[node].word);
+                std::process::exit(EXIT_ACCEPTED);
+            }
+            None if tree.truncated => {
+                let limits = match (tree.depth_limited, tree.nodes_limited) {
+                    (true, false) => format!("some branches reached --max-steps ({})", max_depth),
+                    (false, true) => format!("the tree reached --max-nodes ({})", max_nodes),
+                    _ => format!(
+                        "some branches reached --max-steps ({}) and the tree reached --max-nodes ({})",
+                        max_depth, max_nodes
+                    ),
+                };
+                println!(
+                    "No branch accepts in the {} configurations explored: {}",
+                    tree.nodes.len(),
+                    limits
+                );
+                std::process::exit(EXIT_UNDECIDED);
+            }
+            None => {
+                println!("No branch accepts");
+                std::process::exit(EXIT_REJECTED);
             }
         }
-        std::process::exit(0);
     }
 
-    println!("{}", tm.to_string());
+    let max_steps = max_steps.unwrap_or(usize::MAX);
+
+    if fast && !interactive {
+        run_compiled(tm.code(), tm.is_acceptor(), max_steps);
+    }
+
+    if !interactive {
+        if let RunOutcome::StepLimit { steps } = runner::run(&mut tm, max_steps) {
+            print_step_limit(steps);
+            std::process::exit(EXIT_UNDECIDED);
+        }
+
+        let res = tm.tape_value();
+        if tm.fell_off() {
+            println!("Error: the head fell off the left end of the tape");
+        }
+
+        print_result(&res);
+        std::process::exit(exit_code(tm.is_acceptor(), &res));
+    }
+
+    println!("{}", tm);
     let mut input = String::new();
 
     io::stdin()
@@ -186,23 +678,600 @@ fn run_machine_cli(file: PathBuf, interactive: bool) {
 
     loop {
         tm.step();
-        println!("{}", tm.to_string());
+        println!("{}", tm);
 
         io::stdin()
             .read_line(&mut input)
             .expect("error: unable to read user input");
 
-        if tm.finished() {
+        if tm.is_final() || tm.halted() {
             break;
         }
     }
 
     tm.step();
-    println!("{}", tm.to_string());
+    println!("{}", tm);
 
     io::stdin()
         .read_line(&mut input)
         .expect("error: unable to read user input");
 
+    if tm.fell_off() {
+        println!("Error: the head fell off the left end of the tape");
+    }
     println!("Result: {}", tm.tape_value());
+    std::process::exit(exit_code(tm.is_acceptor(), &tm.tape_value()));
+}
+
+/// Runs the machine with the compiled engine, which does not allocate on every step, and reports
+/// how many steps per second it ran
+#[cfg(not(target_arch = "wasm32"))]
+fn run_compiled(code: &str, acceptor: bool, max_steps: usize) {
+    use std::time::Instant;
+    use turing_machine::machine::{CompiledMachine, Program};
+
+    let mut machine = match Program::parse(code)
+        .map_err(|e| e.to_string())
+        .and_then(|p| CompiledMachine::new(&p))
+    {
+        Ok(m) => m,
+        Err(e) => {
+            println!("Error: the machine cannot be compiled: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let start = Instant::now();
+    let halted = machine.run(max_steps);
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let res = machine.output();
+    if !halted {
+        print_step_limit(machine.steps());
+    } else {
+        if machine.fell_off() {
+            println!("Error: the head fell off the left end of the tape");
+        }
+        print_result(&res);
+    }
+    println!(
+        "Ran {} steps in {:.3} s ({:.0} steps/s)",
+        machine.steps(),
+        elapsed,
+        machine.steps() as f64 / elapsed.max(f64::EPSILON)
+    );
+
+    std::process::exit(if halted {
+        exit_code(acceptor, &res)
+    } else {
+        EXIT_UNDECIDED
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn print_step_limit(steps: usize) {
+    println!(
+        "After {} steps, the machine has not halted (use --max-steps to run it longer)",
+        steps
+    );
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn print_result(res: &turing_machine::machine::MachineOutput) {
+    use turing_machine::machine::MachineOutput;
+
+    match res {
+        MachineOutput::Undefined(steps) => {
+            println!("After {} steps, the result is: Undefined", steps);
+        }
+        MachineOutput::Value((steps, value)) => {
+            println!("After {} steps, the result is: {}", steps, value);
+        }
+        MachineOutput::Word((steps, word)) => {
+            println!("After {} steps, the tape contains: {}", steps, word);
+        }
+        MachineOutput::Accepted(steps) => {
+            println!("After {} steps, the input is: ACCEPTED", steps);
+        }
+        MachineOutput::Rejected(steps) => {
+            println!("After {} steps, the input is: REJECTED", steps);
+        }
+    }
+}
+
+/// The exit code of a machine that stopped running: acceptors tell whether they accepted the
+/// input, any other machine exits successfully
+#[cfg(not(target_arch = "wasm32"))]
+fn exit_code(acceptor: bool, res: &turing_machine::machine::MachineOutput) -> i32 {
+    use turing_machine::machine::MachineOutput;
+
+    if !acceptor {
+        return 0;
+    }
+
+    match res {
+        MachineOutput::Accepted(_) => EXIT_ACCEPTED,
+        MachineOutput::Rejected(_) => EXIT_REJECTED,
+        _ => EXIT_UNDECIDED,
+    }
+}
+
+/// Reads the tests of an exercise of a workbook, given as `chapter.exercise` (starting at 1)
+#[cfg(not(target_arch = "wasm32"))]
+fn read_exercise_tests(
+    workbook: &std::path::Path,
+    exercise: &str,
+) -> Vec<turing_machine::runner::ExerciseTest> {
+    use turing_machine::{grader, windows::read_workbook};
+
+    let workbook = match read_workbook(workbook) {
+        Some(wb) => wb,
+        None => {
+            error!("Cannot load the workbook {:?}", workbook);
+            std::process::exit(1);
+        }
+    };
+
+    let exercise = match grader::parse_exercise_index(exercise)
+        .and_then(|(chapter, ex)| workbook.get(chapter)?.1.get(ex))
+    {
+        Some(ex) => ex,
+        None => {
+            error!("The exercise {} does not exist in the workbook", exercise);
+            std::process::exit(1);
+        }
+    };
+
+    if exercise.tests.is_empty() {
+        error!("The exercise \"{}\" has no tests", exercise.title);
+        std::process::exit(1);
+    }
+
+    exercise.tests.clone()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn grade(
+    workbook: PathBuf,
+    exercise: &str,
+    submissions: PathBuf,
+    max_steps: usize,
+    format: GradebookFormat,
+    output: Option<PathBuf>,
+) {
+    use turing_machine::grader;
+
+    let tests = read_exercise_tests(&workbook, exercise);
+
+    let entries = match grader::grade_path(&submissions, &tests, max_steps) {
+        Ok(e) => e,
+        Err(e) => {
+            error!("Cannot read the submissions {:?}: {}", submissions, e);
+            std::process::exit(1);
+        }
+    };
+
+    let gradebook = match format {
+        GradebookFormat::Csv => grader::to_csv(&entries),
+        GradebookFormat::Json => grader::to_json(&entries),
+    };
+
+    match output {
+        Some(file) => {
+            if let Err(e) = fs::write(&file, gradebook) {
+                error!("Cannot write the gradebook {:?}: {}", file, e);
+                std::process::exit(1);
+            }
+        }
+        None => print!("{}", gradebook),
+    }
+}
+
+/// Reads a machine given in the command line, which is a file or, for busy beavers, the notation
+/// itself. Returns its text and its format.
+#[cfg(not(target_arch = "wasm32"))]
+fn read_machine(
+    machine: &str,
+    from: Option<MachineFormat>,
+) -> (String, turing_machine::convert::Format) {
+    use turing_machine::convert::Format;
+
+    let path = PathBuf::from(machine);
+    let from = match from.map(Format::from).or_else(|| Format::from_path(&path)) {
+        Some(f) => f,
+        None => {
+            error!("Cannot guess the format of {}, use --from", machine);
+            std::process::exit(1);
+        }
+    };
+
+    if from == Format::BusyBeaver && !path.exists() {
+        return (machine.to_string(), from);
+    }
+
+    match fs::read_to_string(&path) {
+        Ok(t) => (t, from),
+        Err(e) => {
+            error!("Cannot read {}: {}", machine, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn convert(
+    input: &str,
+    from: Option<MachineFormat>,
+    to: Option<MachineFormat>,
+    output: Option<PathBuf>,
+) {
+    use turing_machine::convert::{self, Format};
+
+    let (text, from) = read_machine(input, from);
+    let to = match to.map(Format::from) {
+        Some(f) => f,
+        None => match &output {
+            Some(file) => match Format::from_path(file) {
+                Some(f) => f,
+                None => {
+                    error!("Cannot guess the format of {:?}, use --to", file);
+                    std::process::exit(1);
+                }
+            },
+            None => Format::Tm,
+        },
+    };
+
+    let result = match convert::convert(&text, from, to) {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Cannot convert {}: {}", input, e);
+            std::process::exit(1);
+        }
+    };
+
+    match output {
+        Some(file) => {
+            if let Err(e) = fs::write(&file, result) {
+                error!("Cannot write {:?}: {}", file, e);
+                std::process::exit(1);
+            }
+        }
+        None => print!("{}", result),
+    }
+}
+
+/// Reads a machine given in the command line and converts it to the `.tm` syntax
+#[cfg(not(target_arch = "wasm32"))]
+fn read_tm(machine: &str, from: Option<MachineFormat>) -> String {
+    use turing_machine::convert::{self, Format};
+
+    let (text, from) = read_machine(machine, from);
+    match convert::convert(&text, from, Format::Tm) {
+        Ok(code) => code,
+        Err(e) => {
+            error!("Cannot convert {}: {}", machine, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn equiv(first: &str, second: &str, inputs: &str, arity: usize, max_steps: usize) {
+    use turing_machine::{equivalence, runner::RunOutcome};
+
+    let inputs = match equivalence::parse_range(inputs) {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Invalid range of inputs {:?}: {}", inputs, e);
+            std::process::exit(1);
+        }
+    };
+
+    let result = match equivalence::check(
+        &read_tm(first, None),
+        &read_tm(second, None),
+        inputs,
+        arity,
+        max_steps,
+    ) {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Cannot compare {} and {}: {}", first, second, e);
+            std::process::exit(1);
+        }
+    };
+
+    let counterexample = match result.counterexample {
+        Some(c) => c,
+        None => {
+            println!(
+                "Both machines compute the same results on the {} inputs checked",
+                result.checked
+            );
+            return;
+        }
+    };
+
+    let describe = |outcome: &RunOutcome| match outcome {
+        RunOutcome::Halted { steps, value } => format!("{} after {} steps", value, steps),
+        RunOutcome::Word { steps, word } => format!("\"{}\" after {} steps", word, steps),
+        RunOutcome::Accepted { steps } => format!("ACCEPTED after {} steps", steps),
+        RunOutcome::Rejected { steps } => format!("REJECTED after {} steps", steps),
+        RunOutcome::Undefined { steps } => format!("Undefined after {} steps", steps),
+        RunOutcome::StepLimit { steps } => format!("did not halt after {} steps", steps),
+    };
+
+    let args: Vec<String> = counterexample.args.iter().map(u32::to_string).collect();
+    println!(
+        "The machines differ on the input {} ({} inputs checked)",
+        args.join(","),
+        result.checked
+    );
+    for (name, run) in [
+        (first, &counterexample.first),
+        (second, &counterexample.second),
+    ] {
+        println!("\t{}: {}", name, describe(&run.outcome));
+    }
+
+    for (name, run) in [
+        (first, &counterexample.first),
+        (second, &counterexample.second),
+    ] {
+        println!("\nTrace of {} (initial tape {}):", name, run.tape);
+        for line in &run.trace {
+            println!("{}", line);
+        }
+        if run.outcome.steps() > equivalence::TRACE_LIMIT {
+            println!(
+                "\t... {} more steps",
+                run.outcome.steps() - equivalence::TRACE_LIMIT
+            );
+        }
+    }
+
+    std::process::exit(EXIT_DIFFERENT);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn profile(machine: &str, sizes: &str, arity: usize, max_steps: usize, output: Option<PathBuf>) {
+    use turing_machine::{equivalence, profiler};
+
+    let sizes = match equivalence::parse_range(sizes) {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Invalid range of sizes {:?}: {}", sizes, e);
+            std::process::exit(1);
+        }
+    };
+
+    let profile = match profiler::profile(&read_tm(machine, None), sizes, arity, max_steps) {
+        Ok(p) => p,
+        Err(e) => {
+            error!("Cannot profile {}: {}", machine, e);
+            std::process::exit(1);
+        }
+    };
+
+    match output {
+        Some(file) => {
+            if let Err(e) = fs::write(&file, profile.to_csv()) {
+                error!("Cannot write {:?}: {}", file, e);
+                std::process::exit(1);
+            }
+        }
+        None => print!("{}", profile.to_csv()),
+    }
+
+    // The summary goes to the standard error, so that the standard output is only the CSV
+    for (name, fits) in [("Steps", &profile.steps), ("Space", &profile.space)] {
+        match fits.first() {
+            Some(fit) => eprintln!(
+                "{}: {} ({}, R² = {:.4})",
+                name,
+                fit.growth,
+                fit.formula(),
+                fit.r2
+            ),
+            None => eprintln!("{}: not enough runs halted to fit a curve", name),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn coverage(
+    machine: &str,
+    exercise: Option<(PathBuf, String)>,
+    input: Option<String>,
+    max_steps: usize,
+) {
+    use turing_machine::{machine::Coverage, runner};
+
+    let code = read_tm(machine, None);
+    let coverage = match (exercise, input) {
+        (Some((workbook, exercise)), _) => {
+            let tests = read_exercise_tests(&workbook, &exercise);
+            match runner::test_coverage(&code, &tests, max_steps) {
+                Ok(c) => c,
+                Err(e) => {
+                    error!("Cannot run the tests of {}: {}", machine, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        (None, input) => {
+            let mut run = machine_with_input(&code, input.as_deref());
+            let mut coverage = Coverage::new(&run);
+            runner::run_covered(&mut run, max_steps, &mut coverage);
+
+            coverage
+        }
+    };
+
+    println!(
+        "Transitions used: {}/{} in {} run(s)",
+        coverage.used_transitions(),
+        coverage.transitions.len(),
+        coverage.runs
+    );
+    for t in &coverage.transitions {
+        println!(
+            "{:>6} {:>10}  {}{}",
+            t.line.map(|l| l.to_string()).unwrap_or_default(),
+            t.count,
+            t.instruction,
+            if t.count == 0 { "  <- never used" } else { "" }
+        );
+    }
+
+    println!(
+        "\nStates visited: {}/{}",
+        coverage.used_states(),
+        coverage.states.len()
+    );
+    for s in &coverage.states {
+        println!(
+            "{:>17}  {}{}",
+            s.count,
+            s.state,
+            if s.count == 0 {
+                "  <- never visited"
+            } else {
+                ""
+            }
+        );
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn diagram(
+    code: &str,
+    input: Option<String>,
+    max_steps: usize,
+    tape: usize,
+    scale: u32,
+    output: &std::path::Path,
+) {
+    use turing_machine::space_time::SpaceTime;
+
+    let mut tm = machine_with_input(code, input.as_deref());
+
+    if tape == 0 || tape > tm.tape_count() {
+        error!(
+            "The machine has {} tape(s), there is no tape {}",
+            tm.tape_count(),
+            tape
+        );
+        std::process::exit(1);
+    }
+
+    let mut diagram = SpaceTime::new(&tm);
+    let mut steps = 0;
+    while !tm.halted() && !tm.is_undefined() && steps < max_steps {
+        tm.step();
+        diagram.observe(&tm);
+        steps += 1;
+    }
+
+    if let Some(e) = &diagram.error {
+        error!("Cannot draw the diagram: {}", e);
+        std::process::exit(1);
+    }
+
+    let rows = diagram.rows(tape - 1).len();
+    let (width, height, pixels) = diagram.pixels(tape - 1, 0..rows);
+    let image = match image::RgbImage::from_raw(width as u32, height as u32, pixels) {
+        Some(image) => image,
+        None => {
+            error!("Cannot draw a diagram of {}x{} pixels", width, height);
+            std::process::exit(1);
+        }
+    };
+    let scale = scale.max(1);
+    let image = image::imageops::resize(
+        &image,
+        image.width() * scale,
+        image.height() * scale,
+        image::imageops::FilterType::Nearest,
+    );
+
+    if let Err(e) = image.save(output) {
+        error!("Cannot write {:?}: {}", output, e);
+        std::process::exit(1);
+    }
+
+    println!(
+        "{} steps, {} cells wide{}",
+        steps,
+        width,
+        if diagram.truncated {
+            format!(" (only the first {} steps are drawn)", rows)
+        } else {
+            String::new()
+        }
+    );
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn render(
+    code: &str,
+    input: Option<String>,
+    steps: std::ops::RangeInclusive<usize>,
+    delay: u32,
+    options: turing_machine::render::Options,
+    gif: Option<PathBuf>,
+    frames: Option<PathBuf>,
+) {
+    use turing_machine::render::{self, Recording};
+
+    let mut tm = machine_with_input(code, input.as_deref());
+
+    if steps.is_empty() {
+        error!(
+            "The first step ({}) is after the last one ({})",
+            steps.start(),
+            steps.end()
+        );
+        std::process::exit(1);
+    }
+
+    let recording = Recording::new(&mut tm, steps.clone());
+    if let Some(e) = recording.error() {
+        error!("Cannot draw the machine: {}", e);
+        std::process::exit(1);
+    }
+    if recording.first_step() < *steps.start() {
+        error!(
+            "The machine stops at step {}, before the first step to draw",
+            recording.first_step()
+        );
+        std::process::exit(1);
+    }
+
+    if let Some(path) = gif {
+        if let Err(e) = render::save_gif(recording.frames(&options), delay, &path) {
+            error!("Cannot write {:?}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(dir) = frames {
+        if let Err(e) = render::save_pngs(recording.frames(&options), &dir) {
+            error!("Cannot write the frames in {:?}: {}", dir, e);
+            std::process::exit(1);
+        }
+    }
+
+    println!(
+        "{} frames, from step {} to {}{}",
+        recording.len(),
+        recording.first_step(),
+        recording.first_step() + recording.len() - 1,
+        if recording.len() >= render::MAX_FRAMES {
+            format!(" (only the first {} frames are drawn)", render::MAX_FRAMES)
+        } else {
+            String::new()
+        }
+    );
 }
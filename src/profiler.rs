@@ -0,0 +1,351 @@
+//! Measures how the steps and the space used by a machine grow with the size of its input, and
+//! finds the growth curve that fits them best.
+
+use std::{cmp::Ordering, fmt, ops::RangeInclusive};
+
+use crate::{
+    machine::{Encoding, Simulator},
+    runner::{self, RunOutcome},
+};
+
+/// Fits whose coefficient of determination is this close to the best one are considered as good,
+/// and the simplest of them is chosen
+const FIT_TOLERANCE: f64 = 1e-3;
+
+/// A run of the machine on an input of a given size
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub size: u32,
+    pub steps: usize,
+    /// Cells used by the tapes
+    pub space: usize,
+    /// Whether the machine halted within the step limit. Otherwise, the sample is not fitted.
+    pub halted: bool,
+}
+
+/// A family of growth curves, from the simplest to the fastest growing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Growth {
+    Constant,
+    Linear,
+    NLogN,
+    Quadratic,
+    Exponential,
+}
+
+pub const GROWTHS: [Growth; 5] = [
+    Growth::Constant,
+    Growth::Linear,
+    Growth::NLogN,
+    Growth::Quadratic,
+    Growth::Exponential,
+];
+
+impl Growth {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Growth::Constant => "constant",
+            Growth::Linear => "linear",
+            Growth::NLogN => "n log n",
+            Growth::Quadratic => "quadratic",
+            Growth::Exponential => "exponential",
+        }
+    }
+
+    /// The term that multiplies the coefficient `a` in `a·f(n) + b`. Exponential curves are
+    /// fitted as `a·bⁿ` instead.
+    fn term(&self, n: f64) -> f64 {
+        match self {
+            Growth::Constant => 0.0,
+            Growth::Linear => n,
+            Growth::NLogN if n > 1.0 => n * n.log2(),
+            Growth::NLogN => 0.0,
+            Growth::Quadratic => n * n,
+            Growth::Exponential => n,
+        }
+    }
+}
+
+impl fmt::Display for Growth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A curve fitted by least squares
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fit {
+    pub growth: Growth,
+    pub a: f64,
+    pub b: f64,
+    /// Coefficient of determination: 1 is a perfect fit
+    pub r2: f64,
+}
+
+impl Fit {
+    /// The value of the curve for the given size
+    pub fn predict(&self, n: f64) -> f64 {
+        match self.growth {
+            Growth::Exponential => self.a * self.b.powf(n),
+            growth => self.a * growth.term(n) + self.b,
+        }
+    }
+
+    /// The curve with its coefficients, e.g. `2.000·n^2 + 1.000`
+    pub fn formula(&self) -> String {
+        match self.growth {
+            Growth::Constant => format!("{:.3}", self.b),
+            Growth::Linear => format!("{:.3}·n + {:.3}", self.a, self.b),
+            Growth::NLogN => format!("{:.3}·n·log2(n) + {:.3}", self.a, self.b),
+            Growth::Quadratic => format!("{:.3}·n^2 + {:.3}", self.a, self.b),
+            Growth::Exponential => format!("{:.3}·{:.3}^n", self.a, self.b),
+        }
+    }
+
+    fn new(growth: Growth, points: &[(f64, f64)]) -> Option<Self> {
+        let mean = |v: &[f64]| v.iter().sum::<f64>() / v.len() as f64;
+        let ys: Vec<f64> = points.iter().map(|p| p.1).collect();
+
+        // The exponential is a line once the logarithm of the values is taken
+        let targets: Vec<f64> = match growth {
+            Growth::Exponential if ys.iter().all(|y| *y > 0.0) => {
+                ys.iter().map(|y| y.ln()).collect()
+            }
+            Growth::Exponential => return None,
+            _ => ys.clone(),
+        };
+        let xs: Vec<f64> = points.iter().map(|p| growth.term(p.0)).collect();
+
+        let (mx, my) = (mean(&xs), mean(&targets));
+        let var: f64 = xs.iter().map(|x| (x - mx).powi(2)).sum();
+        let cov: f64 = xs
+            .iter()
+            .zip(&targets)
+            .map(|(x, y)| (x - mx) * (y - my))
+            .sum();
+        let slope = if var > 0.0 { cov / var } else { 0.0 };
+        let intercept = my - slope * mx;
+
+        let mut fit = match growth {
+            Growth::Exponential => Fit {
+                growth,
+                a: intercept.exp(),
+                b: slope.exp(),
+                r2: 0.0,
+            },
+            _ => Fit {
+                growth,
+                a: slope,
+                b: intercept,
+                r2: 0.0,
+            },
+        };
+
+        let my = mean(&ys);
+        let total: f64 = ys.iter().map(|y| (y - my).powi(2)).sum();
+        let residual: f64 = points
+            .iter()
+            .map(|(n, y)| (y - fit.predict(*n)).powi(2))
+            .sum();
+        fit.r2 = if total > 0.0 {
+            1.0 - residual / total
+        } else if residual < 1e-9 {
+            1.0
+        } else {
+            0.0
+        };
+
+        fit.r2.is_finite().then_some(fit)
+    }
+}
+
+/// Fits every growth curve to the points `(size, value)`, from the best fit to the worst. The
+/// simplest curve whose fit is almost as good as the best one goes first.
+pub fn fit(points: &[(f64, f64)]) -> Vec<Fit> {
+    if points.len() < 3 {
+        return vec![];
+    }
+
+    let mut fits: Vec<Fit> = GROWTHS
+        .iter()
+        .filter_map(|g| Fit::new(*g, points))
+        .collect();
+    let best = fits.iter().map(|f| f.r2).fold(f64::MIN, f64::max);
+
+    // `GROWTHS` goes from the simplest to the most complex, and the sort is stable
+    let good = |f: &Fit| f.r2 >= best - FIT_TOLERANCE;
+    fits.sort_by(|x, y| match (good(x), good(y)) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => y.r2.total_cmp(&x.r2),
+    });
+
+    fits
+}
+
+/// The runs of a machine over a range of sizes, and the curves that fit them
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub samples: Vec<Sample>,
+    /// Fits of the steps, the best one first
+    pub steps: Vec<Fit>,
+    /// Fits of the cells used, the best one first
+    pub space: Vec<Fit>,
+}
+
+impl Profile {
+    /// The samples as CSV, one row per size
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("size,steps,space,halted\n");
+
+        for s in &self.samples {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                s.size, s.steps, s.space, s.halted
+            ));
+        }
+
+        csv
+    }
+}
+
+/// The tape of size `n`. For numbers, it is the argument `n` (repeated `arity` times) written with
+/// the encoding of the machine. For machines that read words (`encoding = raw;`), it is a word of
+/// `n` symbols made by repeating the tape of the code.
+fn input(tm: &Simulator, size: u32, arity: usize) -> Result<String, String> {
+    if tm.encoding() != Encoding::Raw {
+        let n = size.to_string();
+        return tm.encode_input(&vec![n.as_str(); arity]);
+    }
+
+    let pattern: Vec<char> = tm.tape_contents()[0].chars().collect();
+    if pattern.is_empty() {
+        return Err(String::from(
+            "the tape of the code is empty, so there is no word to make the inputs from",
+        ));
+    }
+
+    Ok(pattern.iter().cycle().take(size as usize).collect())
+}
+
+/// Runs the machine on the inputs of every size in the range, with at most `max_steps` steps
+/// each, and fits the growth curves to the runs that halted
+pub fn profile(
+    code: &str,
+    sizes: RangeInclusive<u32>,
+    arity: usize,
+    max_steps: usize,
+) -> Result<Profile, String> {
+    let (tm, _warnings) = Simulator::new(code).map_err(|e| e.to_string())?;
+    let mut samples = vec![];
+
+    for size in sizes {
+        let tape = input(&tm, size, arity)?;
+        let (mut run, _warnings) = Simulator::new(&runner::with_input(code, &tape))
+            .map_err(|e| format!("with the tape {}: {}", tape, e))?;
+        let outcome = runner::run(&mut run, max_steps);
+
+        samples.push(Sample {
+            size,
+            steps: outcome.steps(),
+            // The tapes never shrink, so the cells they have now are all the ones that were used
+            space: (0..run.tape_count()).map(|k| run.tape_len(k)).sum(),
+            halted: !matches!(outcome, RunOutcome::StepLimit { .. }),
+        });
+    }
+
+    let points = |value: fn(&Sample) -> usize| -> Vec<(f64, f64)> {
+        samples
+            .iter()
+            .filter(|s| s.halted)
+            .map(|s| (s.size as f64, value(s) as f64))
+            .collect()
+    };
+    let steps = fit(&points(|s| s.steps));
+    let space = fit(&points(|s| s.space));
+
+    Ok(Profile {
+        samples,
+        steps,
+        space,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(f: fn(f64) -> f64) -> Vec<(f64, f64)> {
+        (1..=10).map(|n| (n as f64, f(n as f64))).collect()
+    }
+
+    fn growths(fits: &[Fit]) -> Vec<Growth> {
+        fits.iter().map(|f| f.growth).collect()
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn fits_linear_points() {
+        let fits = fit(&points(|n| 3.0 * n + 2.0));
+
+        assert_eq!(
+            growths(&fits),
+            [
+                Growth::Linear,
+                Growth::NLogN,
+                Growth::Quadratic,
+                Growth::Exponential,
+                Growth::Constant
+            ]
+        );
+        assert_close(fits[0].a, 3.0);
+        assert_close(fits[0].b, 2.0);
+        assert_close(fits[0].r2, 1.0);
+    }
+
+    #[test]
+    fn fits_quadratic_points() {
+        let fits = fit(&points(|n| 2.0 * n * n + 1.0));
+
+        assert_eq!(
+            growths(&fits),
+            [
+                Growth::Quadratic,
+                Growth::NLogN,
+                Growth::Linear,
+                Growth::Exponential,
+                Growth::Constant
+            ]
+        );
+        assert_close(fits[0].a, 2.0);
+        assert_close(fits[0].b, 1.0);
+    }
+
+    #[test]
+    fn fits_exponential_points() {
+        let fits = fit(&points(|n| 3.0 * 2f64.powf(n)));
+
+        assert_eq!(fits[0].growth, Growth::Exponential);
+        assert_close(fits[0].a, 3.0);
+        assert_close(fits[0].b, 2.0);
+        assert_close(fits[0].predict(12.0), 3.0 * 4096.0);
+    }
+
+    #[test]
+    fn prefers_the_simplest_good_fit() {
+        // Every curve fits a constant perfectly, so they stay from the simplest to the most complex
+        let fits = fit(&points(|_| 5.0));
+
+        assert_eq!(growths(&fits), GROWTHS);
+        assert_close(fits[0].b, 5.0);
+    }
+
+    #[test]
+    fn needs_three_points() {
+        assert!(fit(&[(1.0, 1.0), (2.0, 2.0)]).is_empty());
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_family = "wasm"))]
+use {
+    crate::config::{APPLICATION, ORGANIZATION, QUALIFIER},
+    directories::ProjectDirs,
+    log::{debug, error},
+    std::{fs::File, io::Write},
+};
+
+#[cfg(target_family = "wasm")]
+use base64::{engine::general_purpose, Engine as _};
+
+use crate::{console_err, console_log};
+
+#[cfg(target_family = "wasm")]
+const STORAGE_KEY: &str = "turing-machine-progress";
+
+/// Every this many seconds spent on an exercise, the progress is written to disk
+const AUTOSAVE_INTERVAL: f64 = 30.0;
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq)]
+pub enum ExerciseStatus {
+    #[default]
+    NotStarted,
+    Attempted,
+    Solved,
+}
+
+impl ExerciseStatus {
+    /// A short symbol to display next to the exercise title in the catalog
+    pub fn icon(&self) -> &'static str {
+        match self {
+            ExerciseStatus::NotStarted => " ",
+            ExerciseStatus::Attempted => "✏",
+            ExerciseStatus::Solved => "✔",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ExerciseProgress {
+    pub status: ExerciseStatus,
+
+    /// The latest code the student wrote for this exercise
+    pub code: Option<String>,
+
+    /// Time spent on the exercise, in seconds
+    pub time_spent: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct WorkbookProgress {
+    /// The exercise that was selected when the workbook was last closed
+    pub last_selected: (usize, usize),
+
+    /// Progress of each exercise, indexed by `"chapter.exercise"`
+    exercises: HashMap<String, ExerciseProgress>,
+}
+
+impl WorkbookProgress {
+    fn key(exercise: (usize, usize)) -> String {
+        format!("{}.{}", exercise.0, exercise.1)
+    }
+
+    pub fn get(&self, exercise: (usize, usize)) -> Option<&ExerciseProgress> {
+        self.exercises.get(&Self::key(exercise))
+    }
+
+    fn get_mut(&mut self, exercise: (usize, usize)) -> &mut ExerciseProgress {
+        self.exercises.entry(Self::key(exercise)).or_default()
+    }
+
+    pub fn status(&self, exercise: (usize, usize)) -> ExerciseStatus {
+        self.get(exercise).map(|e| e.status).unwrap_or_default()
+    }
+
+    /// Returns how many exercises of the given chapter are solved
+    pub fn solved_in_chapter(&self, chapter: usize, exercises: usize) -> usize {
+        (0..exercises)
+            .filter(|i| self.status((chapter, *i)) == ExerciseStatus::Solved)
+            .count()
+    }
+}
+
+/// The progress of the student across all the workbooks they have opened.
+/// It is stored next to the configuration file (or in the local storage of the browser).
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Progress {
+    /// Progress of each workbook, indexed by the workbook identifier
+    workbooks: HashMap<String, WorkbookProgress>,
+
+    #[serde(skip)]
+    unsaved_time: f64,
+}
+
+impl Progress {
+    pub fn workbook(&self, workbook: &str) -> Option<&WorkbookProgress> {
+        self.workbooks.get(workbook)
+    }
+
+    fn workbook_mut(&mut self, workbook: &str) -> &mut WorkbookProgress {
+        self.workbooks.entry(workbook.to_string()).or_default()
+    }
+
+    pub fn status(&self, workbook: &str, exercise: (usize, usize)) -> ExerciseStatus {
+        self.workbook(workbook)
+            .map(|wb| wb.status(exercise))
+            .unwrap_or_default()
+    }
+
+    pub fn last_selected(&self, workbook: &str) -> (usize, usize) {
+        self.workbook(workbook)
+            .map(|wb| wb.last_selected)
+            .unwrap_or_default()
+    }
+
+    pub fn set_last_selected(&mut self, workbook: &str, exercise: (usize, usize)) {
+        let wb = self.workbook_mut(workbook);
+
+        if wb.last_selected != exercise {
+            wb.last_selected = exercise;
+            self.save();
+        }
+    }
+
+    /// Returns the latest code the student wrote for the exercise, if any
+    pub fn code(&self, workbook: &str, exercise: (usize, usize)) -> Option<&String> {
+        self.workbook(workbook)
+            .and_then(|wb| wb.get(exercise))
+            .and_then(|e| e.code.as_ref())
+    }
+
+    pub fn set_code(&mut self, workbook: &str, exercise: (usize, usize), code: &str) {
+        let ex = self.workbook_mut(workbook).get_mut(exercise);
+
+        if ex.code.as_deref() != Some(code) {
+            ex.code = Some(code.to_string());
+            if ex.status == ExerciseStatus::NotStarted {
+                ex.status = ExerciseStatus::Attempted;
+            }
+        }
+    }
+
+    /// Marks the exercise as attempted, unless it was already solved
+    pub fn mark_attempted(&mut self, workbook: &str, exercise: (usize, usize)) {
+        let ex = self.workbook_mut(workbook).get_mut(exercise);
+
+        if ex.status == ExerciseStatus::NotStarted {
+            ex.status = ExerciseStatus::Attempted;
+            self.save();
+        }
+    }
+
+    pub fn mark_solved(&mut self, workbook: &str, exercise: (usize, usize)) {
+        let ex = self.workbook_mut(workbook).get_mut(exercise);
+
+        if ex.status != ExerciseStatus::Solved {
+            ex.status = ExerciseStatus::Solved;
+            self.save();
+        }
+    }
+
+    /// Forgets the code and status of the exercise, so that the student can start over
+    pub fn reset(&mut self, workbook: &str, exercise: (usize, usize)) {
+        let ex = self.workbook_mut(workbook).get_mut(exercise);
+        ex.code = None;
+        ex.status = ExerciseStatus::NotStarted;
+        self.save();
+    }
+
+    pub fn time_spent(&self, workbook: &str, exercise: (usize, usize)) -> f64 {
+        self.workbook(workbook)
+            .and_then(|wb| wb.get(exercise))
+            .map(|e| e.time_spent)
+            .unwrap_or_default()
+    }
+
+    /// Adds `seconds` to the time spent on the exercise. The progress is saved periodically.
+    pub fn add_time(&mut self, workbook: &str, exercise: (usize, usize), seconds: f64) {
+        self.workbook_mut(workbook).get_mut(exercise).time_spent += seconds;
+        self.unsaved_time += seconds;
+
+        if self.unsaved_time >= AUTOSAVE_INTERVAL {
+            self.save();
+        }
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    pub fn load() -> Option<Self> {
+        match ProjectDirs::from(QUALIFIER, ORGANIZATION, APPLICATION) {
+            Some(dir) => {
+                let file = dir.config_dir().join("progress.toml");
+
+                log::info!("Loading progress file: {:?}", file);
+
+                let contents = match std::fs::read_to_string(&file) {
+                    Ok(s) => s,
+                    Err(e) => {
+                        console_err!("Cannot read progress file: {}", e);
+                        return None;
+                    }
+                };
+
+                match toml::from_str::<Progress>(&contents) {
+                    Ok(p) => Some(p),
+                    Err(e) => {
+                        console_err!("Cannot parse progress file: {}", e);
+                        None
+                    }
+                }
+            }
+            None => {
+                console_err!("Cannot find a valid directory to store the progress file.");
+                None
+            }
+        }
+    }
+
+    #[cfg(target_family = "wasm")]
+    pub fn load() -> Option<Self> {
+        let storage = web_sys::window()?.local_storage().ok()??;
+        let encoded = storage.get_item(STORAGE_KEY).ok()??;
+
+        let data = match general_purpose::STANDARD.decode(encoded) {
+            Ok(d) => d,
+            Err(e) => {
+                console_err!("Cannot decode the stored progress: {}", e);
+                return None;
+            }
+        };
+
+        match bincode::deserialize::<Progress>(&data) {
+            Ok(p) => Some(p),
+            Err(e) => {
+                console_err!("Cannot deserialize the stored progress: {}", e);
+                None
+            }
+        }
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    pub fn save(&mut self) {
+        self.unsaved_time = 0.0;
+
+        if let Some(dir) = ProjectDirs::from(QUALIFIER, ORGANIZATION, APPLICATION) {
+            if !dir.config_dir().try_exists().unwrap_or(true) {
+                if let Err(e) = std::fs::create_dir_all(dir.config_dir()) {
+                    console_err!(
+                        "Could not create configuration directory {:?}: {}",
+                        dir.config_dir(),
+                        e
+                    );
+                    return;
+                }
+            }
+
+            let file_path = dir.config_dir().join("progress.toml");
+
+            let mut file: File = match File::create(&file_path) {
+                Ok(f) => f,
+                Err(e) => {
+                    console_err!(
+                        "Could not create progress file {}: {}",
+                        &file_path.to_string_lossy(),
+                        e
+                    );
+                    return;
+                }
+            };
+
+            let serialized = match toml::to_string_pretty(self) {
+                Ok(s) => s,
+                Err(e) => {
+                    console_err!("Could not serialize the progress: {}", e);
+                    return;
+                }
+            };
+
+            match file.write_all(serialized.as_bytes()) {
+                Ok(_) => console_log!("Progress saved at {}", &file_path.to_string_lossy()),
+                Err(e) => console_err!("Could not write progress file: {}", e),
+            };
+        } else {
+            console_err!("Could not open project directory");
+        }
+    }
+
+    #[cfg(target_family = "wasm")]
+    pub fn save(&mut self) {
+        self.unsaved_time = 0.0;
+
+        let storage = match web_sys::window().and_then(|w| w.local_storage().ok().flatten()) {
+            Some(s) => s,
+            None => {
+                console_err!("The local storage is not available");
+                return;
+            }
+        };
+
+        let data = match bincode::serialize(self) {
+            Ok(d) => d,
+            Err(e) => {
+                console_err!("Could not serialize the progress: {}", e);
+                return;
+            }
+        };
+
+        match storage.set_item(STORAGE_KEY, &general_purpose::STANDARD.encode(data)) {
+            Ok(_) => console_log!("Progress saved in the local storage"),
+            Err(e) => console_err!("Could not save the progress: {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,443 @@
+//! Animations of a run: the tapes, the head and the state drawn offscreen at every step, as the
+//! tape widget shows them, to export them as an animated GIF or as numbered PNG frames without a
+//! display.
+
+use std::{fs::File, io::BufWriter, ops::RangeInclusive, path::Path};
+
+use ab_glyph::{point, Font, FontArc, PxScale, ScaleFont};
+use eframe::egui::FontDefinitions;
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, DynamicImage, ImageResult, Rgb, RgbImage,
+};
+
+use crate::{
+    machine::Simulator,
+    space_time::{self, SpaceTime},
+};
+
+/// Steps remembered by a recording. Longer runs only show their first steps.
+pub const MAX_FRAMES: usize = space_time::MAX_ROWS;
+/// Cells drawn in a row. The view follows the head on wider tapes.
+const MAX_COLUMNS: i64 = 48;
+
+const BACKGROUND: Rgb<u8> = Rgb([27, 27, 27]);
+const CELL_COLOR: Rgb<u8> = Rgb([173, 216, 230]);
+const STROKE_COLOR: Rgb<u8> = Rgb([0, 0, 0]);
+const TEXT_COLOR: Rgb<u8> = Rgb([0, 0, 0]);
+const LABEL_COLOR: Rgb<u8> = Rgb([160, 160, 160]);
+/// Color of the head and of the state, as in the tape widget
+const HEAD_COLOR: Rgb<u8> = Rgb([148, 73, 141]);
+
+/// How the frames are drawn
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Size in pixels of every cell
+    pub cell_size: u32,
+    /// Whether the state and the next instruction are drawn below the tapes
+    pub state: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            cell_size: 40,
+            state: true,
+        }
+    }
+}
+
+/// What the machine is doing at a step
+#[derive(Debug, Clone)]
+struct Label {
+    step: usize,
+    state: String,
+    instruction: Option<String>,
+}
+
+/// The configurations of the steps of a run that are drawn
+#[derive(Debug, Clone)]
+pub struct Recording {
+    diagram: SpaceTime,
+    labels: Vec<Label>,
+}
+
+impl Recording {
+    /// Runs the machine until it stops or reaches the last step of the range, keeping the
+    /// configurations of the steps in the range (at most [`MAX_FRAMES`] of them)
+    pub fn new(tm: &mut Simulator, steps: RangeInclusive<usize>) -> Self {
+        let running = |tm: &Simulator| !tm.halted() && !tm.is_undefined();
+
+        while running(tm) && tm.steps() < *steps.start() {
+            tm.step();
+        }
+
+        let label = |tm: &Simulator| Label {
+            step: tm.steps(),
+            state: tm.current_state().to_string(),
+            instruction: tm.current_instruction(),
+        };
+        let mut recording = Self {
+            diagram: SpaceTime::new(tm),
+            labels: vec![label(tm)],
+        };
+
+        while running(tm) && tm.steps() < *steps.end() && recording.len() < MAX_FRAMES {
+            tm.step();
+            if !recording.diagram.observe(tm) {
+                break;
+            }
+            recording.labels.push(label(tm));
+        }
+
+        recording
+    }
+
+    /// Why the steps could not be recorded, if they were not. Then there are no frames to draw.
+    pub fn error(&self) -> Option<&str> {
+        self.diagram.error.as_deref()
+    }
+
+    /// Number of frames
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// The step of the first frame
+    pub fn first_step(&self) -> usize {
+        self.labels.first().map_or(0, |l| l.step)
+    }
+
+    /// Draws every frame, in order
+    pub fn frames<'a>(&'a self, options: &'a Options) -> impl Iterator<Item = RgbImage> + 'a {
+        let canvas = Canvas::new(self, options);
+        (0..self.len()).map(move |i| canvas.draw(self, i))
+    }
+}
+
+/// The layout shared by every frame of a recording
+struct Canvas {
+    font: FontArc,
+    /// Size of the cells
+    cell: f32,
+    /// Size of the position labels and of the step
+    label: f32,
+    /// Height of the heads below the cells
+    head: f32,
+    /// Radius of the circle with the state, or 0 if it is not drawn
+    state: f32,
+    /// Leftmost and rightmost positions used by every tape in the recording
+    bounds: Vec<(i64, i64)>,
+    width: u32,
+    height: u32,
+}
+
+impl Canvas {
+    fn new(recording: &Recording, options: &Options) -> Self {
+        let font = FontDefinitions::default()
+            .font_data
+            .get("Hack")
+            .and_then(|data| FontArc::try_from_vec(data.font.to_vec()).ok())
+            .expect("the monospace font of egui is always available");
+
+        let bounds: Vec<(i64, i64)> = (0..recording.diagram.tape_count())
+            .map(|k| {
+                recording
+                    .diagram
+                    .rows(k)
+                    .iter()
+                    .fold((i64::MAX, i64::MIN), |(left, right), row| {
+                        (
+                            left.min(row.start).min(row.head),
+                            right
+                                .max(row.start + row.cells.len() as i64 - 1)
+                                .max(row.head),
+                        )
+                    })
+            })
+            .collect();
+
+        // Small cells keep the text and the head big enough to be read
+        let cell = options.cell_size.max(8) as f32;
+        let mut canvas = Self {
+            font,
+            cell,
+            label: (cell * 0.3).max(10.0),
+            head: (cell * 0.4).max(8.0),
+            state: if options.state {
+                (cell * 0.6).max(18.0)
+            } else {
+                0.0
+            },
+            bounds,
+            width: 0,
+            height: 0,
+        };
+
+        let columns = canvas
+            .bounds
+            .iter()
+            .map(|(left, right)| (right - left + 1).min(MAX_COLUMNS))
+            .max()
+            .unwrap_or(1);
+        // The frames are wide enough for the longest instruction
+        let widest = recording
+            .labels
+            .iter()
+            .filter_map(|l| l.instruction.as_deref())
+            .filter(|_| options.state)
+            .map(|i| canvas.text_width(i, canvas.label * 1.3))
+            .fold(0.0, f32::max);
+
+        canvas.width = ((columns + 1) as f32 * cell).max(widest + cell).ceil() as u32;
+        canvas.height =
+            (canvas.tape_top(canvas.bounds.len()) + canvas.state_height()).ceil() as u32;
+
+        canvas
+    }
+
+    /// Height of a tape with its position labels and its head
+    fn tape_height(&self) -> f32 {
+        self.label * 1.4 + self.cell + self.head * 1.5
+    }
+
+    /// The top of the cells of a tape
+    fn tape_top(&self, tape: usize) -> f32 {
+        self.label * 1.8 + tape as f32 * self.tape_height() + self.label * 1.4
+    }
+
+    /// Height of the state and the instruction below the tapes
+    fn state_height(&self) -> f32 {
+        if self.state > 0.0 {
+            self.state * 2.0 + self.label * 3.0
+        } else {
+            0.0
+        }
+    }
+
+    fn text_width(&self, text: &str, size: f32) -> f32 {
+        let font = self.font.as_scaled(PxScale::from(size));
+        text.chars().map(|c| font.h_advance(font.glyph_id(c))).sum()
+    }
+
+    fn draw(&self, recording: &Recording, frame: usize) -> RgbImage {
+        let mut image = RgbImage::from_pixel(self.width, self.height, BACKGROUND);
+        let cell = self.cell;
+        let margin = cell / 2.0;
+        let mut first_head = None;
+
+        let label = &recording.labels[frame];
+        self.text(
+            &mut image,
+            &format!("step {}", label.step),
+            self.width as f32 / 2.0,
+            self.label,
+            self.label,
+            LABEL_COLOR,
+        );
+
+        for (k, &(left, right)) in self.bounds.iter().enumerate() {
+            let row = &recording.diagram.rows(k)[frame];
+            let top = self.tape_top(k);
+
+            // Wide tapes only show the cells around the head
+            let columns = (right - left + 1).min(MAX_COLUMNS);
+            let first = (row.head - columns / 2).min(right - columns + 1).max(left);
+
+            for position in first..first + columns {
+                let x = margin + (position - first) as f32 * cell;
+                let border = (cell / 20.0).max(1.0);
+                fill_rect(&mut image, (x, top), (cell, cell), STROKE_COLOR);
+                fill_rect(
+                    &mut image,
+                    (x + border, top + border),
+                    (cell - 2.0 * border, cell - 2.0 * border),
+                    CELL_COLOR,
+                );
+
+                let symbol = recording.diagram.symbols()[row.get(position) as usize];
+                self.text(
+                    &mut image,
+                    &symbol.to_string(),
+                    x + cell / 2.0,
+                    top + cell / 2.0,
+                    cell * 0.6,
+                    TEXT_COLOR,
+                );
+
+                // Small cells only get every fifth position
+                if cell >= 40.0 || position % 5 == 0 {
+                    self.text(
+                        &mut image,
+                        &position.to_string(),
+                        x + cell / 2.0,
+                        top - self.label * 0.7,
+                        self.label,
+                        LABEL_COLOR,
+                    );
+                }
+            }
+
+            // The head points at its cell from below
+            let head = margin + (row.head - first) as f32 * cell + cell / 2.0;
+            let bottom = top + cell;
+            fill_triangle(
+                &mut image,
+                [
+                    (head, bottom + 2.0),
+                    (head - self.head * 0.6, bottom + self.head),
+                    (head + self.head * 0.6, bottom + self.head),
+                ],
+                HEAD_COLOR,
+            );
+            first_head.get_or_insert(head);
+        }
+
+        if self.state > 0.0 {
+            let radius = self.state;
+            let y = self.tape_top(self.bounds.len()) + radius;
+            // The state follows the head of the first tape, like in the tape widget
+            let x = first_head
+                .unwrap_or(self.width as f32 / 2.0)
+                .min(self.width as f32 - radius)
+                .max(radius);
+
+            fill_circle(&mut image, (x, y), radius, HEAD_COLOR);
+            self.text(&mut image, &label.state, x, y, radius * 0.75, TEXT_COLOR);
+
+            if let Some(instruction) = &label.instruction {
+                self.text(
+                    &mut image,
+                    instruction,
+                    self.width as f32 / 2.0,
+                    y + radius + self.label * 1.5,
+                    self.label * 1.3,
+                    LABEL_COLOR,
+                );
+            }
+        }
+
+        image
+    }
+
+    /// Writes a line of text centered at the given point
+    fn text(&self, image: &mut RgbImage, text: &str, x: f32, y: f32, size: f32, color: Rgb<u8>) {
+        let font = self.font.as_scaled(PxScale::from(size));
+        let mut caret = point(
+            x - self.text_width(text, size) / 2.0,
+            y + (font.ascent() + font.descent()) / 2.0,
+        );
+
+        for c in text.chars() {
+            let glyph = font
+                .glyph_id(c)
+                .with_scale_and_position(font.scale(), caret);
+            caret.x += font.h_advance(glyph.id);
+
+            if let Some(outline) = self.font.outline_glyph(glyph) {
+                let bounds = outline.px_bounds();
+                outline.draw(|gx, gy, coverage| {
+                    blend(
+                        image,
+                        bounds.min.x as i64 + gx as i64,
+                        bounds.min.y as i64 + gy as i64,
+                        color,
+                        coverage,
+                    );
+                });
+            }
+        }
+    }
+}
+
+/// Mixes a color into a pixel, if it is inside of the image
+fn blend(image: &mut RgbImage, x: i64, y: i64, color: Rgb<u8>, alpha: f32) {
+    let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) else {
+        return;
+    };
+    if let Some(pixel) = image.get_pixel_mut_checked(x, y) {
+        let alpha = alpha.clamp(0.0, 1.0);
+        for (p, c) in pixel.0.iter_mut().zip(color.0) {
+            *p = (*p as f32 + (c as f32 - *p as f32) * alpha).round() as u8;
+        }
+    }
+}
+
+fn fill_rect(
+    image: &mut RgbImage,
+    (x, y): (f32, f32),
+    (width, height): (f32, f32),
+    color: Rgb<u8>,
+) {
+    for py in y.round() as i64..(y + height).round() as i64 {
+        for px in x.round() as i64..(x + width).round() as i64 {
+            blend(image, px, py, color, 1.0);
+        }
+    }
+}
+
+fn fill_circle(image: &mut RgbImage, (cx, cy): (f32, f32), radius: f32, color: Rgb<u8>) {
+    for py in (cy - radius).floor() as i64..=(cy + radius).ceil() as i64 {
+        for px in (cx - radius).floor() as i64..=(cx + radius).ceil() as i64 {
+            let distance = ((px as f32 + 0.5 - cx).powi(2) + (py as f32 + 0.5 - cy).powi(2)).sqrt();
+            blend(image, px, py, color, radius + 0.5 - distance);
+        }
+    }
+}
+
+fn fill_triangle(image: &mut RgbImage, points: [(f32, f32); 3], color: Rgb<u8>) {
+    let side = |(ax, ay): (f32, f32), (bx, by): (f32, f32), (x, y): (f32, f32)| {
+        (bx - ax) * (y - ay) - (by - ay) * (x - ax)
+    };
+    let [a, b, c] = points;
+    let (left, right) = (a.0.min(b.0).min(c.0), a.0.max(b.0).max(c.0));
+    let (top, bottom) = (a.1.min(b.1).min(c.1), a.1.max(b.1).max(c.1));
+
+    for py in top.floor() as i64..=bottom.ceil() as i64 {
+        for px in left.floor() as i64..=right.ceil() as i64 {
+            let p = (px as f32 + 0.5, py as f32 + 0.5);
+            let sides = [side(a, b, p), side(b, c, p), side(c, a, p)];
+            if sides.iter().all(|s| *s >= 0.0) || sides.iter().all(|s| *s <= 0.0) {
+                blend(image, px, py, color, 1.0);
+            }
+        }
+    }
+}
+
+/// Writes the frames as an animated GIF that loops forever, showing every frame for the given
+/// number of milliseconds
+pub fn save_gif(
+    frames: impl Iterator<Item = RgbImage>,
+    delay: u32,
+    path: &Path,
+) -> ImageResult<()> {
+    let mut encoder = GifEncoder::new_with_speed(BufWriter::new(File::create(path)?), 10);
+    encoder.set_repeat(Repeat::Infinite)?;
+
+    encoder.try_encode_frames(frames.map(|frame| {
+        Ok(image::Frame::from_parts(
+            DynamicImage::ImageRgb8(frame).into_rgba8(),
+            0,
+            0,
+            Delay::from_numer_denom_ms(delay, 1),
+        ))
+    }))
+}
+
+/// Writes every frame to the directory as `frame_0000.png`, `frame_0001.png`… and returns the
+/// number of frames written
+pub fn save_pngs(frames: impl Iterator<Item = RgbImage>, dir: &Path) -> ImageResult<usize> {
+    std::fs::create_dir_all(dir)?;
+
+    let mut count = 0;
+    for (i, frame) in frames.enumerate() {
+        frame.save(dir.join(format!("frame_{:04}.png", i)))?;
+        count += 1;
+    }
+
+    Ok(count)
+}
@@ -0,0 +1,302 @@
+use serde::{Deserialize, Serialize};
+
+use crate::machine::{CompileError, Coverage, MachineOutput, Simulator};
+
+/// Steps after which a machine is considered not to halt when running tests
+pub const DEFAULT_STEP_LIMIT: usize = 100_000;
+
+/// A test case of an exercise: the tape the machine starts with and the value it must compute
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ExerciseTest {
+    /// The initial tape, as written in the code (e.g. `1110111`)
+    pub input: String,
+
+    /// The expected output, or `None` if the function must be undefined for this input
+    pub expected: Option<u32>,
+
+    /// For acceptors, whether the input must be accepted (`Some(true)`) or rejected
+    /// (`Some(false)`). When it is set, `expected` is ignored.
+    pub accept: Option<bool>,
+
+    /// For machines with their own alphabet, the word that must be left on the tape. When it is
+    /// set (and `accept` is not), `expected` is ignored.
+    pub expected_word: Option<String>,
+}
+
+impl ExerciseTest {
+    /// A test that an acceptor must accept (or reject) the word
+    pub fn word(input: &str, accept: bool) -> Self {
+        Self {
+            input: String::from(input),
+            expected: None,
+            accept: Some(accept),
+            expected_word: None,
+        }
+    }
+
+    /// A test that a machine with its own alphabet must leave the word `expected` on the tape
+    pub fn output_word(input: &str, expected: &str) -> Self {
+        Self {
+            input: String::from(input),
+            expected: None,
+            accept: None,
+            expected_word: Some(String::from(expected)),
+        }
+    }
+}
+
+/// How a bounded run of a machine ended
+#[derive(Clone, Debug, PartialEq)]
+pub enum RunOutcome {
+    /// The machine halted in a final state, leaving the given value on the tape
+    Halted { steps: usize, value: u32 },
+    /// A machine with its own alphabet halted in a final state, leaving the given word on the tape
+    Word { steps: usize, word: String },
+    /// An acceptor accepted its input
+    Accepted { steps: usize },
+    /// An acceptor rejected its input
+    Rejected { steps: usize },
+    /// The machine stopped in a state that is not final
+    Undefined { steps: usize },
+    /// The machine did not halt within the step limit
+    StepLimit { steps: usize },
+}
+
+impl RunOutcome {
+    pub fn steps(&self) -> usize {
+        match self {
+            RunOutcome::Halted { steps, .. }
+            | RunOutcome::Word { steps, .. }
+            | RunOutcome::Accepted { steps }
+            | RunOutcome::Rejected { steps }
+            | RunOutcome::Undefined { steps }
+            | RunOutcome::StepLimit { steps } => *steps,
+        }
+    }
+
+    /// The computed value, `None` if the function is undefined (or did not halt)
+    pub fn value(&self) -> Option<u32> {
+        match self {
+            RunOutcome::Halted { value, .. } => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Whether two runs computed the same result, regardless of the steps they took.
+    /// Not halting within the step limit counts as undefined.
+    pub fn same_result(&self, other: &RunOutcome) -> bool {
+        match (self, other) {
+            (RunOutcome::Halted { value: a, .. }, RunOutcome::Halted { value: b, .. }) => a == b,
+            (RunOutcome::Word { word: a, .. }, RunOutcome::Word { word: b, .. }) => a == b,
+            (RunOutcome::Accepted { .. }, RunOutcome::Accepted { .. })
+            | (RunOutcome::Rejected { .. }, RunOutcome::Rejected { .. }) => true,
+            (
+                RunOutcome::Undefined { .. } | RunOutcome::StepLimit { .. },
+                RunOutcome::Undefined { .. } | RunOutcome::StepLimit { .. },
+            ) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Runs the machine until it halts, becomes undefined or reaches `max_steps`
+pub fn run(tm: &mut Simulator, max_steps: usize) -> RunOutcome {
+    run_with(tm, max_steps, None)
+}
+
+/// Like [`run`], counting in `coverage` the transitions and states the machine uses
+pub fn run_covered(tm: &mut Simulator, max_steps: usize, coverage: &mut Coverage) -> RunOutcome {
+    run_with(tm, max_steps, Some(coverage))
+}
+
+fn run_with(
+    tm: &mut Simulator,
+    max_steps: usize,
+    mut coverage: Option<&mut Coverage>,
+) -> RunOutcome {
+    let mut steps = 0;
+
+    let outcome = loop {
+        if tm.halted() {
+            break match tm.tape_value() {
+                MachineOutput::Value((_, value)) => RunOutcome::Halted { steps, value },
+                MachineOutput::Word((_, word)) => RunOutcome::Word { steps, word },
+                MachineOutput::Accepted(_) => RunOutcome::Accepted { steps },
+                MachineOutput::Rejected(_) => RunOutcome::Rejected { steps },
+                MachineOutput::Undefined(_) => RunOutcome::Undefined { steps },
+            };
+        }
+
+        if tm.is_undefined() {
+            break RunOutcome::Undefined { steps };
+        }
+
+        if steps >= max_steps {
+            break RunOutcome::StepLimit { steps };
+        }
+
+        if let Some(coverage) = coverage.as_deref_mut() {
+            coverage.observe(tm);
+        }
+        tm.step();
+        steps += 1;
+    };
+
+    if let Some(coverage) = coverage {
+        coverage.finish(tm);
+    }
+
+    outcome
+}
+
+/// Returns where the tape statements of the code (`{...};`, not assignments like `I = {q0};`)
+/// are, as the positions of their braces
+fn tape_statements(code: &str) -> Vec<(usize, usize)> {
+    let mut tapes = vec![];
+    let mut open = None;
+    let mut in_comment = false;
+    let mut statement_started = false;
+    let mut previous = '\0';
+
+    for (i, c) in code.char_indices() {
+        if in_comment {
+            in_comment = c != '\n';
+        } else if c == '/' && previous == '/' {
+            in_comment = true;
+        } else if c == ';' {
+            statement_started = false;
+            open = None;
+        } else if c == '{' && !statement_started {
+            statement_started = true;
+            open = Some(i);
+        } else if c == '}' {
+            if let Some(start) = open.take() {
+                tapes.push((start, i));
+            }
+        } else if !c.is_whitespace() && c != '/' {
+            statement_started = true;
+        }
+
+        previous = c;
+    }
+
+    tapes
+}
+
+/// Replaces the initial tape declared in the code (the `{...};` statement that is not an
+/// assignment) with `input`. If the code declares no tape, one is added at the beginning.
+pub fn with_input(code: &str, input: &str) -> String {
+    match tape_statements(code).first() {
+        Some((start, end)) => format!("{}{{{}}}{}", &code[..*start], input, &code[end + 1..]),
+        None => format!("{{{}}};\n{}", input, code),
+    }
+}
+
+/// Replaces the initial tapes declared in the code with `tapes`, in order. Tapes that the code
+/// does not declare are added after the last one it does (or at the beginning).
+pub fn with_tapes(code: &str, tapes: &[String]) -> String {
+    let declared = tape_statements(code);
+    let mut code = code.to_string();
+
+    let missing: String = tapes
+        .iter()
+        .skip(declared.len())
+        .map(|t| format!("{{{}}};\n", t))
+        .collect();
+    match declared.last() {
+        Some((_, end)) if !missing.is_empty() => {
+            code.insert_str(end + 1, &format!(";\n{}", missing.trim_end_matches(";\n")))
+        }
+        None => code.insert_str(0, &missing),
+        _ => {}
+    }
+
+    // From the last one, so that the positions of the others do not change
+    for ((start, end), tape) in declared.iter().zip(tapes).rev() {
+        code.replace_range(start + 1..*end, tape);
+    }
+
+    code
+}
+
+/// Compiles the code with the input of the test and runs it with the given step limit
+pub fn run_test(
+    code: &str,
+    test: &ExerciseTest,
+    max_steps: usize,
+) -> Result<RunOutcome, CompileError> {
+    let (mut tm, _warnings) = Simulator::new(&with_input(code, &test.input))?;
+
+    Ok(run(&mut tm, max_steps))
+}
+
+/// Runs every test and adds up how often each transition and state of the code is used
+pub fn test_coverage(
+    code: &str,
+    tests: &[ExerciseTest],
+    max_steps: usize,
+) -> Result<Coverage, CompileError> {
+    let (tm, _warnings) = Simulator::new(code)?;
+    let mut coverage = Coverage::new(&tm);
+
+    for test in tests {
+        let (mut tm, _warnings) = Simulator::new(&with_input(code, &test.input))?;
+        run_covered(&mut tm, max_steps, &mut coverage);
+    }
+
+    Ok(coverage)
+}
+
+/// Returns whether the outcome of a run satisfies the test. A machine that does not halt
+/// within the step limit computes an undefined value, and neither accepts nor rejects.
+pub fn passes(test: &ExerciseTest, outcome: &RunOutcome) -> bool {
+    match (test.accept, &test.expected_word, outcome) {
+        (Some(accept), _, RunOutcome::Accepted { .. }) => accept,
+        (Some(accept), _, RunOutcome::Rejected { .. }) => !accept,
+        (Some(_), _, _) => false,
+        (None, Some(expected), RunOutcome::Word { word, .. }) => word == expected,
+        (None, Some(_), _) => false,
+        (
+            None,
+            None,
+            RunOutcome::Halted { .. } | RunOutcome::Undefined { .. } | RunOutcome::StepLimit { .. },
+        ) => outcome.value() == test.expected,
+        (None, None, _) => false,
+    }
+}
+
+/// The outcome of running a test with the student code and with the reference solution.
+/// An outcome is `None` when the corresponding code does not compile.
+#[derive(Clone, Debug)]
+pub struct TestComparison {
+    pub test: ExerciseTest,
+    pub student: Option<RunOutcome>,
+    pub solution: Option<RunOutcome>,
+}
+
+impl TestComparison {
+    /// Whether both programs compute the same result for the input of the test
+    pub fn matches(&self) -> bool {
+        match (&self.student, &self.solution) {
+            (Some(student), Some(solution)) => student.same_result(solution),
+            _ => false,
+        }
+    }
+}
+
+/// Runs every test with both the student code and the reference solution
+pub fn compare(
+    student: &str,
+    solution: &str,
+    tests: &[ExerciseTest],
+    max_steps: usize,
+) -> Vec<TestComparison> {
+    tests
+        .iter()
+        .map(|test| TestComparison {
+            test: test.clone(),
+            student: run_test(student, test, max_steps).ok(),
+            solution: run_test(solution, test, max_steps).ok(),
+        })
+        .collect()
+}
@@ -0,0 +1,232 @@
+//! Space-time diagrams of a run: one row of pixels per step and one column per cell of a tape,
+//! with the head marked, as usually drawn for busy beavers and cellular automata.
+
+use std::ops::Range;
+
+use crate::machine::Simulator;
+
+/// Steps remembered by the diagram. Longer runs only show their first steps.
+pub const MAX_ROWS: usize = 10_000;
+
+/// Cells remembered by the diagram, adding up every row of every tape. Runs on long tapes only
+/// show their first steps.
+pub const MAX_CELLS: usize = 20_000_000;
+
+/// Color of the blank cells
+const BLANK_COLOR: [u8; 3] = [255, 255, 255];
+/// Color of the cell under the head
+const HEAD_COLOR: [u8; 3] = [214, 39, 40];
+/// Colors of the other symbols, in the order they first appear
+const PALETTE: [[u8; 3]; 8] = [
+    [30, 30, 30],
+    [31, 119, 180],
+    [44, 160, 44],
+    [255, 127, 14],
+    [148, 103, 189],
+    [140, 86, 75],
+    [227, 119, 194],
+    [23, 190, 207],
+];
+
+/// The contents of a tape at a step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub step: usize,
+    /// Position of the first cell, measured from the cell where the tape started
+    pub start: i64,
+    /// Index of the symbol of every cell in [`SpaceTime::symbols`]
+    pub cells: Vec<u8>,
+    pub head: i64,
+}
+
+impl Row {
+    /// Returns the index of the symbol at the given position (the blank outside of the tape)
+    pub fn get(&self, position: i64) -> u8 {
+        usize::try_from(position - self.start)
+            .ok()
+            .and_then(|i| self.cells.get(i))
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+/// The rows of a tape and where its first cell is now
+#[derive(Debug, Clone, Default)]
+struct History {
+    /// Position of the first cell, which decreases when the tape grows to the left
+    origin: i64,
+    len: usize,
+    rows: Vec<Row>,
+    /// Leftmost and rightmost positions used
+    bounds: (i64, i64),
+}
+
+/// Records the configurations of a machine step by step to draw its space-time diagram
+#[derive(Debug, Clone)]
+pub struct SpaceTime {
+    /// The symbols seen so far, the blank first
+    symbols: Vec<char>,
+    tapes: Vec<History>,
+    /// Cells in the rows of every tape
+    cells: usize,
+    /// Whether the run went on after [`MAX_ROWS`] steps or [`MAX_CELLS`] cells
+    pub truncated: bool,
+    /// Why the diagram stopped recording the run, if it could not draw a step
+    pub error: Option<String>,
+}
+
+impl SpaceTime {
+    /// Starts recording from the current configuration of the machine
+    pub fn new(tm: &Simulator) -> Self {
+        let mut diagram = Self {
+            symbols: vec![tm.blank()],
+            tapes: (0..tm.tape_count())
+                .map(|k| History {
+                    len: tm.tape_len(k),
+                    ..Default::default()
+                })
+                .collect(),
+            cells: 0,
+            truncated: false,
+            error: None,
+        };
+        if let Err(e) = diagram.record(tm) {
+            diagram.error = Some(e);
+        }
+
+        diagram
+    }
+
+    fn symbol_index(&mut self, symbol: char) -> Result<u8, String> {
+        let i = match self.symbols.iter().position(|s| *s == symbol) {
+            Some(i) => i,
+            None => {
+                self.symbols.push(symbol);
+                self.symbols.len() - 1
+            }
+        };
+
+        u8::try_from(i).map_err(|_| {
+            format!(
+                "the tapes use more than {} symbols, which cannot be told apart",
+                u8::MAX as usize + 1
+            )
+        })
+    }
+
+    fn record(&mut self, tm: &Simulator) -> Result<(), String> {
+        // Every tape is read before adding a row to any of them, so that they all have the same
+        // rows if a symbol cannot be drawn
+        let cells = (0..self.tapes.len())
+            .map(|k| {
+                (0..tm.tape_len(k))
+                    .map(|i| self.symbol_index(tm.symbol(k, i)))
+                    .collect::<Result<Vec<u8>, String>>()
+            })
+            .collect::<Result<Vec<Vec<u8>>, String>>()?;
+
+        for (k, cells) in cells.into_iter().enumerate() {
+            let len = cells.len();
+            let head = tm.tape_position(k);
+            // When a tape grows to the left, its first cell is one position further to the left
+            if head == 0 && len > self.tapes[k].len {
+                self.tapes[k].origin -= 1;
+            }
+
+            self.cells += len;
+            let tape = &mut self.tapes[k];
+            let row = Row {
+                step: tm.steps(),
+                start: tape.origin,
+                cells,
+                head: tape.origin + head as i64,
+            };
+
+            let (left, right) = (
+                row.start.min(row.head),
+                (row.start + len as i64 - 1).max(row.head),
+            );
+            tape.bounds = if tape.rows.is_empty() {
+                (left, right)
+            } else {
+                (tape.bounds.0.min(left), tape.bounds.1.max(right))
+            };
+            tape.len = len;
+            tape.rows.push(row);
+        }
+
+        Ok(())
+    }
+
+    /// Must be called after every step of the machine. Returns whether the step was recorded,
+    /// which stops happening after [`MAX_ROWS`] steps, [`MAX_CELLS`] cells or an error.
+    pub fn observe(&mut self, tm: &Simulator) -> bool {
+        if self.error.is_some() {
+            return false;
+        }
+
+        let cells: usize = (0..self.tapes.len()).map(|k| tm.tape_len(k)).sum();
+        if self.tapes.first().is_some_and(|t| t.rows.len() >= MAX_ROWS)
+            || self.cells + cells > MAX_CELLS
+        {
+            self.truncated = true;
+            return false;
+        }
+
+        match self.record(tm) {
+            Ok(()) => true,
+            Err(e) => {
+                self.error = Some(e);
+                false
+            }
+        }
+    }
+
+    pub fn tape_count(&self) -> usize {
+        self.tapes.len()
+    }
+
+    /// The symbols in the order of their colors, the blank first
+    pub fn symbols(&self) -> &[char] {
+        &self.symbols
+    }
+
+    pub fn rows(&self, tape: usize) -> &[Row] {
+        &self.tapes[tape].rows
+    }
+
+    /// The color of a symbol, by its index in [`SpaceTime::symbols`]
+    pub fn color(symbol: u8) -> [u8; 3] {
+        match symbol {
+            0 => BLANK_COLOR,
+            s => PALETTE[(s as usize - 1) % PALETTE.len()],
+        }
+    }
+
+    /// The color of the head
+    pub fn head_color() -> [u8; 3] {
+        HEAD_COLOR
+    }
+
+    /// Draws the diagram of a tape with a pixel per cell and step, for the given rows. Returns its
+    /// width, its height and the RGB values of its pixels, row by row.
+    pub fn pixels(&self, tape: usize, rows: Range<usize>) -> (usize, usize, Vec<u8>) {
+        let tape = &self.tapes[tape];
+        let rows = &tape.rows[rows.start.min(tape.rows.len())..rows.end.min(tape.rows.len())];
+        let (left, right) = tape.bounds;
+        let width = (right - left + 1) as usize;
+        let mut pixels = Vec::with_capacity(width * rows.len() * 3);
+
+        for row in rows {
+            for position in left..=right {
+                pixels.extend(if position == row.head {
+                    HEAD_COLOR
+                } else {
+                    Self::color(row.get(position))
+                });
+            }
+        }
+
+        (width, rows.len(), pixels)
+    }
+}
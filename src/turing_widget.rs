@@ -1,4 +1,6 @@
-use eframe::egui::{self, widgets::Widget};
+use std::collections::VecDeque;
+
+use eframe::egui::{self, widgets::Widget, RichText, Sense};
 use eframe::emath::Align2;
 use eframe::epaint::{Color32, FontFamily, FontId, Pos2, Rect, Rounding, Stroke, Vec2};
 use internationalization::t;
@@ -6,12 +8,128 @@ use internationalization::t;
 #[cfg(not(target_family = "wasm"))]
 use log::warn;
 
-use turing_lib::{CompilerError, CompilerWarning, Library, TuringMachine, TuringOutput};
+use turing_lib::{CompilerWarning, Library};
 
-use crate::{console_warn, window::is_mobile};
+use crate::{
+    console_warn,
+    fast_forward::{FastForward, Run},
+    machine::{
+        CompileError, Coverage, Cycle, CycleDetector, CycleReport, Encoding, MachineOutput, OneWay,
+        Program, Simulator, TapeEdit,
+    },
+    space_time::SpaceTime,
+    window::is_mobile,
+};
 
 const STROKE_WIDTH: f32 = 3f32;
 const FONT_SIZE: f32 = 30f32;
+/// Positions of each head remembered for the trail
+pub(crate) const MAX_TRAIL: usize = 32;
+/// Color of the cells visited or written the most in the heatmap
+const HEAT_COLOR: Color32 = Color32::from_rgb(255, 140, 0);
+/// Smallest and largest sizes of the cells when zooming
+pub const MIN_CELL_SIZE: f32 = 25.0;
+pub const MAX_CELL_SIZE: f32 = 300.0;
+/// Scroll (in points) that zooms the tape by a factor of e
+const ZOOM_SPEED: f32 = 400.0;
+/// Cells smaller than this only get every fifth position
+const LABELED_CELL_SIZE: f32 = 40.0;
+/// Space above the cells for their positions
+const LABEL_HEIGHT: f32 = 16.0;
+/// Height of the strip of each tape in the minimap
+const MINIMAP_ROW_HEIGHT: f32 = 8.0;
+
+/// What the color of the cells shows
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CellHeat {
+    /// Every cell has the same color
+    #[default]
+    None,
+    /// How many times the head has been on each cell
+    Visits,
+    /// How many times the symbol of each cell has changed
+    Writes,
+}
+
+/// Optional information painted over the tapes
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TapeOverlays {
+    pub heat: CellHeat,
+    /// Number of previous positions of the heads shown as a fading trail (0 to hide it)
+    pub trail: usize,
+    /// Whether to outline the cells written in the last step
+    pub last_write: bool,
+}
+
+/// What a head has done on its tape since the machine started, with the cells indexed like
+/// the ones of the tape
+#[derive(Debug, Clone, Default)]
+struct TapeTrace {
+    visits: Vec<usize>,
+    writes: Vec<usize>,
+    /// The last positions of the head, the most recent one last
+    trail: VecDeque<usize>,
+    /// The cell whose symbol changed in the last step
+    written: Option<usize>,
+    /// Position of the first cell, measured from the first cell of the tape when the trace started
+    origin: i64,
+}
+
+impl TapeTrace {
+    fn new(head: usize, len: usize) -> Self {
+        let mut trace = Self::default();
+        trace.resize(head, len);
+        trace.visits[head] += 1;
+
+        trace
+    }
+
+    fn resize(&mut self, head: usize, len: usize) {
+        let len = len.max(head + 1);
+        if self.visits.len() < len {
+            self.visits.resize(len, 0);
+            self.writes.resize(len, 0);
+        }
+    }
+
+    /// Records a step. `previous` is the cell where the head was, and `grew_left` whether the
+    /// tape grew to the left, shifting every cell one position to the right.
+    fn record(&mut self, head: usize, len: usize, previous: usize, grew_left: bool, changed: bool) {
+        if grew_left {
+            self.origin -= 1;
+            self.visits.insert(0, 0);
+            self.writes.insert(0, 0);
+            self.trail.iter_mut().for_each(|p| *p += 1);
+        }
+        let previous = previous + grew_left as usize;
+        self.resize(head.max(previous), len);
+
+        self.visits[head] += 1;
+        self.written = changed.then_some(previous);
+        if changed {
+            self.writes[previous] += 1;
+        }
+
+        self.trail.push_back(previous);
+        if self.trail.len() > MAX_TRAIL {
+            self.trail.pop_front();
+        }
+    }
+}
+
+/// The color of a cell in the heatmap, from the color of the tape to the hot color. The scale is
+/// logarithmic, since the cells of a loop are visited many more times than the rest.
+fn heat(count: usize, max: usize) -> Color32 {
+    let t = ((count as f32).ln_1p() / (max.max(1) as f32).ln_1p()).clamp(0.0, 1.0);
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) as u8;
+    let cold = Color32::LIGHT_BLUE;
+
+    Color32::from_rgb(
+        mix(cold.r(), HEAT_COLOR.r()),
+        mix(cold.g(), HEAT_COLOR.g()),
+        mix(cold.b(), HEAT_COLOR.b()),
+    )
+}
 
 #[derive(Debug, Clone)]
 /// A widget that displays a Turing machine
@@ -20,23 +138,46 @@ pub struct TuringWidget {
     pub tape_rect_size: f32,
     pub font_id: FontId,
     pub offset: f32,
+    /// How much each tape moves with respect to `offset` in the current animation
+    tape_shifts: Vec<f32>,
     pub paused: bool,
     pub tape_anim_speed: f32,
-    pub left: f32,
-    pub threshold_inf_loop: usize, // Threshold for infinite loop detection
+    /// Whether the view moves with the heads. Otherwise, it stays where the user dragged it.
+    pub follow_head: bool,
+    /// The position at the center of the view on each tape, measured like the positions of the
+    /// cells (see [`TapeTrace::origin`])
+    centers: Vec<f32>,
     tri_color: Color32,
     tri_stroke_wid: f32,
     tri_stroke: Stroke,
     tri_size: f32,
-    tm: TuringMachine,
+    tm: Simulator,
+    /// Watches the configurations of the machine to find out if it never halts
+    cycle: CycleDetector,
+    /// Whether the last run in the background stopped at
+    /// [`MAX_STEPS`](crate::fast_forward::MAX_STEPS) without halting
+    step_limit: bool,
+    /// How often every transition and state has been used since the machine started
+    coverage: Coverage,
+    /// What the heads have done on each tape, for the overlays
+    traces: Vec<TapeTrace>,
+    pub overlays: TapeOverlays,
+    /// The configurations of the run, for its space-time diagram
+    space_time: SpaceTime,
+    /// Whether the tapes were changed by hand since the machine was compiled
+    tape_edited: bool,
+    /// The machine with the tapes changed by hand, before its first step, to rewind from it
+    edited: Option<Simulator>,
+    /// The tape and cell whose context menu is open
+    menu_cell: Option<(usize, usize)>,
     warnings: Vec<CompilerWarning>,
-    errors: Option<CompilerError>,
+    errors: Option<CompileError>,
     pub lang: String,
 }
 
 impl TuringWidget {
-    /// Creates a new TuringWidget from a TuringMachine
-    pub fn new(tm: TuringMachine, warnings: Vec<CompilerWarning>) -> Self {
+    /// Creates a new TuringWidget from a compiled machine
+    pub fn new(tm: Simulator, warnings: Vec<CompilerWarning>) -> Self {
         let tri_color = Color32::from_rgb(148, 73, 141);
         let tri_stroke_wid: f32 = 10.0;
         let tri_stroke = Stroke::new(tri_stroke_wid, tri_color);
@@ -45,16 +186,26 @@ impl TuringWidget {
         Self {
             stroke_width: STROKE_WIDTH,
             offset: 0.0,
+            tape_shifts: vec![],
             tape_rect_size: 100.0,
             tape_anim_speed: 1.0,
             font_id: FontId::new(30f32, FontFamily::Monospace),
             paused: true,
-            left: 300.0,
-            threshold_inf_loop: 100,
+            follow_head: true,
+            centers: vec![],
             tri_color,
             tri_stroke_wid,
             tri_stroke,
             tri_size,
+            cycle: CycleDetector::new(&tm),
+            step_limit: false,
+            coverage: Coverage::new(&tm),
+            traces: Self::new_traces(&tm),
+            space_time: SpaceTime::new(&tm),
+            tape_edited: false,
+            edited: None,
+            menu_cell: None,
+            overlays: TapeOverlays::default(),
             tm,
             warnings,
             errors: None,
@@ -67,7 +218,6 @@ impl TuringWidget {
         let mut new_tm = self.clone();
 
         new_tm.lang = config.language().to_string();
-        new_tm.threshold_inf_loop = config.threshold_inf_loop();
         new_tm.tape_rect_size = config.tape_size();
         new_tm.tape_anim_speed = config.tape_speed();
 
@@ -75,8 +225,8 @@ impl TuringWidget {
     }
 
     /// Restarts the turing machine with the given code
-    pub fn restart(&mut self, code: &str) -> Result<Self, CompilerError> {
-        let (tm, warnings) = match TuringMachine::new(code) {
+    pub fn restart(&mut self, code: &str) -> Result<Self, CompileError> {
+        let (tm, warnings) = match Simulator::new(code) {
             Ok((t, warnings)) => {
                 for w in &warnings {
                     #[cfg(not(target_family = "wasm"))]
@@ -99,16 +249,26 @@ impl TuringWidget {
         Ok(Self {
             stroke_width: STROKE_WIDTH,
             offset: 0.0,
+            tape_shifts: vec![],
             tape_rect_size: self.tape_rect_size,
             tape_anim_speed: self.tape_anim_speed,
             font_id: self.font_id.clone(),
             paused: self.paused,
-            left: self.left,
-            threshold_inf_loop: self.threshold_inf_loop,
+            follow_head: self.follow_head,
+            centers: vec![],
             tri_color: self.tri_color,
             tri_stroke_wid: self.tri_stroke_wid,
             tri_stroke: self.tri_stroke,
             tri_size: self.tri_size,
+            cycle: CycleDetector::new(&tm),
+            step_limit: false,
+            coverage: Coverage::new(&tm),
+            traces: Self::new_traces(&tm),
+            space_time: SpaceTime::new(&tm),
+            tape_edited: false,
+            edited: None,
+            menu_cell: None,
+            overlays: self.overlays,
             tm,
             warnings,
             errors: None,
@@ -116,49 +276,148 @@ impl TuringWidget {
         })
     }
 
+    fn new_traces(tm: &Simulator) -> Vec<TapeTrace> {
+        (0..tm.tape_count())
+            .map(|k| TapeTrace::new(tm.tape_position(k), tm.tape_len(k)))
+            .collect()
+    }
+
     /// Calculate a step of the Turing machine
     pub fn step(&mut self) -> f32 {
-        let prev = self.tm.tape_position;
+        let count = self.tm.tape_count();
+        let prev: Vec<(usize, usize, char)> = (0..count)
+            .map(|k| {
+                let head = self.tm.tape_position(k);
+                (head, self.tm.tape_len(k), self.tm.symbol(k, head))
+            })
+            .collect();
+        self.step_limit = false;
+        let running = !self.tm.halted() && !self.tm.is_undefined();
+        if running {
+            self.coverage.observe(&self.tm);
+        }
         self.tm.step();
-        self.offset = self.tm.tape_position as f32 - prev as f32;
+        self.cycle.observe(&self.tm);
+        if running {
+            self.space_time.observe(&self.tm);
+        }
+        if running && (self.finished() || self.tm.is_undefined()) {
+            self.coverage.finish(&self.tm);
+        }
 
-        if self.finished()
-            || self.tm.is_undefined()
-            || self.tm.is_infinite_loop(self.threshold_inf_loop)
-        {
+        let moves: Vec<f32> = prev
+            .iter()
+            .enumerate()
+            .map(|(k, (prev, prev_len, symbol))| {
+                let head = self.tm.tape_position(k);
+                let len = self.tm.tape_len(k);
+                // When the tape grows to the left, every cell is shifted one position to the right
+                let grew_left = head == 0 && len > *prev_len;
+
+                if running {
+                    let changed = self.tm.symbol(k, prev + grew_left as usize) != *symbol;
+                    if let Some(trace) = self.traces.get_mut(k) {
+                        trace.record(head, len, *prev, grew_left, changed);
+                    }
+                }
+
+                head as f32 - *prev as f32 - if grew_left { 1.0 } else { 0.0 }
+            })
+            .collect();
+
+        // The offset animates the tape that moved the most, the others follow proportionally
+        self.offset = moves
+            .iter()
+            .copied()
+            .fold(0.0, |acc, m| if m.abs() > acc.abs() { m } else { acc });
+        self.tape_shifts = if self.offset == 0.0 {
+            vec![0.0; count]
+        } else {
+            moves.iter().map(|m| m / self.offset).collect()
+        };
+
+        if self.finished() || self.tm.is_undefined() || self.is_inf_loop() {
             self.paused = true;
         }
 
         self.offset
     }
 
+    /// Starts running the machine to completion in the background, from its current configuration
+    pub fn fast_forward(&self, now: f64) -> FastForward {
+        FastForward::start(
+            self.tm.clone(),
+            self.coverage.clone(),
+            self.cycle.clone(),
+            now,
+        )
+    }
+
+    /// Replaces the machine with the configuration reached by a run in the background, the
+    /// coverage with the one of the whole run and the cycle detector with the one that watched it
+    pub fn sync(&mut self, run: Run) {
+        self.tm = run.tm;
+        self.coverage = run.coverage;
+        self.cycle = run.cycle;
+        self.step_limit = run.step_limit;
+        // The cells may have moved while running in the background, so the traces and the view
+        // start again
+        self.traces = Self::new_traces(&self.tm);
+        self.centers.clear();
+        self.space_time = SpaceTime::new(&self.tm);
+        self.tm.reset_frequencies();
+        self.offset = 0.0;
+        self.tape_shifts = vec![0.0; self.tm.tape_count()];
+        self.paused = true;
+    }
+
     /// Returns whether the turing machine is in a final state, the current state is the same as the previous state and the current instruction is HALT
     pub fn finished(&self) -> bool {
-        self.tm.finished()
-            && self.tm.previous_state.clone().unwrap_or_default() == self.tm.current_state
-            && match self.tm.get_current_instruction() {
-                Some(ins) => {
-                    println!("{:?}", ins);
-                    ins.movement == turing_lib::Movement::HALT
-                        && ins.from_value == ins.to_value
-                        && ins.from_state == ins.to_state
-                }
-                None => true,
-            }
+        self.tm.halted()
     }
 
     /// Returns the current tape value
-    pub fn tape_value(&self) -> TuringOutput {
+    pub fn tape_value(&self) -> MachineOutput {
         self.tm.tape_value()
     }
 
-    /// Returns the current tape length
+    /// Returns the current length of the first tape
     pub fn len(&self) -> usize {
-        self.tm.tape.len()
+        self.tm.tape_len(0)
     }
 
     pub fn is_empty(&self) -> bool {
-        self.tm.tape.is_empty()
+        self.len() == 0
+    }
+
+    /// Returns the number of tapes of the machine
+    pub fn tape_count(&self) -> usize {
+        self.tm.tape_count()
+    }
+
+    /// Returns the contents of every tape
+    pub fn tape_contents(&self) -> Vec<String> {
+        self.tm.tape_contents()
+    }
+
+    /// Returns the code of an equivalent single-tape machine, if the machine has several tapes
+    pub fn single_tape_code(&self) -> Option<Result<String, String>> {
+        self.tm.to_single_tape()
+    }
+
+    /// Returns whether the machine can choose between several transitions
+    pub fn is_nondeterministic(&self) -> bool {
+        self.tm.is_nondeterministic()
+    }
+
+    /// Returns the program of a machine with the extended syntax
+    pub fn program(&self) -> Option<&Program> {
+        self.tm.program()
+    }
+
+    /// Makes the next steps follow a branch of the computation tree
+    pub fn follow(&mut self, path: Vec<usize>) {
+        self.tm.follow(path);
     }
 
     /// Returns the description of the Turing machine if it exists
@@ -168,12 +427,12 @@ impl TuringWidget {
             return None;
         }
 
-        self.tm.description.as_ref()
+        self.tm.description()
     }
 
     /// Returns the current code
     pub fn code(&self) -> &str {
-        &self.tm.code
+        self.tm.code()
     }
 
     /// Returns the current warnings
@@ -182,21 +441,33 @@ impl TuringWidget {
     }
 
     /// Returns the composed libraries
-    pub fn libraries(&self) -> &Vec<Library> {
-        &self.tm.composed_libs
+    pub fn libraries(&self) -> &[Library] {
+        self.tm.libraries()
     }
 
     pub fn uses_libraries(&self) -> bool {
-        !self.tm.composed_libs.is_empty()
+        !self.tm.libraries().is_empty()
     }
 
     /// Returns the current values of the tape converted to strings
     pub fn tape_values(&self) -> Vec<String> {
-        self.tm
-            .values()
-            .iter()
-            .map(|v| v.to_string())
-            .collect::<Vec<String>>()
+        self.tm.values()
+    }
+
+    /// Whether the tapes are bounded on the left
+    pub fn one_way(&self) -> Option<OneWay> {
+        self.tm.one_way()
+    }
+
+    /// Returns whether the machine stopped because a head tried to move past the left end of a
+    /// one-way tape
+    pub fn fell_off(&self) -> bool {
+        self.tm.fell_off()
+    }
+
+    /// How the values of the machine are read from the tape
+    pub fn encoding(&self) -> Encoding {
+        self.tm.encoding()
     }
 
     /// Reset the frequencies of the Turing machine
@@ -204,9 +475,199 @@ impl TuringWidget {
         self.tm.reset_frequencies();
     }
 
+    /// How often every transition and state has been used since the machine started
+    pub fn coverage(&self) -> &Coverage {
+        &self.coverage
+    }
+
+    /// The configurations the machine went through, for its space-time diagram
+    pub fn space_time(&self) -> &SpaceTime {
+        &self.space_time
+    }
+
+    /// Returns whether the last run in the background stopped at
+    /// [`MAX_STEPS`](crate::fast_forward::MAX_STEPS) without halting
+    pub fn step_limit_reached(&self) -> bool {
+        self.step_limit
+    }
+
     /// Returns true if the Turing machine is in an infinite loop
     pub fn is_inf_loop(&self) -> bool {
-        self.tm.is_infinite_loop(self.threshold_inf_loop)
+        self.cycle.cycle().is_some()
+    }
+
+    /// The cycle that proves that the machine never halts, if it has been found
+    pub fn cycle(&self) -> Option<&Cycle> {
+        self.cycle.cycle()
+    }
+
+    /// The cycle with the states and transitions it goes through, if it has been found
+    pub fn cycle_report(&self) -> Option<&CycleReport> {
+        self.cycle.report()
+    }
+
+    /// Runs the machine again from the beginning of its code (or from the tapes changed by hand)
+    /// up to the given step, without animating it
+    pub fn rewind(&mut self, step: usize) -> Result<(), CompileError> {
+        let mut tm = match &self.edited {
+            Some(tm) => tm.clone(),
+            None => Simulator::new(self.tm.code())?.0,
+        };
+        let mut coverage = Coverage::new(&tm);
+        let mut space_time = SpaceTime::new(&tm);
+
+        while tm.steps() < step && !tm.halted() && !tm.is_undefined() {
+            coverage.observe(&tm);
+            tm.step();
+            space_time.observe(&tm);
+        }
+        self.sync(Run {
+            cycle: CycleDetector::new(&tm),
+            tm,
+            coverage,
+            step_limit: false,
+        });
+        self.space_time = space_time;
+
+        Ok(())
+    }
+
+    /// The blank of the machine
+    pub fn blank(&self) -> char {
+        self.tm.blank()
+    }
+
+    /// Whether the tapes can be changed by hand, which is only possible before the machine starts
+    pub fn can_edit_tape(&self) -> bool {
+        self.paused && self.tm.steps() == 0
+    }
+
+    /// Changes a tape by hand. The loop detection, coverage and overlays start again from it.
+    pub fn edit_tape(&mut self, tape: usize, edit: TapeEdit) {
+        self.tm.edit_tape(tape, edit);
+        self.tape_edited = true;
+        self.edited = Some(self.tm.clone());
+
+        self.cycle = CycleDetector::new(&self.tm);
+        self.coverage = Coverage::new(&self.tm);
+        self.traces = Self::new_traces(&self.tm);
+        self.space_time = SpaceTime::new(&self.tm);
+    }
+
+    /// Whether the tapes were changed by hand since the machine was compiled
+    pub fn tape_edited(&self) -> bool {
+        self.tape_edited
+    }
+
+    /// The contents of the tapes written as in the code, to save the edited tapes in it
+    pub fn tape_inputs(&self) -> Vec<String> {
+        self.tm.tape_inputs()
+    }
+
+    /// Position of the first cell of a tape (see [`TapeTrace::origin`])
+    fn origin(&self, tape: usize) -> i64 {
+        self.traces.get(tape).map_or(0, |t| t.origin)
+    }
+
+    /// The tape and cell (as an index of the tape) under a point of the view, if any. `center` is
+    /// the center of the first tape.
+    fn cell_at(&self, point: Pos2, center: Pos2, row_height: f32) -> Option<(usize, usize)> {
+        let size = self.tape_rect_size;
+        let k = ((point.y - center.y + size / 2.0) / row_height).floor();
+        if k < 0.0
+            || k as usize >= self.tm.tape_count()
+            || point.y > center.y + k * row_height + size / 2.0
+        {
+            return None;
+        }
+
+        let k = k as usize;
+        let position = (self.centers.get(k)? + (point.x - center.x) / size).round() as i64;
+        usize::try_from(position - self.origin(k))
+            .ok()
+            .map(|i| (k, i))
+    }
+
+    /// Draws the used part of every tape in a strip, with the heads and the cells in sight
+    /// (`visible` of them), and moves the view to where the strip is clicked or dragged
+    fn minimap(&mut self, ui: &mut egui::Ui, rect: Rect, visible: f32) {
+        let count = self.tm.tape_count();
+        let row_height = rect.height() / count as f32;
+        // The positions of the used cells of each tape
+        let ranges: Vec<(f32, f32)> = (0..count)
+            .map(|k| {
+                let origin = self.origin(k) as f32;
+                (origin - 0.5, origin + self.tm.tape_len(k) as f32 - 0.5)
+            })
+            .collect();
+
+        let response = ui
+            .interact(rect, egui::Id::new("tape_minimap"), Sense::click_and_drag())
+            .on_hover_text_at_pointer(t!("tooltip.tape.minimap", self.lang));
+        if let (Some(pointer), true) = (
+            response.interact_pointer_pos(),
+            response.is_pointer_button_down_on(),
+        ) {
+            let k = (((pointer.y - rect.top()) / row_height) as usize).min(count - 1);
+            let (left, right) = ranges[k];
+            let position = left + (pointer.x - rect.left()) / rect.width() * (right - left);
+            if let Some(center) = self.centers.get(k) {
+                let shift = position - center;
+                self.centers.iter_mut().for_each(|c| *c += shift);
+                self.follow_head = false;
+            }
+        }
+
+        let painter = ui.painter_at(rect);
+        let blank = self.tm.blank();
+        for (k, (left, right)) in ranges.into_iter().enumerate() {
+            let strip = Rect::from_min_size(
+                rect.left_top() + Vec2::new(0.0, k as f32 * row_height),
+                Vec2::new(rect.width(), row_height - 2.0),
+            );
+            let x =
+                |position: f32| strip.left() + (position - left) / (right - left) * strip.width();
+            let span = |from: f32, to: f32| {
+                Rect::from_min_max(Pos2::new(from, strip.top()), Pos2::new(to, strip.bottom()))
+            };
+            painter.rect_filled(strip, 0.0, Color32::LIGHT_BLUE);
+
+            // The cells with a symbol, merged when they are closer than a pixel
+            let origin = self.origin(k) as f32;
+            let mut runs: Vec<(f32, f32)> = vec![];
+            for i in 0..self.tm.tape_len(k) {
+                if self.tm.symbol(k, i) == blank {
+                    continue;
+                }
+
+                let from = x(origin + i as f32 - 0.5);
+                let to = x(origin + i as f32 + 0.5).max(from + 1.0);
+                match runs.last_mut() {
+                    Some(run) if from - run.1 < 1.0 => run.1 = to,
+                    _ => runs.push((from, to)),
+                }
+            }
+            for (from, to) in runs {
+                painter.rect_filled(span(from, to), 0.0, Color32::DARK_BLUE);
+            }
+
+            let head = x(origin + self.tm.tape_position(k) as f32);
+            painter.rect_filled(span(head - 1.0, head + 1.0), 0.0, self.tri_color);
+
+            if let Some(center) = self.centers.get(k) {
+                let from = x(center - visible / 2.0).max(strip.left());
+                let to = x(center + visible / 2.0).min(strip.right());
+                if from < to {
+                    painter.rect_stroke(span(from, to), 0.0, Stroke::new(1.5, Color32::BLACK));
+                }
+            }
+        }
+    }
+
+    /// Forgets the cycle that was found, to let the machine continue. It will be found again once
+    /// the machine goes through it from the current configuration.
+    pub fn forget_cycle(&mut self) {
+        self.cycle = CycleDetector::new(&self.tm);
     }
 }
 
@@ -226,7 +687,11 @@ impl Widget for &mut TuringWidget {
         if ui.is_rect_visible(ui.cursor()) {
             let stroke = Stroke::new(stroke_width, Color32::BLACK);
             let rounding = Rounding::same(10f32);
-            let size = Vec2::new(self.tape_rect_size, self.tape_rect_size);
+            let count = self.tm.tape_count();
+            let bounds = ui.max_rect();
+
+            let minimap_top = ui.cursor().top() + 5.0;
+            let minimap_height = count as f32 * MINIMAP_ROW_HEIGHT;
             let center = ui.cursor().center_top()
                 + Vec2::new(
                     if is_mobile(ui.ctx()) {
@@ -234,62 +699,311 @@ impl Widget for &mut TuringWidget {
                     } else {
                         0.0
                     },
-                    self.tape_rect_size / 2.0 + if is_mobile(ui.ctx()) { 25.0 } else { 50.0 },
+                    self.tape_rect_size / 2.0
+                        + minimap_height
+                        + if is_mobile(ui.ctx()) { 25.0 } else { 50.0 },
                 );
 
-            let pos = center + Vec2::new(self.offset * size.x, 0.0);
+            let row_height = self.tape_rect_size + tri_size / 3.0;
+            let editable = self.can_edit_tape();
+            let alphabet = self.tm.alphabet();
+            let mut edit = None;
 
-            for i in 0..(self.tm.tape.len() + 5) {
-                let position = Pos2::new(
-                    pos.x + (i as f32 - self.tm.tape_position as f32) * size.x,
-                    pos.y,
-                );
-                let mut rect = Rect::from_center_size(position, size);
-                if ui.is_rect_visible(rect) {
-                    if rect.min.x < self.left {
-                        rect.set_left(self.left);
-                    } else if rect.max.x > self.left + ui.ctx().screen_rect().width() - 200.0 {
-                        rect.set_right(self.left + ui.ctx().screen_rect().width() - 200.0);
+            // Where each head is in the animation, as a position of its tape
+            let heads: Vec<f32> = (0..count)
+                .map(|k| {
+                    let shift = self.tape_shifts.get(k).copied().unwrap_or(1.0);
+                    self.origin(k) as f32 + self.tm.tape_position(k) as f32 - self.offset * shift
+                })
+                .collect();
+            if self.follow_head || self.centers.len() != count {
+                self.centers = heads.clone();
+            }
+
+            let view = Rect::from_min_max(
+                Pos2::new(
+                    bounds.left(),
+                    center.y - self.tape_rect_size / 2.0 - LABEL_HEIGHT,
+                ),
+                Pos2::new(
+                    bounds.right(),
+                    center.y + (count - 1) as f32 * row_height + self.tape_rect_size / 2.0,
+                ),
+            );
+            let response =
+                ui.interact(view, egui::Id::new("turingwidget"), Sense::click_and_drag());
+
+            if response.dragged() {
+                let shift = response.drag_delta().x / self.tape_rect_size;
+                self.centers.iter_mut().for_each(|c| *c -= shift);
+                self.follow_head = false;
+            }
+
+            if response.hovered() {
+                let (scroll, zoom) = ui.input(|i| (i.scroll_delta.y, i.zoom_delta()));
+                let factor = zoom * (scroll / ZOOM_SPEED).exp();
+
+                if factor != 1.0 {
+                    let new_size =
+                        (self.tape_rect_size * factor).clamp(MIN_CELL_SIZE, MAX_CELL_SIZE);
+                    // Without following the head, the cell under the pointer stays under it
+                    if let (Some(pointer), false) = (response.hover_pos(), self.follow_head) {
+                        let dx = pointer.x - center.x;
+                        self.centers
+                            .iter_mut()
+                            .for_each(|c| *c += dx / self.tape_rect_size - dx / new_size);
                     }
+                    self.tape_rect_size = new_size;
+                }
+            }
 
-                    ui.painter()
-                        .rect_filled(rect, rounding, Color32::LIGHT_BLUE);
-                    ui.painter().rect_stroke(rect, rounding, stroke);
+            // Before running, clicking a cell writes the next symbol of the alphabet, and the
+            // other changes are in its context menu
+            let response = if editable {
+                let pointer = response.interact_pointer_pos();
+                if response.clicked() {
+                    if let Some((k, i)) = pointer.and_then(|p| self.cell_at(p, center, row_height))
+                    {
+                        let symbol = self.tm.symbol(k, i);
+                        let next = alphabet
+                            .iter()
+                            .position(|s| *s == symbol)
+                            .map_or(0, |p| (p + 1) % alphabet.len());
+                        edit = Some((k, TapeEdit::Write(i, alphabet[next])));
+                    }
+                }
+                if response.secondary_clicked() {
+                    self.menu_cell = pointer.and_then(|p| self.cell_at(p, center, row_height));
+                }
 
-                    if position.x > self.left {
-                        ui.painter().text(
-                            position,
-                            Align2::CENTER_CENTER,
-                            if self.tm.get(i).unwrap_or(false) {
-                                "1"
-                            } else {
-                                "0"
-                            },
-                            self.font_id.clone(),
-                            Color32::BLACK,
+                let response =
+                    response.on_hover_text_at_pointer(t!("tooltip.tape.edit", self.lang));
+                match self.menu_cell {
+                    Some((k, i)) => response.context_menu(|ui| {
+                        let symbol = self.tm.symbol(k, i);
+                        ui.horizontal(|ui| {
+                            for s in &alphabet {
+                                if ui
+                                    .selectable_label(
+                                        *s == symbol,
+                                        RichText::new(s.to_string()).monospace(),
+                                    )
+                                    .clicked()
+                                {
+                                    edit = Some((k, TapeEdit::Write(i, *s)));
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                        ui.separator();
+
+                        let len = self.tm.tape_len(k);
+                        let actions = [
+                            (
+                                t!("lbl.tape.edit.left", self.lang),
+                                TapeEdit::Insert(i),
+                                true,
+                            ),
+                            (
+                                t!("lbl.tape.edit.right", self.lang),
+                                TapeEdit::Insert(i + 1),
+                                true,
+                            ),
+                            (
+                                t!("lbl.tape.edit.delete", self.lang),
+                                TapeEdit::Remove(i),
+                                i < len && len > 1,
+                            ),
+                            (
+                                t!("lbl.tape.edit.head", self.lang),
+                                TapeEdit::MoveHead(i),
+                                true,
+                            ),
+                        ];
+                        for (text, action, enabled) in actions {
+                            if ui.add_enabled(enabled, egui::Button::new(text)).clicked() {
+                                edit = Some((k, action));
+                                ui.close_menu();
+                            }
+                        }
+                    }),
+                    None => response,
+                }
+            } else {
+                response.on_hover_text_at_pointer(t!("tooltip.tape.view", self.lang))
+            };
+
+            let size = Vec2::new(self.tape_rect_size, self.tape_rect_size);
+            // In sight on each side of the center, plus the cells cut by the edges
+            let half = (view.width() / 2.0 / size.x).ceil() as i64 + 1;
+
+            self.minimap(
+                ui,
+                Rect::from_min_size(
+                    Pos2::new(view.center().x - view.width() * 0.4, minimap_top),
+                    Vec2::new(view.width() * 0.8, minimap_height),
+                ),
+                view.width() / size.x,
+            );
+
+            // The cells are clipped to the view, which is also below the heads of the tapes
+            let painter = ui.painter_at(view.expand2(Vec2::new(0.0, 10.0)));
+            let cell_font = FontId::new(
+                self.font_id.size.min(size.x * 0.6),
+                self.font_id.family.clone(),
+            );
+            let blank = self.tm.blank();
+
+            for (k, head) in heads.iter().enumerate() {
+                let row = center + Vec2::new(0.0, k as f32 * row_height);
+                let origin = self.origin(k);
+                let view_center = self.centers[k];
+                // The x-coordinate of the center of the cell at a position
+                let x = |position: f32| row.x + (position - view_center) * size.x;
+
+                let trace = self.traces.get(k);
+                let counts = trace.and_then(|t| match self.overlays.heat {
+                    CellHeat::None => None,
+                    CellHeat::Visits => Some(&t.visits),
+                    CellHeat::Writes => Some(&t.writes),
+                });
+                let max = counts.and_then(|c| c.iter().max()).copied().unwrap_or(0);
+
+                let first = view_center.round() as i64 - half;
+                for position in first..=first + 2 * half {
+                    // Cells to the left of the used tape are blank, unless the tape ends there
+                    let index = usize::try_from(position - origin).ok();
+                    if index.is_none() && self.tm.one_way().is_some() {
+                        continue;
+                    }
+
+                    let rect = Rect::from_center_size(Pos2::new(x(position as f32), row.y), size);
+                    let fill = match counts {
+                        Some(c) => heat(index.and_then(|i| c.get(i)).copied().unwrap_or(0), max),
+                        None => Color32::LIGHT_BLUE,
+                    };
+                    painter.rect_filled(rect, rounding, fill);
+                    painter.rect_stroke(rect, rounding, stroke);
+
+                    if self.overlays.last_write
+                        && index.is_some()
+                        && trace.and_then(|t| t.written) == index
+                    {
+                        painter.rect_stroke(
+                            rect.shrink(stroke_width * 2.0),
+                            rounding,
+                            Stroke::new(stroke_width * 2.0, Color32::GOLD),
                         );
                     }
-                } else {
-                    continue;
+
+                    painter.text(
+                        rect.center(),
+                        Align2::CENTER_CENTER,
+                        index.map_or(blank, |i| self.tm.symbol(k, i)),
+                        cell_font.clone(),
+                        Color32::BLACK,
+                    );
+
+                    // Small cells only get every fifth position
+                    if size.x >= LABELED_CELL_SIZE || position % 5 == 0 {
+                        painter.text(
+                            rect.center_top() - Vec2::new(0.0, 2.0),
+                            Align2::CENTER_BOTTOM,
+                            position.to_string(),
+                            FontId::proportional(LABEL_HEIGHT * 0.75),
+                            Color32::GRAY,
+                        );
+                    }
+                }
+
+                // The previous positions of the head, fading with their age
+                if let Some(trace) = trace {
+                    let trail: Vec<usize> = trace
+                        .trail
+                        .iter()
+                        .rev()
+                        .take(self.overlays.trail)
+                        .copied()
+                        .collect();
+
+                    for (age, p) in trail.iter().enumerate().rev() {
+                        let alpha = 255.0 * (1.0 - age as f32 / trail.len() as f32);
+                        painter.circle_filled(
+                            Pos2::new(x((origin + *p as i64) as f32), row.y + size.y * 0.35),
+                            size.x * 0.08,
+                            Color32::from_rgba_unmultiplied(
+                                self.tri_color.r(),
+                                self.tri_color.g(),
+                                self.tri_color.b(),
+                                alpha as u8,
+                            ),
+                        );
+                    }
+                }
+
+                // A one-way tape ends to the left of its first cell
+                if self.tm.one_way().is_some() {
+                    let x = x(origin as f32 - 0.5);
+                    painter.line_segment(
+                        [
+                            Pos2::new(x, row.y - size.y / 2.0 - 5.0),
+                            Pos2::new(x, row.y + size.y / 2.0 + 5.0),
+                        ],
+                        Stroke::new(stroke_width * 3.0, Color32::DARK_GRAY),
+                    );
+                }
+
+                // Every tape but the last one gets a small head, the last one gets the big one.
+                // A head out of sight stays at the edge of the view.
+                if k + 1 < count {
+                    let side = tri_size / 6.0;
+                    let top = Pos2::new(
+                        x(*head).min(view.right() - side).max(view.left() + side),
+                        row.y + self.tape_rect_size / 2.0 + 5.0,
+                    );
+                    ui.painter().add(egui::Shape::convex_polygon(
+                        vec![
+                            top,
+                            top + Vec2::new(side, side),
+                            top + Vec2::new(-side, side),
+                        ],
+                        self.tri_color,
+                        Stroke::NONE,
+                    ));
                 }
             }
 
+            if let Some((tape, e)) = edit {
+                self.edit_tape(tape, e);
+            }
+
+            // The head, state and messages are drawn below the last tape
+            let center = center + Vec2::new(0.0, (count - 1) as f32 * row_height);
+            let head = Vec2::new(
+                ((heads[count - 1] - self.centers[count - 1]) * size.x)
+                    .min(view.right() - center.x - tri_size / 2.0)
+                    .max(view.left() - center.x + tri_size / 2.0),
+                0.0,
+            );
+
             let height = if is_mobile(ui.ctx()) { 1.2 } else { 1.0 };
 
             let c1: Pos2 = center
+                + head
                 + Vec2::new(
                     height * tri_size / 1.75 - self.tri_stroke_wid * 2.0,
                     height * tri_size,
                 );
             let c2: Pos2 = center
+                + head
                 + Vec2::new(
                     -height * tri_size / 1.75 + self.tri_stroke_wid * 2.0,
                     height * tri_size,
                 );
-            let c3: Pos2 = center + Vec2::new(0.0, self.tape_rect_size / 3.0);
+            let c3: Pos2 = center + head + Vec2::new(0.0, self.tape_rect_size / 3.0);
 
             let circle_rad = tri_size / 2.0 + if is_mobile(ui.ctx()) { 0.0 } else { 0.5 };
-            let circle_center = center + Vec2::new(0.0, tri_size + 25.0);
+            let circle_center = center + head + Vec2::new(0.0, tri_size + 25.0);
 
             ui.painter().line_segment([c2, c3], self.tri_stroke);
             ui.painter().line_segment([c3, c1], self.tri_stroke);
@@ -301,12 +1015,32 @@ impl Widget for &mut TuringWidget {
             ui.painter().text(
                 circle_center,
                 Align2::CENTER_CENTER,
-                &self.tm.current_state,
+                self.tm.current_state(),
                 self.font_id.clone(),
                 Color32::BLACK,
             );
 
-            match self.tm.get_current_instruction() {
+            match self.tm.current_instruction() {
+                Some(_) if self.tm.fell_off() => {
+                    ui.painter().text(
+                        center + Vec2::new(0.0, self.tri_size + 100.0),
+                        Align2::CENTER_CENTER,
+                        t!("err.left_end", self.lang),
+                        font_id.clone(),
+                        Color32::LIGHT_RED,
+                    );
+                    self.paused = true;
+                }
+                Some(_) if self.is_inf_loop() => {
+                    ui.painter().text(
+                        center + Vec2::new(0.0, self.tri_size + 100.0),
+                        Align2::CENTER_CENTER,
+                        t!("err.infinite_loop", self.lang),
+                        font_id.clone(),
+                        Color32::LIGHT_RED,
+                    );
+                    self.paused = true;
+                }
                 Some(ins) => {
                     ui.painter().text(
                         center + Vec2::new(0.0, self.tri_size + 100.0),
@@ -326,20 +1060,11 @@ impl Widget for &mut TuringWidget {
                             Color32::LIGHT_RED,
                         );
                         self.paused = true;
-                    } else if self.tm.is_infinite_loop(self.threshold_inf_loop) {
-                        ui.painter().text(
-                            center + Vec2::new(0.0, self.tri_size + 100.0),
-                            Align2::CENTER_CENTER,
-                            t!("err.infinite_loop", self.lang),
-                            font_id.clone(),
-                            Color32::LIGHT_RED,
-                        );
-                        self.paused = true;
                     }
                 }
             };
 
-            if self.tm.finished() {
+            if self.tm.is_final() {
                 ui.painter().text(
                     center + Vec2::new(0.0, self.tri_size + 150.0),
                     Align2::CENTER_CENTER,
@@ -352,6 +1077,8 @@ impl Widget for &mut TuringWidget {
                     self.paused = true;
                 }
             }
+
+            return response;
         }
 
         ui.interact(
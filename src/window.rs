@@ -6,18 +6,22 @@ use std::{
 
 use crate::{
     console_err, console_log, console_warn,
+    convert::{self, Format},
+    fast_forward,
+    machine::{CompileError, MachineOutput, Simulator},
+    runner::{self, ExerciseTest},
+    turing_widget::{MAX_CELL_SIZE, MAX_TRAIL, MIN_CELL_SIZE},
     windows::{
-        AboutWindow, CompositionHelpWindow, DebugWindow, InfiniteLoopWindow, SecondaryWindow,
+        AboutWindow, CompositionHelpWindow, ComputationTreeWindow, CoverageWindow, DebugWindow,
+        InfiniteLoopWindow, LoopAction, ProfilerWindow, SecondaryWindow, SpaceTimeWindow,
         WorkbookEditorWindow, WorkbookWindow,
     },
-    TuringWidget,
+    CellHeat, ExerciseStatus, FastForward, Progress, TestRun, TuringWidget,
 };
 
 use eframe::egui::{self, Id, RichText, TextEdit, Ui};
 use eframe::epaint::Color32;
 use internationalization::t;
-use turing_lib::TuringOutput;
-use turing_lib::{CompilerError, TuringMachine};
 
 #[cfg(not(target_family = "wasm"))]
 use {
@@ -35,6 +39,8 @@ use {crate::get_lang, poll_promise::Promise, wasm_bindgen::prelude::wasm_bindgen
 
 const DEFAULT_CODE: &str = include_str!("../Examples/Example1.tm");
 const MOBILE_THRESHOLD: f32 = 500.0;
+/// Maximum time (in seconds) between two frames that is counted as time spent on an exercise
+const MAX_FRAME_GAP: f64 = 30.0;
 
 pub fn is_mobile(ctx: &egui::Context) -> bool {
     ctx.screen_rect().width() < MOBILE_THRESHOLD
@@ -75,8 +81,14 @@ impl Display for Language {
 
 pub struct MyApp {
     code: String,
-    error: Option<CompilerError>,
+    error: Option<CompileError>,
     tm: TuringWidget,
+    /// The machine running to completion in the background, if any
+    fast_forward: Option<FastForward>,
+    /// Lines of the code (starting at 1) highlighted in the editor, e.g. the ones of a cycle
+    highlighted_lines: Vec<usize>,
+    /// Lines of the code (starting at 1) of the transitions that were never used
+    uncovered_lines: Vec<usize>,
 
     // Windows
     about_window: Option<Box<AboutWindow>>,
@@ -85,6 +97,20 @@ pub struct MyApp {
     book_window: Option<Box<WorkbookWindow>>,
     workbook_editor_window: Option<Box<WorkbookEditorWindow>>,
     composition_help_window: Option<Box<CompositionHelpWindow>>,
+    tree_window: Option<Box<ComputationTreeWindow>>,
+    profiler_window: Option<Box<ProfilerWindow>>,
+    coverage_window: Option<Box<CoverageWindow>>,
+    space_time_window: Option<Box<SpaceTimeWindow>>,
+
+    progress: Progress,
+    /// The workbook and exercise the student is currently solving, if any
+    current_exercise: Option<(String, (usize, usize))>,
+    /// Tests the code must pass for the current exercise to be solved
+    exercise_tests: Vec<ExerciseTest>,
+    /// The tests running in the background since the machine finished, with the workbook and
+    /// exercise they belong to
+    solved_check: Option<(String, (usize, usize), TestRun)>,
+    last_frame_time: Option<f64>,
 
     #[cfg(not(target_family = "wasm"))]
     config: Config,
@@ -107,7 +133,7 @@ impl MyApp {
     pub fn new(
         file: &Option<PathBuf>,
         cc: &eframe::CreationContext<'_>,
-    ) -> Result<Self, CompilerError> {
+    ) -> Result<Self, CompileError> {
         let code = if cfg!(target_family = "wasm") {
             DEFAULT_CODE.to_string()
         } else {
@@ -131,7 +157,7 @@ impl MyApp {
             }
         };
 
-        let (tm, warnings) = match TuringMachine::new(&code) {
+        let (tm, warnings) = match Simulator::new(&code) {
             Ok((t, warnings)) => {
                 for w in &warnings {
                     console_warn!("\tCompiler warning: {:?}", w)
@@ -169,15 +195,28 @@ impl MyApp {
             };
 
             Ok(Self {
-                code: String::from(&tm.code),
+                code: String::from(tm.code()),
                 error: None,
                 tm: TuringWidget::new(tm, warnings).set_config(&config),
+                fast_forward: None,
+                highlighted_lines: vec![],
+                uncovered_lines: vec![],
                 about_window: None,
                 debug_window: None,
                 infinite_loop_window: None,
                 book_window: None,
                 workbook_editor_window: None,
                 composition_help_window: None,
+                tree_window: None,
+                profiler_window: None,
+                coverage_window: None,
+                space_time_window: None,
+
+                progress: Progress::load().unwrap_or_default(),
+                current_exercise: None,
+                exercise_tests: vec![],
+                solved_check: None,
+                last_frame_time: None,
 
                 config,
                 autosave: file.is_some() && config.autosave_disabled(),
@@ -190,15 +229,28 @@ impl MyApp {
         #[cfg(target_family = "wasm")]
         {
             Ok(Self {
-                code: String::from(&tm.code),
+                code: String::from(tm.code()),
                 error: None,
                 tm: TuringWidget::new(tm, warnings),
+                fast_forward: None,
+                highlighted_lines: vec![],
+                uncovered_lines: vec![],
                 about_window: None,
                 debug_window: None,
                 infinite_loop_window: None,
                 book_window: None,
                 workbook_editor_window: None,
                 composition_help_window: None,
+                tree_window: None,
+                profiler_window: None,
+                coverage_window: None,
+                space_time_window: None,
+
+                progress: Progress::load().unwrap_or_default(),
+                current_exercise: None,
+                exercise_tests: vec![],
+                solved_check: None,
+                last_frame_time: None,
 
                 lang: get_lang(),
 
@@ -227,13 +279,13 @@ impl MyApp {
     /// # Arguments
     /// * _ui - A mutable reference to the egui Ui, used to build and update the user interface.
     /// * ctx - A reference to the egui::Context, providing access to the UI context.
-    /// * error - A reference to a pest::error::Error, containing information about the parsing error.
+    /// * error - A reference to the compilation error, containing information about the parsing error.
     ///
     /// The error panel is displayed at the bottom of the UI, showing the line and column numbers
     /// where the error occurred, the erroneous input, and a message describing the expected input or
     /// the reason for the error. The panel uses different text colors and sizes to improve readability
     /// and highlight the most important information.
-    fn handle_error(_ui: &mut Ui, ctx: &egui::Context, error: &CompilerError) {
+    fn handle_error(_ui: &mut Ui, ctx: &egui::Context, error: &CompileError) {
         egui::TopBottomPanel::bottom("error").show(ctx, |ui| {
             egui::Frame::none()
                 .fill(Color32::BLACK)
@@ -332,6 +384,7 @@ impl MyApp {
                 ctx.clear_animations();
 
                 let target = self.tm.step();
+                self.check_solved();
 
                 ctx.animate_value_with_time(Id::new("offset"), target, self.tm.tape_anim_speed);
                 return true;
@@ -342,6 +395,37 @@ impl MyApp {
         .inner
     }
 
+    /// Once the machine has finished, starts running the tests of the current exercise in the
+    /// background to find out if it is solved. Exercises without tests cannot be checked, so they
+    /// stay attempted.
+    fn check_solved(&mut self) {
+        if let Some((workbook, exercise)) = &self.current_exercise {
+            if self.solved_check.is_none()
+                && !self.exercise_tests.is_empty()
+                && self.tm.finished()
+                && self.tm.code() == self.code
+                && self.progress.status(workbook, *exercise) != ExerciseStatus::Solved
+            {
+                let tests = TestRun::start(self.code.clone(), self.exercise_tests.clone());
+                self.solved_check = Some((workbook.clone(), *exercise, tests));
+            }
+        }
+    }
+
+    /// Pauses the machine and opens the window that explains its cycle, if it has been found
+    fn report_cycle(&mut self) {
+        if let Some(report) = self.tm.cycle_report() {
+            console_warn!("Infinite loop detected: {:?}", report.cycle);
+
+            self.infinite_loop_window = Some(Box::new(InfiniteLoopWindow::new(
+                &self.get_lang(),
+                report.clone(),
+                self.tm.blank(),
+            )));
+            self.tm.paused = true;
+        }
+    }
+
     /// This method restarts the Turing machine with the provided code. It attempts to parse the new code
     /// and update the Turing machine's state accordingly. If the parsing is successful, the Turing machine
     /// is updated and any previous error information is cleared. If an error occurs during parsing, the error
@@ -354,6 +438,9 @@ impl MyApp {
     /// error occurs. It also updates the code field with the provided code and manages the error field
     /// based on the success or failure of parsing the new code.
     pub fn restart(&mut self, code: &str) {
+        self.fast_forward = None;
+        self.highlighted_lines.clear();
+        self.uncovered_lines.clear();
         self.tm = match self.tm.restart(code) {
             Ok(t) => {
                 self.error = None;
@@ -367,6 +454,26 @@ impl MyApp {
         self.code = String::from(code);
     }
 
+    /// Replaces the code of a multi-tape machine with the code of an equivalent single-tape machine.
+    /// The new code is not associated with the previous file, so that autosave does not overwrite it.
+    fn convert_to_single_tape(&mut self) {
+        match self.tm.single_tape_code() {
+            Some(Ok(code)) => {
+                #[cfg(not(target_family = "wasm"))]
+                {
+                    self.file = None;
+                }
+                self.current_exercise = None;
+                self.exercise_tests.clear();
+                self.restart(&code);
+            }
+            Some(Err(e)) => {
+                console_err!("Cannot convert the machine to a single tape: {}", e);
+            }
+            None => {}
+        }
+    }
+
     /// The method checks if a file is associated with the Turing machine's code. If there is an associated
     /// file, it attempts to create and write the file with the current code. If the write operation is
     /// successful, the method returns the current Instant. If an error occurs during the save operation,
@@ -580,6 +687,9 @@ impl MyApp {
 
         if let Some(file) = res {
             let unparsed_file = std::fs::read_to_string(file).expect("cannot read file");
+            self.current_exercise = None;
+            self.exercise_tests.clear();
+            self.fast_forward = None;
             self.tm = match self.tm.restart(&unparsed_file) {
                 Ok(t) => {
                     self.error = None;
@@ -594,6 +704,106 @@ impl MyApp {
         }
     }
 
+    /// Spawns a dialog to pick a machine written for another simulator and returns its code
+    /// converted to the `.tm` syntax
+    #[cfg(target_family = "wasm")]
+    async fn import_file(format: Format) -> Option<String> {
+        let file = rfd::AsyncFileDialog::new()
+            .add_filter(format.name(), &[format.extension()])
+            .pick_file()
+            .await?;
+
+        let text = match String::from_utf8(file.read().await) {
+            Ok(s) => s,
+            Err(e) => {
+                console_err!("Error reading file: {:?}", e);
+                return None;
+            }
+        };
+
+        match convert::convert(&text, format, Format::Tm) {
+            Ok(code) => Some(code),
+            Err(e) => {
+                console_err!("Cannot import the machine: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Spawns a dialog to pick a machine written for another simulator and loads its code
+    /// converted to the `.tm` syntax. The code is not associated with any file, so that saving it
+    /// does not overwrite the imported one.
+    #[cfg(not(target_family = "wasm"))]
+    fn import_file(&mut self, format: Format) {
+        let path = std::env::current_dir().unwrap();
+
+        let file = match rfd::FileDialog::new()
+            .add_filter(format.name(), &[format.extension()])
+            .set_directory(path)
+            .pick_file()
+        {
+            Some(f) => f,
+            None => return,
+        };
+
+        let code = match std::fs::read_to_string(&file)
+            .map_err(|e| e.to_string())
+            .and_then(|text| convert::convert(&text, format, Format::Tm))
+        {
+            Ok(code) => code,
+            Err(e) => {
+                console_err!("Cannot import {:?}: {}", file, e);
+                return;
+            }
+        };
+
+        self.file = None;
+        self.current_exercise = None;
+        self.exercise_tests.clear();
+        self.restart(&code);
+    }
+
+    /// Converts the code to the format of another simulator and spawns a dialog to save it
+    fn export_file(&self, format: Format) {
+        let text = match convert::convert(&self.code, Format::Tm, format) {
+            Ok(text) => text,
+            Err(e) => {
+                console_err!("Cannot export the machine: {}", e);
+                return;
+            }
+        };
+
+        Self::save_text(
+            &text,
+            "my-turing-program",
+            format.name(),
+            format.extension(),
+        );
+    }
+
+    /// Asks where to save the text (or downloads it on the web) as a file of the given type
+    #[cfg_attr(target_family = "wasm", allow(unused_variables))]
+    fn save_text(text: &str, file_name: &str, type_name: &str, extension: &str) {
+        #[cfg(target_family = "wasm")]
+        downloadToFile(text, &format!("{}.{}", file_name, extension));
+
+        #[cfg(not(target_family = "wasm"))]
+        {
+            let path = std::env::current_dir().unwrap();
+
+            if let Some(f) = rfd::FileDialog::new()
+                .add_filter(type_name, &[extension])
+                .set_directory(path)
+                .set_file_name(format!("{}.{}", file_name, extension))
+                .save_file()
+            {
+                if let Err(e) = std::fs::write(&f, text) {
+                    console_err!("Cannot write {:?}: {}", f, e);
+                }
+            }
+        }
+    }
+
     /// Handles the display and behavior of various windows like the About window, Debugger window, Infinite Loop window, and the Book window.
     ///
     /// # Arguments
@@ -638,25 +848,49 @@ impl MyApp {
                 debug.set_lang(lang);
                 if self.error.is_none() {
                     debug.set_values(self.tm.tape_values(), self.tm.tape_value());
+                    debug.set_tapes(self.tm.tape_contents());
+                    debug.set_encoding(self.tm.encoding());
                 }
             }
         }
 
-        if let Some(inf_loop) = &self.infinite_loop_window {
-            if !inf_loop.show(ctx) {
+        if let Some(inf_loop) = self.infinite_loop_window.as_mut() {
+            inf_loop.set_lang(lang);
+            let (active, action) = inf_loop.show(ctx);
+
+            match action {
+                Some(LoopAction::JumpToStart(step)) => {
+                    // The cycle will be found again once the machine goes through it
+                    self.fast_forward = None;
+                    if let Err(e) = self.tm.rewind(step) {
+                        console_err!("Cannot run the machine again: {}", e);
+                    }
+                    self.infinite_loop_window = None;
+                }
+                Some(LoopAction::Highlight(lines)) => {
+                    self.highlighted_lines = lines;
+                }
+                None => {}
+            }
+
+            if !active {
                 self.infinite_loop_window = None;
+                self.tm.forget_cycle();
                 self.tm.paused = false;
-            } else if let Some(inf_loop) = &mut self.infinite_loop_window {
-                inf_loop.set_lang(lang);
+            } else if self.infinite_loop_window.is_some() {
                 self.tm.paused = true;
-                self.tm.reset_frequencies();
             }
         }
 
         if let Some(book) = self.book_window.as_mut() {
-            let (active, code) = book.show(ctx);
+            let (active, code) = book.show(ctx, &mut self.progress);
 
             if let Some(c) = code {
+                let (workbook, exercise) = book.current_exercise();
+                self.progress.mark_attempted(&workbook, exercise);
+                self.current_exercise = Some((workbook, exercise));
+                self.exercise_tests = book.current_tests();
+
                 self.restart(&c);
                 self.debug_window = Some(Box::new(DebugWindow::new(
                     lang,
@@ -681,6 +915,70 @@ impl MyApp {
                 self.composition_help_window = None;
             }
         }
+
+        if let Some(profiler) = self.profiler_window.as_mut() {
+            profiler.set_lang(lang);
+            let (active, csv) = profiler.show(ctx);
+
+            if let Some(csv) = csv {
+                Self::save_text(&csv, "profile", "CSV", "csv");
+            }
+
+            if !active {
+                self.profiler_window = None;
+            }
+        }
+
+        if let Some(coverage) = self.coverage_window.as_mut() {
+            coverage.set_lang(lang);
+            let (active, lines) = coverage.show(
+                ctx,
+                self.tm.coverage(),
+                self.tm.code(),
+                &self.exercise_tests,
+            );
+
+            if let Some(lines) = lines {
+                self.uncovered_lines = lines;
+            }
+
+            if !active {
+                self.coverage_window = None;
+            }
+        }
+
+        if let Some(space_time) = self.space_time_window.as_mut() {
+            space_time.set_lang(lang);
+            let (active, step) = space_time.show(ctx, self.tm.space_time());
+
+            if let Some(step) = step {
+                self.fast_forward = None;
+                if let Err(e) = self.tm.rewind(step) {
+                    console_err!("Cannot run the machine again: {}", e);
+                }
+            }
+
+            if !active {
+                self.space_time_window = None;
+            }
+        }
+
+        if let Some(tree) = self.tree_window.as_mut() {
+            tree.set_lang(lang);
+            let (active, path) = tree.show(ctx);
+
+            if let Some(path) = path {
+                // Replay the branch on the code that was explored, even if it was edited since
+                let code = tree.code().to_string();
+                self.restart(&code);
+                self.tm.follow(path);
+                self.tm.paused = true;
+            }
+
+            if !active {
+                self.tree_window = None;
+            }
+        }
     }
 
     /// Draws the top panel containing the menu with options for file handling, debugger, exercises, language, and about information.
@@ -738,6 +1036,72 @@ impl MyApp {
                                     downloadToFile(&self.code, "my-turing-program.tm");
                                 }
 
+                                ui.separator();
+
+                                ui.menu_button(t!("menu.file.import", lang), |ui| {
+                                    for format in convert::FOREIGN_FORMATS {
+                                        if ui
+                                            .button(format!(
+                                                "{} (.{})",
+                                                format.name(),
+                                                format.extension()
+                                            ))
+                                            .on_hover_text_at_pointer(t!(
+                                                "tooltip.file.import",
+                                                lang
+                                            ))
+                                            .clicked()
+                                        {
+                                            #[cfg(target_family = "wasm")]
+                                            {
+                                                self.file_request_future =
+                                                    Some(poll_promise::Promise::spawn_local(
+                                                        Self::import_file(format),
+                                                    ));
+                                            }
+
+                                            #[cfg(not(target_family = "wasm"))]
+                                            self.import_file(format);
+
+                                            ui.close_menu();
+                                        }
+                                    }
+                                });
+
+                                ui.add_enabled_ui(self.error.is_none(), |ui| {
+                                    ui.menu_button(t!("menu.file.export", lang), |ui| {
+                                        for format in convert::FOREIGN_FORMATS {
+                                            if ui
+                                                .button(format!(
+                                                    "{} (.{})",
+                                                    format.name(),
+                                                    format.extension()
+                                                ))
+                                                .on_hover_text_at_pointer(t!(
+                                                    "tooltip.file.export",
+                                                    lang
+                                                ))
+                                                .clicked()
+                                            {
+                                                self.export_file(format);
+                                                ui.close_menu();
+                                            }
+                                        }
+                                    });
+                                });
+
+                                if ui
+                                    .add_enabled(
+                                        self.error.is_none() && self.tm.tape_count() > 1,
+                                        egui::Button::new(t!("menu.file.single_tape", lang)),
+                                    )
+                                    .on_hover_text_at_pointer(t!("tooltip.file.single_tape", lang))
+                                    .clicked()
+                                {
+                                    self.convert_to_single_tape();
+                                    ui.close_menu();
+                                }
+
                                 #[cfg(not(target_family = "wasm"))]
                                 ui.add_enabled_ui(self.file.is_some(), |ui| {
                                     let prev = self.autosave;
@@ -758,13 +1122,68 @@ impl MyApp {
                                 )));
                             }
 
+                            if ui
+                                .add_enabled(
+                                    self.error.is_none() && self.tm.is_nondeterministic(),
+                                    egui::Button::new(t!("menu.tree", lang)),
+                                )
+                                .on_hover_text_at_pointer(t!("tooltip.tree", lang))
+                                .clicked()
+                                && self.tree_window.is_none()
+                            {
+                                if let Some(program) = self.tm.program() {
+                                    self.tree_window =
+                                        Some(Box::new(ComputationTreeWindow::new(lang, program)));
+                                }
+                            }
+
+                            if ui
+                                .add_enabled(
+                                    self.error.is_none(),
+                                    egui::Button::new(t!("menu.profiler", lang)),
+                                )
+                                .on_hover_text_at_pointer(t!("tooltip.profiler", lang))
+                                .clicked()
+                                && self.profiler_window.is_none()
+                            {
+                                self.profiler_window =
+                                    Some(Box::new(ProfilerWindow::new(lang, self.tm.code())));
+                            }
+
+                            if ui
+                                .add_enabled(
+                                    self.error.is_none(),
+                                    egui::Button::new(t!("menu.coverage", lang)),
+                                )
+                                .on_hover_text_at_pointer(t!("tooltip.coverage", lang))
+                                .clicked()
+                                && self.coverage_window.is_none()
+                            {
+                                self.coverage_window = Some(Box::new(CoverageWindow::new(lang)));
+                            }
+
+                            if ui
+                                .add_enabled(
+                                    self.error.is_none(),
+                                    egui::Button::new(t!("menu.space_time", lang)),
+                                )
+                                .on_hover_text_at_pointer(t!("tooltip.space_time", lang))
+                                .clicked()
+                                && self.space_time_window.is_none()
+                            {
+                                self.space_time_window =
+                                    Some(Box::new(SpaceTimeWindow::new(lang)));
+                            }
+
                             if cfg!(feature = "teacher") {
                                 ui.menu_button(t!("menu.exercises", lang), |ui| {
                                     if ui.button(t!("menu.exercises", lang)).clicked()
                                         && self.book_window.is_none()
                                     {
-                                        self.book_window =
-                                            Some(Box::new(WorkbookWindow::new(&self.get_lang())));
+                                        self.book_window = Some(Box::new(WorkbookWindow::new(
+                                            &self.get_lang(),
+                                            &self.progress,
+                                        )));
                                     }
 
                                     if ui.button(t!("menu.exercises.editor", lang)).clicked()
@@ -778,8 +1197,10 @@ impl MyApp {
                             } else if ui.button(t!("menu.exercises", lang)).clicked()
                                 && self.book_window.is_none()
                             {
-                                self.book_window =
-                                    Some(Box::new(WorkbookWindow::new(&self.get_lang())));
+                                self.book_window = Some(Box::new(WorkbookWindow::new(
+                                    &self.get_lang(),
+                                    &self.progress,
+                                )));
                             }
 
                             ui.menu_button(t!("menu.language", lang), |ui| {
@@ -848,21 +1269,12 @@ impl MyApp {
     /// Draws the side panel containing the file open/save buttons, compile button, and code editor.
     /// It also handles autosaving and displays a "Saved file" feedback popup if applicable.
     ///
-    /// # Returns
-    ///
-    /// The x-coordinate of the right side of the side panel.
-    ///
     /// # Arguments
     ///
     /// * ctx - An egui::Context object required for creating and displaying UI components.
     /// * lang - A string representing the language used for displaying text in the UI.
     /// * editor_focused - A mutable reference to a boolean indicating whether the code editor is currently focused.
-    fn draw_side_panel(
-        &mut self,
-        ctx: &egui::Context,
-        lang: &str,
-        editor_focused: &mut bool,
-    ) -> f32 {
+    fn draw_side_panel(&mut self, ctx: &egui::Context, lang: &str, editor_focused: &mut bool) {
         let contents = |ui: &mut egui::Ui| {
             ui.vertical_centered_justified(|ui| {
                 ui.add_space(10.0);
@@ -907,6 +1319,7 @@ impl MyApp {
                     .button(egui::RichText::new(t!("btn.compile", lang)).strong())
                     .clicked()
                 {
+                    self.fast_forward = None;
                     self.tm = match self.tm.restart(&self.code) {
                         Ok(t) => {
                             self.error = None;
@@ -965,12 +1378,48 @@ impl MyApp {
                 egui::ScrollArea::vertical()
                     .max_height(ui.available_height() - 50.0)
                     .show(ui, |my_ui: &mut Ui| {
-                        let editor = TextEdit::multiline(&mut self.code)
+                        let highlighted_lines = &self.highlighted_lines;
+                        let uncovered_lines = &self.uncovered_lines;
+                        let mut layouter = |ui: &Ui, text: &str, wrap_width: f32| {
+                            let mut job = egui::text::LayoutJob::default();
+
+                            for (i, line) in text.split_inclusive('\n').enumerate() {
+                                job.append(
+                                    line,
+                                    0.0,
+                                    egui::TextFormat {
+                                        font_id: egui::TextStyle::Monospace.resolve(ui.style()),
+                                        color: ui.visuals().widgets.inactive.text_color(),
+                                        background: if highlighted_lines.contains(&(i + 1)) {
+                                            Color32::from_rgba_unmultiplied(148, 73, 141, 90)
+                                        } else if uncovered_lines.contains(&(i + 1)) {
+                                            Color32::from_rgba_unmultiplied(220, 50, 47, 70)
+                                        } else {
+                                            Color32::TRANSPARENT
+                                        },
+                                        ..Default::default()
+                                    },
+                                );
+                            }
+                            job.wrap.max_width = wrap_width;
+
+                            ui.fonts(|f| f.layout_job(job))
+                        };
+
+                        let mut editor = TextEdit::multiline(&mut self.code)
                             .code_editor()
-                            .desired_width(0.0)
-                            .show(my_ui);
+                            .desired_width(0.0);
+                        if !highlighted_lines.is_empty() || !uncovered_lines.is_empty() {
+                            editor = editor.layouter(&mut layouter);
+                        }
+                        let editor = editor.show(my_ui);
 
                         let res = editor.response;
+                        if res.changed() {
+                            // The lines may not be the same anymore
+                            self.highlighted_lines.clear();
+                            self.uncovered_lines.clear();
+                        }
 
                         // Autosave only works on desktop
                         #[cfg(not(target_family = "wasm"))]
@@ -980,6 +1429,16 @@ impl MyApp {
                             self.saved_feedback = self.auto_save_file();
                         }
 
+                        if let Some((workbook, exercise)) = &self.current_exercise {
+                            if res.changed() {
+                                self.progress.set_code(workbook, *exercise, &self.code);
+                            }
+
+                            if res.lost_focus() {
+                                self.progress.save();
+                            }
+                        }
+
                         *editor_focused = res.has_focus();
 
                         // FIXME: Does not work because TextEdit is lacking the Sense(click)
@@ -1015,18 +1474,13 @@ impl MyApp {
                 .default_pos(egui::pos2(0.0, 0.0))
                 .constrain(true)
                 .show(ctx, contents);
-            0.0
         } else {
-            egui::SidePanel::left("left")
-                .show(ctx, contents)
-                .response
-                .rect
-                .right()
+            egui::SidePanel::left("left").show(ctx, contents);
         }
     }
 
-    /// Draws the central panel containing the Turing machine description, sliders for tape size, animation speed,
-    /// and infinite loop threshold, as well as the current output and playback controls.
+    /// Draws the central panel containing the Turing machine description, sliders for the animation speed and the tape overlays,
+    /// as well as the current output and playback controls.
     ///
     /// # Arguments
     ///
@@ -1048,43 +1502,74 @@ impl MyApp {
                         }
 
                         let mut sliders = |ui: &mut egui::Ui| {
-                            let _prev_tape_size = self.tm.tape_rect_size;
                             let _prev_tape_speed = self.tm.tape_anim_speed;
-                            let _prev_threshold_inf_loop = self.tm.threshold_inf_loop;
 
-                            ui.add(
-                                egui::Slider::new(&mut self.tm.tape_rect_size, 25.0..=300.0)
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::Slider::new(&mut self.tm.tape_anim_speed, 0.2..=2.0)
+                                        .suffix(t!("lbl.seconds", lang))
+                                        .text(t!("lbl.tape.speed", lang)),
+                                )
+                                .on_hover_text_at_pointer(t!("tooltip.tape.duration", lang));
+
+                                ui.add(
+                                    egui::Slider::new(
+                                        &mut self.tm.tape_rect_size,
+                                        MIN_CELL_SIZE..=MAX_CELL_SIZE,
+                                    )
                                     .suffix(" px")
                                     .text(t!("lbl.tape.size", lang)),
-                            )
-                            .on_hover_text_at_pointer(t!("tooltip.tape.size", lang));
-                            ui.add(
-                                egui::Slider::new(&mut self.tm.tape_anim_speed, 0.2..=2.0)
-                                    .suffix(t!("lbl.seconds", lang))
-                                    .text(t!("lbl.tape.speed", lang)),
-                            )
-                            .on_hover_text_at_pointer(t!("tooltip.tape.duration", lang));
-                            ui.add(
-                                egui::Slider::new(&mut self.tm.threshold_inf_loop, 10..=2000)
-                                    .suffix(t!("lbl.iterations", lang))
-                                    .text(t!("lbl.tape.inf_loop", lang)),
-                            )
-                            .on_hover_text_at_pointer(t!("tooltip.tape.iterations", lang));
+                                )
+                                .on_hover_text_at_pointer(t!("tooltip.tape.size", lang));
 
-                            #[cfg(not(target_family = "wasm"))]
-                            {
-                                if _prev_tape_size != self.tm.tape_rect_size {
-                                    self.config.set_tape_size(self.tm.tape_rect_size);
-                                }
+                                ui.checkbox(&mut self.tm.follow_head, t!("lbl.tape.follow", lang))
+                                    .on_hover_text_at_pointer(t!("tooltip.tape.follow", lang));
+                            });
 
-                                if _prev_tape_speed != self.tm.tape_anim_speed {
-                                    self.config.set_tape_speed(self.tm.tape_anim_speed);
-                                }
+                            let overlays = &mut self.tm.overlays;
+                            ui.horizontal(|ui| {
+                                egui::ComboBox::from_id_source("tape_heat")
+                                    .selected_text(match overlays.heat {
+                                        CellHeat::None => t!("lbl.tape.heat.none", lang),
+                                        CellHeat::Visits => t!("lbl.tape.heat.visits", lang),
+                                        CellHeat::Writes => t!("lbl.tape.heat.writes", lang),
+                                    })
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(
+                                            &mut overlays.heat,
+                                            CellHeat::None,
+                                            t!("lbl.tape.heat.none", lang),
+                                        );
+                                        ui.selectable_value(
+                                            &mut overlays.heat,
+                                            CellHeat::Visits,
+                                            t!("lbl.tape.heat.visits", lang),
+                                        );
+                                        ui.selectable_value(
+                                            &mut overlays.heat,
+                                            CellHeat::Writes,
+                                            t!("lbl.tape.heat.writes", lang),
+                                        );
+                                    })
+                                    .response
+                                    .on_hover_text_at_pointer(t!("tooltip.tape.heat", lang));
+
+                                ui.add(
+                                    egui::Slider::new(&mut overlays.trail, 0..=MAX_TRAIL)
+                                        .text(t!("lbl.tape.trail", lang)),
+                                )
+                                .on_hover_text_at_pointer(t!("tooltip.tape.trail", lang));
+
+                                ui.checkbox(
+                                    &mut overlays.last_write,
+                                    t!("lbl.tape.last_write", lang),
+                                )
+                                .on_hover_text_at_pointer(t!("tooltip.tape.last_write", lang));
+                            });
 
-                                if _prev_threshold_inf_loop != self.tm.threshold_inf_loop {
-                                    self.config
-                                        .set_threshold_inf_loop(self.tm.threshold_inf_loop);
-                                }
+                            #[cfg(not(target_family = "wasm"))]
+                            if _prev_tape_speed != self.tm.tape_anim_speed {
+                                self.config.set_tape_speed(self.tm.tape_anim_speed);
                             }
                         };
 
@@ -1105,22 +1590,74 @@ impl MyApp {
                     ui.spacing();
 
                     match &self.tm.tape_value() {
-                        TuringOutput::Undefined(_) => {
+                        MachineOutput::Undefined(_) => {
                             ui.label(t!("lbl.undefined", lang));
                         }
-                        TuringOutput::Defined((_, out)) => {
+                        MachineOutput::Value((_, out)) => {
                             ui.label(t!("lbl.current_output", out: &out.to_string(), lang));
                         }
+                        MachineOutput::Word((_, out)) => {
+                            ui.label(t!("lbl.current_output", out: out, lang));
+                        }
+                        MachineOutput::Accepted(_) => {
+                            ui.label(
+                                RichText::new(t!("lbl.accepted", lang))
+                                    .strong()
+                                    .color(Color32::LIGHT_GREEN),
+                            );
+                        }
+                        MachineOutput::Rejected(_) => {
+                            ui.label(
+                                RichText::new(t!("lbl.rejected", lang))
+                                    .strong()
+                                    .color(Color32::LIGHT_RED),
+                            );
+                        }
                     }
 
                     ui.spacing();
                     ui.spacing();
 
                     ui.vertical_centered(|ui| {
+                        if let Some(run) = &self.fast_forward {
+                            let now = ctx.input(|i| i.time);
+
+                            ui.horizontal(|ui| {
+                                ui.add_space(ui.available_width() / 2.0 - 200.0);
+                                ui.spinner();
+                                ui.label(t!(
+                                    "lbl.fast_forward.steps",
+                                    steps: &run.steps().to_string(),
+                                    lang
+                                ));
+                                ui.label(t!(
+                                    "lbl.fast_forward.speed",
+                                    speed: &format!("{:.0}", run.steps_per_second(now)),
+                                    lang
+                                ));
+                            });
+                            if ui
+                                .button(t!("btn.fast_forward.cancel", lang))
+                                .on_hover_text_at_pointer(t!("tooltip.fast_forward.cancel", lang))
+                                .clicked()
+                            {
+                                run.cancel();
+                            }
+
+                            return;
+                        }
+
                         let mut text = t!("lbl.pause", lang);
                         if self.tm.finished() {
                             ui.label(t!("lbl.finished", lang));
                             text = t!("lbl.restart", lang)
+                        } else if self.tm.step_limit_reached() {
+                            ui.label(t!(
+                                "lbl.fast_forward.limit",
+                                steps: &fast_forward::MAX_STEPS.to_string(),
+                                lang
+                            ));
+                            text = t!("lbl.resume", lang);
                         } else if self.tm.paused {
                             ui.label(t!("lbl.paused", lang));
                             text = t!("lbl.resume", lang);
@@ -1129,11 +1666,37 @@ impl MyApp {
                         }
 
                         ui.horizontal(|ui| {
-                            ui.add_space(ui.available_width() / 2.0 - 115.0);
+                            ui.add_space(ui.available_width() / 2.0 - 190.0);
                             let b = ui
                                 .button(text)
                                 .on_hover_text_at_pointer(t!("tooltip.button.playpause", lang));
 
+                            if ui
+                                .add_enabled(
+                                    !self.tm.finished() && self.error.is_none(),
+                                    egui::Button::new(t!("btn.fast_forward", lang)),
+                                )
+                                .on_hover_text_at_pointer(t!("tooltip.fast_forward", lang))
+                                .clicked()
+                            {
+                                self.fast_forward =
+                                    Some(self.tm.fast_forward(ctx.input(|i| i.time)));
+                            }
+
+                            if self.tm.tape_edited()
+                                && ui
+                                    .button(t!("btn.tape.write", lang))
+                                    .on_hover_text_at_pointer(t!("tooltip.tape.write", lang))
+                                    .clicked()
+                            {
+                                let code = runner::with_tapes(&self.code, &self.tm.tape_inputs());
+                                self.restart(&code);
+
+                                if let Some((workbook, exercise)) = &self.current_exercise {
+                                    self.progress.set_code(workbook, *exercise, &self.code);
+                                }
+                            }
+
                             if (b.clicked()
                                 || ui.input_mut(|i| {
                                     i.consume_key(egui::Modifiers::NONE, egui::Key::Space)
@@ -1148,13 +1711,7 @@ impl MyApp {
                             }
                             if self.process_turing_controls(ui, ctx, editor_focused, lang) {
                                 ctx.request_repaint();
-                                if self.tm.is_inf_loop() {
-                                    console_warn!("Infinite loop detected!");
-
-                                    self.infinite_loop_window =
-                                        Some(Box::new(InfiniteLoopWindow::new(&self.get_lang())));
-                                    self.tm.paused = true;
-                                }
+                                self.report_cycle();
                             }
                         });
                     });
@@ -1162,6 +1719,19 @@ impl MyApp {
                     self.tm.lang = self.get_lang();
                     ui.add(&mut self.tm);
 
+                    // The size is saved once the zoom settles, not on every frame of the
+                    // mouse wheel, the pinch or the slider
+                    #[cfg(not(target_family = "wasm"))]
+                    if self.tm.tape_rect_size != self.config.tape_size()
+                        && ui.input(|i| {
+                            !i.pointer.any_down()
+                                && i.scroll_delta == egui::Vec2::ZERO
+                                && i.zoom_delta() == 1.0
+                        })
+                    {
+                        self.config.set_tape_size(self.tm.tape_rect_size);
+                    }
+
                     if let Some(e) = &self.error {
                         Self::handle_error(ui, ctx, e);
                     }
@@ -1211,6 +1781,7 @@ impl eframe::App for MyApp {
         if let Some(file_async) = &self.file_request_future {
             if let Some(file_result) = file_async.ready() {
                 if let Some(new_code) = file_result {
+                    self.fast_forward = None;
                     self.tm = match self.tm.restart(new_code) {
                         Ok(t) => {
                             self.error = None;
@@ -1222,6 +1793,8 @@ impl eframe::App for MyApp {
                         }
                     };
                     self.code = String::from(new_code);
+                    self.current_exercise = None;
+                    self.exercise_tests.clear();
                 }
 
                 self.file_request_future = None;
@@ -1288,15 +1861,46 @@ impl eframe::App for MyApp {
                 // Ctrl+R
                 console_log!("Restarting...");
 
+                self.fast_forward = None;
                 self.tm = self.tm.restart(&self.code).unwrap();
             }
         });
 
+        let now = ctx.input(|i| i.time);
+        if let (Some((workbook, exercise)), Some(last)) =
+            (&self.current_exercise, self.last_frame_time)
+        {
+            // Long pauses between frames (e.g. the window was minimized) are not counted
+            self.progress
+                .add_time(workbook, *exercise, (now - last).min(MAX_FRAME_GAP));
+        }
+        self.last_frame_time = Some(now);
+
+        if let Some(fast_forward) = self.fast_forward.as_mut() {
+            if let Some(run) = fast_forward.poll() {
+                self.tm.sync(run);
+                self.fast_forward = None;
+                self.report_cycle();
+                self.check_solved();
+            }
+            ctx.request_repaint();
+        }
+
+        if let Some((workbook, exercise, tests)) = self.solved_check.as_mut() {
+            if let Some(passed) = tests.poll() {
+                if passed {
+                    self.progress.mark_solved(workbook, *exercise);
+                }
+                self.solved_check = None;
+            }
+            ctx.request_repaint();
+        }
+
         self.handle_windows(ctx, &lang);
 
         self.draw_top_panel(ctx, &lang);
 
-        self.tm.left = self.draw_side_panel(ctx, &lang, &mut editor_focused);
+        self.draw_side_panel(ctx, &lang, &mut editor_focused);
 
         self.draw_central_panel(ctx, &lang, editor_focused);
     }
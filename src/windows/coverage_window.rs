@@ -0,0 +1,252 @@
+use eframe::egui::{self, Color32, RichText, Sense};
+use internationalization::t;
+
+use crate::{
+    machine::Coverage,
+    runner::{self, ExerciseTest},
+};
+
+/// Color of the transitions and states used the least
+const COLD: Color32 = Color32::from_rgb(70, 130, 180);
+/// Color of the transitions and states used the most
+const HOT: Color32 = Color32::from_rgb(220, 50, 47);
+const BAR_WIDTH: f32 = 120.0;
+
+/// The runs whose coverage is shown
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Source {
+    /// The run of the machine on the tape, since it started
+    Run,
+    /// Every test of the current exercise
+    Tests,
+}
+
+/// Shows how often every transition and state was used, as a heatmap, either in the current run
+/// or over the tests of the exercise
+#[derive(Debug, Clone)]
+pub struct CoverageWindow {
+    lang: String,
+    source: Source,
+    /// The coverage of the tests, with the code it was computed for
+    tests: Option<(String, Result<Coverage, String>)>,
+}
+
+/// The color of a count, from cold to hot. The scale is logarithmic, since the transitions of a
+/// loop are used many more times than the rest.
+fn heat(count: usize, max: usize) -> Color32 {
+    if count == 0 {
+        return Color32::GRAY;
+    }
+
+    let t = ((count as f32).ln_1p() / (max.max(1) as f32).ln_1p()).clamp(0.0, 1.0);
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) as u8;
+
+    Color32::from_rgb(
+        mix(COLD.r(), HOT.r()),
+        mix(COLD.g(), HOT.g()),
+        mix(COLD.b(), HOT.b()),
+    )
+}
+
+impl CoverageWindow {
+    pub fn new(lang: &str) -> Self {
+        Self {
+            lang: String::from(lang),
+            source: Source::Run,
+            tests: None,
+        }
+    }
+
+    pub fn set_lang(&mut self, lang: &str) {
+        self.lang = lang.to_string();
+    }
+
+    /// Runs the tests again if the code changed since they were last run
+    fn update_tests(&mut self, code: &str, tests: &[ExerciseTest]) {
+        if self.tests.as_ref().is_some_and(|(c, _)| c == code) {
+            return;
+        }
+
+        self.tests = Some((
+            code.to_string(),
+            runner::test_coverage(code, tests, runner::DEFAULT_STEP_LIMIT)
+                .map_err(|e| e.to_string()),
+        ));
+    }
+
+    /// Returns whether the window is still open and the lines of the code to mark as never used,
+    /// if the user asked to mark them (or to clear them, with an empty list)
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        run: &Coverage,
+        code: &str,
+        tests: &[ExerciseTest],
+    ) -> (bool, Option<Vec<usize>>) {
+        let mut active = true;
+        let mut lines = None;
+
+        if tests.is_empty() {
+            self.source = Source::Run;
+        }
+
+        egui::Window::new(t!("title.coverage", self.lang))
+            .id(egui::Id::new("coverage_window"))
+            .resizable(true)
+            .default_width(420.0)
+            .open(&mut active)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.radio_value(
+                        &mut self.source,
+                        Source::Run,
+                        t!("coverage.lbl.run", self.lang),
+                    );
+                    if ui
+                        .add_enabled(
+                            !tests.is_empty(),
+                            egui::RadioButton::new(
+                                self.source == Source::Tests,
+                                t!("coverage.lbl.tests", self.lang),
+                            ),
+                        )
+                        .on_disabled_hover_text(t!("coverage.lbl.no_tests", self.lang))
+                        .clicked()
+                    {
+                        self.source = Source::Tests;
+                    }
+                });
+
+                let coverage = match self.source {
+                    Source::Run => run.clone(),
+                    Source::Tests => {
+                        self.update_tests(code, tests);
+                        match &self.tests {
+                            Some((_, Ok(c))) => c.clone(),
+                            Some((_, Err(e))) => {
+                                ui.colored_label(Color32::LIGHT_RED, e.as_str());
+                                return;
+                            }
+                            None => return,
+                        }
+                    }
+                };
+
+                ui.separator();
+
+                egui::Grid::new("coverage_summary").show(ui, |ui| {
+                    ui.strong(t!("coverage.lbl.transitions", self.lang));
+                    ui.monospace(format!(
+                        "{}/{}",
+                        coverage.used_transitions(),
+                        coverage.transitions.len()
+                    ));
+                    ui.end_row();
+
+                    ui.strong(t!("coverage.lbl.states", self.lang));
+                    ui.monospace(format!(
+                        "{}/{}",
+                        coverage.used_states(),
+                        coverage.states.len()
+                    ));
+                    ui.end_row();
+
+                    ui.strong(t!("coverage.lbl.runs", self.lang));
+                    ui.monospace(coverage.runs.to_string());
+                    ui.end_row();
+                });
+
+                ui.separator();
+
+                let max_state = coverage.states.iter().map(|s| s.count).max().unwrap_or(0);
+                ui.horizontal_wrapped(|ui| {
+                    for s in &coverage.states {
+                        ui.label(
+                            RichText::new(format!(" {} · {} ", s.state, s.count))
+                                .monospace()
+                                .color(Color32::BLACK)
+                                .background_color(heat(s.count, max_state)),
+                        );
+                    }
+                });
+
+                ui.separator();
+
+                let max = coverage
+                    .transitions
+                    .iter()
+                    .map(|t| t.count)
+                    .max()
+                    .unwrap_or(0);
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("coverage_transitions")
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.strong(t!("coverage.lbl.line", self.lang));
+                                ui.strong(t!("coverage.lbl.transition", self.lang));
+                                ui.strong(t!("coverage.lbl.count", self.lang));
+                                ui.end_row();
+
+                                for transition in &coverage.transitions {
+                                    ui.monospace(
+                                        transition.line.map(|l| l.to_string()).unwrap_or_default(),
+                                    );
+
+                                    let instruction =
+                                        RichText::new(&transition.instruction).monospace();
+                                    if transition.count == 0 {
+                                        ui.label(instruction.color(Color32::LIGHT_RED))
+                                            .on_hover_text(t!("coverage.lbl.unused", self.lang));
+                                    } else {
+                                        ui.label(instruction);
+                                    }
+
+                                    ui.monospace(transition.count.to_string());
+
+                                    let (rect, _) = ui.allocate_exact_size(
+                                        egui::vec2(BAR_WIDTH, 12.0),
+                                        Sense::hover(),
+                                    );
+                                    let width = if max > 0 {
+                                        BAR_WIDTH * transition.count as f32 / max as f32
+                                    } else {
+                                        0.0
+                                    };
+                                    ui.painter().rect_filled(
+                                        egui::Rect::from_min_size(
+                                            rect.min,
+                                            egui::vec2(width, rect.height()),
+                                        ),
+                                        2.0,
+                                        heat(transition.count, max),
+                                    );
+                                    ui.end_row();
+                                }
+                            });
+                    });
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    let unused = coverage.unused_lines();
+                    if ui
+                        .add_enabled(
+                            !unused.is_empty(),
+                            egui::Button::new(t!("coverage.btn.mark", self.lang)),
+                        )
+                        .clicked()
+                    {
+                        lines = Some(unused);
+                    }
+
+                    if ui.button(t!("coverage.btn.clear", self.lang)).clicked() {
+                        lines = Some(vec![]);
+                    }
+                });
+            });
+
+        (active, lines)
+    }
+}
@@ -1,7 +1,7 @@
 use eframe::egui::{self, RichText};
 use egui_extras::{Column, TableBuilder};
 
-use turing_lib::TuringOutput;
+use crate::machine::{Encoding, MachineOutput};
 
 use super::SecondaryWindow;
 use internationalization::t;
@@ -10,7 +10,11 @@ use internationalization::t;
 pub struct DebugWindow {
     lang: String,
     pub tape_values: Option<Vec<String>>,
-    pub tape_value: Option<TuringOutput>,
+    pub tape_value: Option<MachineOutput>,
+    /// The contents of each tape, only shown for machines with several tapes
+    pub tapes: Vec<String>,
+    /// How the values are read from the tape
+    pub encoding: Option<Encoding>,
     position: egui::Pos2,
 }
 
@@ -18,21 +22,31 @@ impl DebugWindow {
     pub fn new(
         lang: &str,
         tape_values: Option<Vec<String>>,
-        tape_value: Option<TuringOutput>,
+        tape_value: Option<MachineOutput>,
         position: Option<egui::Pos2>,
     ) -> Self {
         Self {
             lang: String::from(lang),
             tape_values,
             tape_value,
+            tapes: vec![],
+            encoding: None,
             position: position.unwrap_or(egui::Pos2::new(100.0, 100.0)),
         }
     }
 
-    pub fn set_values(&mut self, tape_values: Vec<String>, tape_value: TuringOutput) {
+    pub fn set_values(&mut self, tape_values: Vec<String>, tape_value: MachineOutput) {
         self.tape_values = Some(tape_values);
         self.tape_value = Some(tape_value);
     }
+
+    pub fn set_tapes(&mut self, tapes: Vec<String>) {
+        self.tapes = tapes;
+    }
+
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = Some(encoding);
+    }
 }
 
 impl SecondaryWindow for DebugWindow {
@@ -94,6 +108,29 @@ impl SecondaryWindow for DebugWindow {
                                 });
                             });
                         });
+
+                    if let Some(encoding) = self.encoding {
+                        ui.label(t!("lbl.encoding", encoding: encoding.name(), self.lang));
+                    }
+
+                    if self.tapes.len() > 1 {
+                        ui.separator();
+
+                        egui::Grid::new("debug_tapes").striped(true).show(ui, |ui| {
+                            for (k, tape) in self.tapes.iter().enumerate() {
+                                ui.label(
+                                    RichText::new(t!(
+                                        "lbl.tape",
+                                        val: &usize::to_string(&(k + 1)),
+                                        self.lang
+                                    ))
+                                    .strong(),
+                                );
+                                ui.monospace(tape);
+                                ui.end_row();
+                            }
+                        });
+                    }
                 } else {
                     ui.label(t!("debug.lbl.no_values", self.lang));
                 }
@@ -4,12 +4,12 @@ use eframe;
 use eframe::egui::{self, RichText};
 use eframe::epaint::Color32;
 use internationalization::t;
-use turing_lib::{CompilerError, ErrorPosition};
 
+use crate::machine::{CompileError, ErrorSpan};
 use crate::window::Language;
 
 pub struct ErrorWindow {
-    error: CompilerError,
+    error: CompileError,
     file: Option<PathBuf>,
     line_msg: String,
     expected_msg: String,
@@ -18,7 +18,7 @@ pub struct ErrorWindow {
 
 impl ErrorWindow {
     pub fn new(
-        error: CompilerError,
+        error: CompileError,
         file: Option<PathBuf>,
         lang: Language,
         cc: &eframe::CreationContext<'_>,
@@ -30,7 +30,7 @@ impl ErrorWindow {
         st.spacing.item_spacing = egui::Vec2::new(10.0, 10.0);
         cc.egui_ctx.set_style(st);
 
-        let position: ErrorPosition = error.position();
+        let position: ErrorSpan = error.position();
 
         let line_msg = match position.end {
             Some(end) => format!(
@@ -1,27 +1,94 @@
-use super::SecondaryWindow;
 use eframe::egui::{self, RichText, Window};
 use internationalization::t;
 
+use crate::machine::{Cycle, CycleReport, TapeSnapshot};
+
+/// Cells shown on each side of the head in the snapshots of the tapes
+const SNAPSHOT_RADIUS: i64 = 6;
+
+/// What the user asked to do with the cycle that was found
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoopAction {
+    /// Run the machine again up to the given step, where the cycle starts
+    JumpToStart(usize),
+    /// Highlight these lines of the code (starting at 1)
+    Highlight(Vec<usize>),
+}
+
+/// Explains why the machine never halts, with the cycle it went through
 #[derive(Debug, Clone)]
 pub struct InfiniteLoopWindow {
     lang: String,
+    report: CycleReport,
+    blank: char,
 }
 
 impl InfiniteLoopWindow {
-    pub fn new(lang: &str) -> Self {
+    pub fn new(lang: &str, report: CycleReport, blank: char) -> Self {
         Self {
             lang: String::from(lang),
+            report,
+            blank,
         }
     }
-}
 
-impl SecondaryWindow for InfiniteLoopWindow {
-    fn set_lang(&mut self, lang: &str) {
+    pub fn set_lang(&mut self, lang: &str) {
         self.lang = lang.to_string();
     }
 
-    fn show(&self, ctx: &egui::Context) -> bool {
+    /// Writes the cells around the head of a tape, with the head between brackets
+    fn tape_text(&self, tape: &TapeSnapshot) -> String {
+        let end = tape.start + tape.cells.len() as i64 - 1;
+        let from = tape.head - SNAPSHOT_RADIUS;
+        let to = tape.head + SNAPSHOT_RADIUS;
+
+        let cells = (from..=to)
+            .map(|p| {
+                if p == tape.head {
+                    format!("[{}]", tape.get(p, self.blank))
+                } else {
+                    tape.get(p, self.blank).to_string()
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        // Mark the contents that do not fit in the snapshot
+        format!(
+            "{}{}{}",
+            if !tape.cells.is_empty() && tape.start < from {
+                "… "
+            } else {
+                ""
+            },
+            cells,
+            if !tape.cells.is_empty() && end > to {
+                " …"
+            } else {
+                ""
+            }
+        )
+    }
+
+    /// The lines of the code where the transitions of the cycle are written
+    fn lines(&self) -> Vec<usize> {
+        let mut lines: Vec<usize> = self
+            .report
+            .transitions
+            .iter()
+            .filter_map(|t| t.line)
+            .collect();
+        lines.sort_unstable();
+        lines.dedup();
+
+        lines
+    }
+
+    /// Returns whether the window is still open and what the user asked to do with the cycle
+    pub fn show(&self, ctx: &egui::Context) -> (bool, Option<LoopAction>) {
         let mut active = true;
+        let mut action = None;
+        let cycle = &self.report.cycle;
 
         Window::new("Oops!")
             .id(egui::Id::new("infinite_window"))
@@ -35,6 +102,129 @@ impl SecondaryWindow for InfiniteLoopWindow {
 
                         ui.separator();
 
+                        ui.label(
+                            RichText::new(match cycle {
+                                Cycle::Repeat { .. } => t!("lbl.inf_loop.repeat", self.lang),
+                                Cycle::Translated { .. } => {
+                                    t!("lbl.inf_loop.translated", self.lang)
+                                }
+                            })
+                            .size(15.0),
+                        );
+
+                        egui::Grid::new("inf_loop_steps").show(ui, |ui| {
+                            ui.label(RichText::new(t!("lbl.inf_loop.start", self.lang)).strong());
+                            ui.monospace(cycle.start().to_string());
+                            ui.end_row();
+
+                            ui.label(RichText::new(t!("lbl.inf_loop.end", self.lang)).strong());
+                            ui.monospace(cycle.end().to_string());
+                            ui.end_row();
+
+                            ui.label(RichText::new(t!("lbl.inf_loop.period", self.lang)).strong());
+                            ui.monospace(cycle.period().to_string());
+                            ui.end_row();
+
+                            if let Cycle::Translated { shift, .. } = cycle {
+                                ui.label(
+                                    RichText::new(if *shift > 0 {
+                                        t!("lbl.inf_loop.shift.right", self.lang)
+                                    } else {
+                                        t!("lbl.inf_loop.shift.left", self.lang)
+                                    })
+                                    .strong(),
+                                );
+                                ui.monospace(shift.abs().to_string());
+                                ui.end_row();
+                            }
+
+                            ui.label(RichText::new(t!("lbl.inf_loop.states", self.lang)).strong());
+                            ui.monospace(self.report.states.join(", "));
+                            ui.end_row();
+
+                            ui.label(
+                                RichText::new(t!("lbl.inf_loop.transitions", self.lang)).strong(),
+                            );
+                            ui.vertical(|ui| {
+                                for transition in &self.report.transitions {
+                                    match transition.line {
+                                        Some(line) => ui.monospace(format!(
+                                            "{}  {}",
+                                            transition.instruction,
+                                            t!(
+                                                "lbl.inf_loop.line",
+                                                line: &line.to_string(),
+                                                self.lang
+                                            )
+                                        )),
+                                        None => ui.monospace(&transition.instruction),
+                                    };
+                                }
+                            });
+                            ui.end_row();
+                        });
+
+                        if self.report.partial {
+                            ui.label(
+                                RichText::new(t!("lbl.inf_loop.partial", self.lang)).italics(),
+                            );
+                        }
+
+                        ui.separator();
+
+                        // The configurations at both ends of the cycle, side by side
+                        egui::Grid::new("inf_loop_tapes").show(ui, |ui| {
+                            ui.label("");
+                            ui.label(RichText::new(t!("lbl.inf_loop.first", self.lang)).strong());
+                            ui.label(RichText::new(t!("lbl.inf_loop.last", self.lang)).strong());
+                            ui.end_row();
+
+                            ui.label(RichText::new(t!("lbl.inf_loop.state", self.lang)).strong());
+                            ui.monospace(&self.report.first.state);
+                            ui.monospace(&self.report.last.state);
+                            ui.end_row();
+
+                            for (k, (first, last)) in self
+                                .report
+                                .first
+                                .tapes
+                                .iter()
+                                .zip(&self.report.last.tapes)
+                                .enumerate()
+                            {
+                                ui.label(
+                                    RichText::new(t!(
+                                        "lbl.tape",
+                                        val: &usize::to_string(&(k + 1)),
+                                        self.lang
+                                    ))
+                                    .strong(),
+                                );
+                                ui.monospace(self.tape_text(first));
+                                ui.monospace(self.tape_text(last));
+                                ui.end_row();
+                            }
+                        });
+
+                        ui.separator();
+
+                        ui.horizontal(|ui| {
+                            if ui.button(t!("btn.inf_loop.jump", self.lang)).clicked() {
+                                action = Some(LoopAction::JumpToStart(cycle.start()));
+                            }
+
+                            let lines = self.lines();
+                            if ui
+                                .add_enabled(
+                                    !lines.is_empty(),
+                                    egui::Button::new(t!("btn.inf_loop.highlight", self.lang)),
+                                )
+                                .clicked()
+                            {
+                                action = Some(LoopAction::Highlight(lines));
+                            }
+                        });
+
                         ui.label(
                             RichText::new(t!("lbl.inf_loop.description1", self.lang)).size(15.0),
                         );
@@ -45,6 +235,6 @@ impl SecondaryWindow for InfiniteLoopWindow {
                 });
             });
 
-        active
+        (active, action)
     }
 }
@@ -2,17 +2,27 @@ use eframe::egui;
 
 mod about_window;
 mod compsition_help_window;
+mod coverage_window;
 mod debug_window;
 mod error_window;
 mod infinite_loop_window;
+mod profiler_window;
+mod space_time_window;
+mod tree_window;
 mod workbook;
 
 pub use about_window::AboutWindow;
 pub use compsition_help_window::CompositionHelpWindow;
+pub use coverage_window::CoverageWindow;
 pub use debug_window::DebugWindow;
 pub use error_window::ErrorWindow;
-pub use infinite_loop_window::InfiniteLoopWindow;
-pub use workbook::{WorkbookEditorWindow, WorkbookWindow};
+pub use infinite_loop_window::{InfiniteLoopWindow, LoopAction};
+pub use profiler_window::ProfilerWindow;
+pub use space_time_window::SpaceTimeWindow;
+pub use tree_window::ComputationTreeWindow;
+#[cfg(not(target_family = "wasm"))]
+pub use workbook::read_workbook;
+pub use workbook::{serialize_workbook, Exercise, Workbook, WorkbookEditorWindow, WorkbookWindow};
 
 pub trait SecondaryWindow {
     fn set_lang(&mut self, lang: &str);
@@ -0,0 +1,264 @@
+use eframe::egui::{self, Align2, Color32, FontId, Pos2, RichText, Sense, Shape, Stroke};
+use internationalization::t;
+
+use crate::{
+    equivalence,
+    profiler::{self, Fit, Growth, Profile, Sample},
+    runner,
+};
+
+const PLOT_HEIGHT: f32 = 220.0;
+/// Space left for the labels of the axes
+const PLOT_MARGIN: f32 = 40.0;
+const POINT_COLOR: Color32 = Color32::LIGHT_BLUE;
+const FIT_COLOR: Color32 = Color32::from_rgb(148, 73, 141);
+
+/// The measure shown in the plot
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Metric {
+    Steps,
+    Space,
+}
+
+/// Runs a machine over a range of input sizes and plots how its steps and space grow, with the
+/// curves that fit them best
+#[derive(Debug, Clone)]
+pub struct ProfilerWindow {
+    lang: String,
+    code: String,
+    sizes: String,
+    arity: usize,
+    metric: Metric,
+    profile: Option<Result<Profile, String>>,
+}
+
+impl ProfilerWindow {
+    pub fn new(lang: &str, code: &str) -> Self {
+        let mut window = Self {
+            lang: String::from(lang),
+            code: String::from(code),
+            sizes: String::from("0..=15"),
+            arity: 1,
+            metric: Metric::Steps,
+            profile: None,
+        };
+        window.run();
+
+        window
+    }
+
+    pub fn set_lang(&mut self, lang: &str) {
+        self.lang = lang.to_string();
+    }
+
+    fn run(&mut self) {
+        self.profile = Some(equivalence::parse_range(&self.sizes).and_then(|sizes| {
+            profiler::profile(&self.code, sizes, self.arity, runner::DEFAULT_STEP_LIMIT)
+        }));
+    }
+
+    fn growth_name(&self, growth: Growth) -> String {
+        match growth {
+            Growth::Constant => t!("profiler.growth.constant", self.lang),
+            Growth::Linear => t!("profiler.growth.linear", self.lang),
+            Growth::NLogN => t!("profiler.growth.nlogn", self.lang),
+            Growth::Quadratic => t!("profiler.growth.quadratic", self.lang),
+            Growth::Exponential => t!("profiler.growth.exponential", self.lang),
+        }
+    }
+
+    fn value(&self, sample: &Sample) -> f64 {
+        match self.metric {
+            Metric::Steps => sample.steps as f64,
+            Metric::Space => sample.space as f64,
+        }
+    }
+
+    fn fits<'a>(&self, profile: &'a Profile) -> &'a [Fit] {
+        match self.metric {
+            Metric::Steps => &profile.steps,
+            Metric::Space => &profile.space,
+        }
+    }
+
+    /// Paints the samples and the best fit. Runs that did not halt are drawn in gray at the top.
+    fn plot(&self, ui: &mut egui::Ui, profile: &Profile) {
+        let (rect, _) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width().max(300.0), PLOT_HEIGHT),
+            Sense::hover(),
+        );
+        let painter = ui.painter_at(rect);
+        let plot = egui::Rect::from_min_max(
+            rect.min + egui::vec2(PLOT_MARGIN, 10.0),
+            rect.max - egui::vec2(10.0, PLOT_MARGIN / 2.0),
+        );
+        let text_color = ui.visuals().text_color();
+        let font = FontId::proportional(12.0);
+
+        let (first, last) = match (profile.samples.first(), profile.samples.last()) {
+            (Some(first), Some(last)) => (first.size as f64, last.size as f64),
+            _ => return,
+        };
+        let max = profile
+            .samples
+            .iter()
+            .filter(|s| s.halted)
+            .map(|s| self.value(s))
+            .fold(1.0, f64::max);
+
+        let to_screen = |n: f64, v: f64| {
+            Pos2::new(
+                plot.left() + ((n - first) / (last - first).max(1.0)) as f32 * plot.width(),
+                plot.bottom() - (v.clamp(0.0, max) / max) as f32 * plot.height(),
+            )
+        };
+
+        let axis = Stroke::new(1.0, ui.visuals().weak_text_color());
+        painter.line_segment([plot.left_bottom(), plot.right_bottom()], axis);
+        painter.line_segment([plot.left_bottom(), plot.left_top()], axis);
+        for (pos, align, text) in [
+            (plot.left_bottom(), Align2::CENTER_TOP, first.to_string()),
+            (plot.right_bottom(), Align2::CENTER_TOP, last.to_string()),
+            (plot.left_bottom(), Align2::RIGHT_CENTER, String::from("0 ")),
+            (plot.left_top(), Align2::RIGHT_CENTER, format!("{} ", max)),
+        ] {
+            painter.text(pos, align, text, font.clone(), text_color);
+        }
+
+        if let Some(fit) = self.fits(profile).first() {
+            let points = (0..=64)
+                .map(|i| {
+                    let n = first + (last - first) * i as f64 / 64.0;
+                    to_screen(n, fit.predict(n))
+                })
+                .collect();
+            painter.add(Shape::line(points, Stroke::new(2.0, FIT_COLOR)));
+        }
+
+        for sample in &profile.samples {
+            let (value, color) = if sample.halted {
+                (self.value(sample), POINT_COLOR)
+            } else {
+                (max, Color32::GRAY)
+            };
+            painter.circle_filled(to_screen(sample.size as f64, value), 3.0, color);
+        }
+    }
+
+    /// Returns whether the window is still open and the CSV of the samples, if the user wants to
+    /// save it
+    pub fn show(&mut self, ctx: &egui::Context) -> (bool, Option<String>) {
+        let mut active = true;
+        let mut csv = None;
+
+        egui::Window::new(t!("title.profiler", self.lang))
+            .id(egui::Id::new("profiler_window"))
+            .resizable(true)
+            .default_width(450.0)
+            .open(&mut active)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(t!("profiler.lbl.sizes", self.lang));
+                    ui.add(egui::TextEdit::singleline(&mut self.sizes).desired_width(80.0));
+                    ui.label(t!("profiler.lbl.arity", self.lang));
+                    ui.add(egui::DragValue::new(&mut self.arity).clamp_range(1..=8));
+
+                    if ui.button(t!("profiler.btn.run", self.lang)).clicked() {
+                        self.run();
+                    }
+                });
+                ui.label(RichText::new(t!("profiler.lbl.help", self.lang)).small());
+
+                ui.separator();
+
+                let profile = match &self.profile {
+                    Some(Ok(p)) => p.clone(),
+                    Some(Err(e)) => {
+                        ui.colored_label(Color32::LIGHT_RED, e.as_str());
+                        return;
+                    }
+                    None => return,
+                };
+
+                ui.horizontal(|ui| {
+                    ui.radio_value(
+                        &mut self.metric,
+                        Metric::Steps,
+                        t!("profiler.lbl.steps", self.lang),
+                    );
+                    ui.radio_value(
+                        &mut self.metric,
+                        Metric::Space,
+                        t!("profiler.lbl.space", self.lang),
+                    );
+                });
+
+                self.plot(ui, &profile);
+
+                let fits = self.fits(&profile);
+                if fits.is_empty() {
+                    ui.label(t!("profiler.lbl.no_fit", self.lang));
+                } else {
+                    egui::Grid::new("profiler_fits")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong(t!("profiler.lbl.growth", self.lang));
+                            ui.strong(t!("profiler.lbl.formula", self.lang));
+                            ui.strong("R²");
+                            ui.end_row();
+
+                            for (i, fit) in fits.iter().enumerate() {
+                                let name = RichText::new(self.growth_name(fit.growth));
+                                ui.label(if i == 0 {
+                                    name.strong().color(FIT_COLOR)
+                                } else {
+                                    name
+                                });
+                                ui.monospace(fit.formula());
+                                ui.monospace(format!("{:.4}", fit.r2));
+                                ui.end_row();
+                            }
+                        });
+                }
+
+                if profile.samples.iter().any(|s| !s.halted) {
+                    ui.label(
+                        RichText::new(t!(
+                            "profiler.lbl.step_limit",
+                            steps: &runner::DEFAULT_STEP_LIMIT.to_string(),
+                            self.lang
+                        ))
+                        .color(Color32::GOLD),
+                    );
+                }
+
+                ui.collapsing(t!("profiler.lbl.samples", self.lang), |ui| {
+                    egui::Grid::new("profiler_samples")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong(t!("profiler.lbl.size", self.lang));
+                            ui.strong(t!("profiler.lbl.steps", self.lang));
+                            ui.strong(t!("profiler.lbl.space", self.lang));
+                            ui.end_row();
+
+                            for sample in &profile.samples {
+                                ui.monospace(sample.size.to_string());
+                                ui.monospace(if sample.halted {
+                                    sample.steps.to_string()
+                                } else {
+                                    format!("> {}", sample.steps)
+                                });
+                                ui.monospace(sample.space.to_string());
+                                ui.end_row();
+                            }
+                        });
+                });
+
+                if ui.button(t!("profiler.btn.csv", self.lang)).clicked() {
+                    csv = Some(profile.to_csv());
+                }
+            });
+
+        (active, csv)
+    }
+}
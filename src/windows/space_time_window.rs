@@ -0,0 +1,173 @@
+use eframe::egui::{self, Color32, ColorImage, RichText, Sense, TextureOptions};
+use egui_extras::RetainedImage;
+use internationalization::t;
+
+use crate::space_time::{Row, SpaceTime};
+
+/// Rows drawn in the image. Longer runs only show their last steps, since textures cannot be
+/// arbitrarily large.
+const IMAGE_ROWS: usize = 4096;
+
+fn color([r, g, b]: [u8; 3]) -> Color32 {
+    Color32::from_rgb(r, g, b)
+}
+
+/// Draws the space-time diagram of the run as the machine goes, and lets the user go back to
+/// any of its steps
+pub struct SpaceTimeWindow {
+    lang: String,
+    tape: usize,
+    /// Pixels per cell and step
+    zoom: f32,
+    image: Option<RetainedImage>,
+    /// The tape, first row, number of rows and last row of the image, to draw it again when they
+    /// change
+    drawn: Option<(usize, usize, usize, Row)>,
+}
+
+impl SpaceTimeWindow {
+    pub fn new(lang: &str) -> Self {
+        Self {
+            lang: String::from(lang),
+            tape: 0,
+            zoom: 4.0,
+            image: None,
+            drawn: None,
+        }
+    }
+
+    pub fn set_lang(&mut self, lang: &str) {
+        self.lang = lang.to_string();
+    }
+
+    /// Returns whether the window is still open and the step the user clicked on, to go back to it
+    pub fn show(&mut self, ctx: &egui::Context, diagram: &SpaceTime) -> (bool, Option<usize>) {
+        let mut active = true;
+        let mut step = None;
+
+        self.tape = self.tape.min(diagram.tape_count().saturating_sub(1));
+        let rows = diagram.rows(self.tape);
+        let first = rows.len().saturating_sub(IMAGE_ROWS);
+
+        let current = rows
+            .last()
+            .map(|last| (self.tape, first, rows.len(), last.clone()));
+
+        if self.image.is_none() || self.drawn != current {
+            let (width, height, pixels) = diagram.pixels(self.tape, first..rows.len());
+            self.image = Some(
+                RetainedImage::from_color_image(
+                    "space_time",
+                    ColorImage::from_rgb([width, height], &pixels),
+                )
+                .with_options(TextureOptions::NEAREST),
+            );
+            self.drawn = current;
+        }
+
+        egui::Window::new(t!("title.space_time", self.lang))
+            .id(egui::Id::new("space_time_window"))
+            .resizable(true)
+            .default_width(400.0)
+            .open(&mut active)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if diagram.tape_count() > 1 {
+                        egui::ComboBox::from_id_source("space_time_tape")
+                            .selected_text(t!(
+                                "lbl.tape",
+                                val: &(self.tape + 1).to_string(),
+                                self.lang
+                            ))
+                            .show_ui(ui, |ui| {
+                                for k in 0..diagram.tape_count() {
+                                    ui.selectable_value(
+                                        &mut self.tape,
+                                        k,
+                                        t!("lbl.tape", val: &(k + 1).to_string(), self.lang),
+                                    );
+                                }
+                            });
+                    }
+
+                    ui.add(
+                        egui::Slider::new(&mut self.zoom, 1.0..=16.0)
+                            .suffix(" px")
+                            .text(t!("space_time.lbl.zoom", self.lang)),
+                    );
+                });
+
+                // The legend of the colors
+                ui.horizontal_wrapped(|ui| {
+                    for (i, symbol) in diagram.symbols().iter().enumerate() {
+                        ui.label(
+                            RichText::new(format!(" {} ", symbol))
+                                .monospace()
+                                .color(Color32::GRAY)
+                                .background_color(color(SpaceTime::color(i as u8))),
+                        );
+                    }
+                    ui.label(
+                        RichText::new(format!(" {} ", t!("space_time.lbl.head", self.lang)))
+                            .color(Color32::WHITE)
+                            .background_color(color(SpaceTime::head_color())),
+                    );
+                });
+
+                ui.label(RichText::new(t!("space_time.lbl.help", self.lang)).small());
+                if let Some(e) = &diagram.error {
+                    ui.label(
+                        RichText::new(t!("space_time.lbl.error", error: e, self.lang))
+                            .color(Color32::RED),
+                    );
+                }
+                if diagram.truncated || first > 0 {
+                    ui.label(
+                        RichText::new(t!(
+                            "space_time.lbl.truncated",
+                            rows: &rows.len().min(IMAGE_ROWS).to_string(),
+                            self.lang
+                        ))
+                        .color(Color32::GOLD),
+                    );
+                }
+
+                ui.separator();
+
+                let image = match &self.image {
+                    Some(image) => image,
+                    None => return,
+                };
+                let size = image.size_vec2() * self.zoom;
+
+                egui::ScrollArea::both()
+                    .max_height(400.0)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        let rect = image.show_size(ui, size).rect;
+                        let response =
+                            ui.interact(rect, egui::Id::new("space_time_image"), Sense::click());
+
+                        if let Some(pos) = response.interact_pointer_pos() {
+                            if response.clicked() {
+                                let row = ((pos.y - rect.top()) / self.zoom) as usize;
+                                step = rows.get(first + row).map(|r| r.step);
+                            }
+                        }
+
+                        if let Some(pos) = response.hover_pos() {
+                            let row = ((pos.y - rect.top()) / self.zoom) as usize;
+                            if let Some(r) = rows.get(first + row) {
+                                response.on_hover_text_at_pointer(t!(
+                                    "space_time.lbl.step",
+                                    step: &r.step.to_string(),
+                                    self.lang
+                                ));
+                            }
+                        }
+                    });
+            });
+
+        (active, step)
+    }
+}
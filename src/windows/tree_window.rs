@@ -0,0 +1,197 @@
+use std::collections::HashSet;
+
+use eframe::egui::{self, collapsing_header::CollapsingState, Color32, RichText};
+use internationalization::t;
+
+use crate::machine::{
+    BranchStatus, ComputationTree, Program, DEFAULT_MAX_DEPTH, DEFAULT_MAX_NODES,
+};
+
+/// Shows the computation tree of a nondeterministic machine and lets the user pick a branch
+/// to replay it on the tape
+#[derive(Debug, Clone)]
+pub struct ComputationTreeWindow {
+    lang: String,
+    program: Program,
+    max_depth: usize,
+    max_nodes: usize,
+    tree: ComputationTree,
+    /// The nodes from the root to the accepting node, which are expanded by default
+    accepting_path: HashSet<usize>,
+    selected: Option<usize>,
+}
+
+impl ComputationTreeWindow {
+    pub fn new(lang: &str, program: &Program) -> Self {
+        let tree = ComputationTree::explore(program, DEFAULT_MAX_DEPTH, DEFAULT_MAX_NODES);
+
+        Self {
+            lang: String::from(lang),
+            program: program.clone(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_nodes: DEFAULT_MAX_NODES,
+            accepting_path: Self::accepting_path(&tree),
+            tree,
+            selected: None,
+        }
+    }
+
+    pub fn set_lang(&mut self, lang: &str) {
+        self.lang = lang.to_string();
+    }
+
+    /// The code of the machine whose computations are shown
+    pub fn code(&self) -> &str {
+        &self.program.code
+    }
+
+    fn explore(&mut self) {
+        self.tree = ComputationTree::explore(&self.program, self.max_depth, self.max_nodes);
+        self.accepting_path = Self::accepting_path(&self.tree);
+        self.selected = None;
+    }
+
+    fn accepting_path(tree: &ComputationTree) -> HashSet<usize> {
+        let mut path = HashSet::new();
+        let mut current = tree.accepted;
+
+        while let Some(id) = current {
+            path.insert(id);
+            current = tree.nodes[id].parent;
+        }
+
+        path
+    }
+
+    fn node_ui(&self, ui: &mut egui::Ui, id: usize, replay: &mut Option<usize>) {
+        let node = &self.tree.nodes[id];
+
+        let color = match node.status {
+            BranchStatus::Accepted => Color32::LIGHT_GREEN,
+            BranchStatus::Rejected => Color32::LIGHT_RED,
+            BranchStatus::Unexplored => Color32::GRAY,
+            BranchStatus::Expanded => ui.visuals().text_color(),
+        };
+        let text = RichText::new(format!("{}: {}", node.state, node.word))
+            .monospace()
+            .color(color);
+        let transition = match node.transition {
+            Some(t) => self.program.transitions[t].to_string(),
+            None => t!("tree.lbl.initial", self.lang),
+        };
+
+        let row = |ui: &mut egui::Ui| {
+            if ui
+                .selectable_label(self.selected == Some(id), text)
+                .on_hover_text(transition)
+                .clicked()
+            {
+                *replay = Some(id);
+            }
+        };
+
+        if node.children.is_empty() {
+            row(ui);
+            return;
+        }
+
+        CollapsingState::load_with_default_open(
+            ui.ctx(),
+            ui.make_persistent_id(("tree_node", id)),
+            node.depth < 2 || self.accepting_path.contains(&id),
+        )
+        .show_header(ui, row)
+        .body(|ui| {
+            for child in &node.children {
+                self.node_ui(ui, *child, replay);
+            }
+        });
+    }
+
+    /// Returns whether the window is still open and the path of the branch to replay, if the
+    /// user selected one
+    pub fn show(&mut self, ctx: &egui::Context) -> (bool, Option<Vec<usize>>) {
+        let mut active = true;
+        let mut replay = None;
+
+        egui::Window::new(t!("title.tree", self.lang))
+            .id(egui::Id::new("tree_window"))
+            .resizable(true)
+            .default_height(400.0)
+            .open(&mut active)
+            .show(ctx, |ui| {
+                match self.tree.accepted {
+                    Some(id) => {
+                        ui.label(
+                            RichText::new(t!(
+                                "tree.lbl.accepted",
+                                steps: &self.tree.nodes[id].depth.to_string(),
+                                self.lang
+                            ))
+                            .color(Color32::LIGHT_GREEN),
+                        );
+                    }
+                    None if self.tree.truncated => {
+                        ui.label(
+                            RichText::new(t!("tree.lbl.truncated", self.lang)).color(Color32::GOLD),
+                        );
+                    }
+                    None => {
+                        ui.label(
+                            RichText::new(t!("tree.lbl.rejected", self.lang))
+                                .color(Color32::LIGHT_RED),
+                        );
+                    }
+                }
+                ui.label(t!(
+                    "tree.lbl.nodes",
+                    nodes: &self.tree.nodes.len().to_string(),
+                    self.lang
+                ));
+
+                ui.horizontal(|ui| {
+                    ui.label(t!("tree.lbl.max_depth", self.lang));
+                    ui.add(egui::DragValue::new(&mut self.max_depth).clamp_range(1..=10_000));
+                    ui.label(t!("tree.lbl.max_nodes", self.lang));
+                    ui.add(
+                        egui::DragValue::new(&mut self.max_nodes)
+                            .clamp_range(1..=1_000_000)
+                            .speed(100),
+                    );
+
+                    if ui.button(t!("tree.btn.explore", self.lang)).clicked() {
+                        self.explore();
+                    }
+                });
+
+                if let Some(id) = self.tree.accepted {
+                    if ui
+                        .button(t!("tree.btn.replay_accepted", self.lang))
+                        .clicked()
+                    {
+                        replay = Some(id);
+                    }
+                }
+
+                ui.separator();
+                ui.label(RichText::new(t!("tree.lbl.help", self.lang)).small());
+
+                egui::ScrollArea::both()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        let mut clicked = None;
+                        self.node_ui(ui, 0, &mut clicked);
+
+                        if clicked.is_some() {
+                            replay = clicked;
+                        }
+                    });
+            });
+
+        if let Some(id) = replay {
+            self.selected = Some(id);
+        }
+
+        (active, replay.map(|id| self.tree.path(id)))
+    }
+}
@@ -1,9 +1,16 @@
-use eframe::egui;
+use eframe::egui::{self, RichText};
 use internationalization::t;
 
-use crate::windows::workbook::raw_data_to_image;
+use crate::{
+    runner::{ExerciseTest, RunOutcome, TestComparison},
+    windows::workbook::raw_data_to_image,
+    ComparisonRun, ExerciseStatus, Progress,
+};
 
-use super::{exercise::Exercise, load_workbook, Workbook, MAX_IMG_SIZE};
+use super::{
+    exercise::Exercise, load_workbook, markdown::show_markdown, student_edition, workbook_id,
+    Workbook, MAX_IMG_SIZE,
+};
 
 #[cfg(target_family = "wasm")]
 use poll_promise::Promise;
@@ -11,14 +18,21 @@ use poll_promise::Promise;
 pub struct BookWindow {
     lang: String,
     exercises: Workbook,
+    id: String,
     selected: (usize, usize),
+    /// How many hints of the selected exercise have been revealed
+    hints_shown: usize,
+    /// Outputs of the student code and the reference solution on the tests of an exercise
+    comparison: Option<((usize, usize), Vec<TestComparison>)>,
+    /// The comparison running in the background, and the exercise it belongs to
+    comparing: Option<((usize, usize), ComparisonRun)>,
 
     #[cfg(target_family = "wasm")]
     file_request_future: Option<Promise<Option<Workbook>>>,
 }
 
 impl BookWindow {
-    pub fn new(lang: &str) -> Self {
+    pub fn new(lang: &str, progress: &Progress) -> Self {
         let exercises: Workbook = vec![
             (
                 "Chapter 1".to_string(),
@@ -30,6 +44,15 @@ impl BookWindow {
                             include_bytes!("../../../assets/ui/exercise1/cover.png"),
                         )),
                         String::from(include_str!("../../../assets/ui/exercise1/code.tm")),
+                    )
+                    .with_statement(
+                        include_str!("../../../assets/ui/exercise1/statement.md"),
+                        vec![
+                            String::from("First, check whether $x$ has run out of ones."),
+                            String::from(
+                                "Each loop removes one 1 from $x$ and one from $y$: if $y$ runs out first, the machine must stop in a non-final state.",
+                            ),
+                        ],
                     ),
                     Exercise::new(
                         "Exercise 2",
@@ -38,36 +61,84 @@ impl BookWindow {
                             include_bytes!("../../../assets/ui/exercise2/cover.png"),
                         )),
                         String::from(include_str!("../../../assets/ui/exercise2/code.tm")),
+                    )
+                    .with_statement(
+                        include_str!("../../../assets/ui/exercise2/statement.md"),
+                        vec![String::from(
+                            "For every 1 of $x$, mark it and write two 1s at the end of the tape.",
+                        )],
                     ),
                 ],
             ),
             //("Chapter 2".to_string(), vec![]),
         ];
 
-        Self {
+        let mut window = Self {
             lang: String::from(lang),
-            exercises,
+            exercises: vec![],
+            id: String::new(),
             selected: (0, 0),
+            hints_shown: 0,
+            comparison: None,
+            comparing: None,
 
             #[cfg(target_family = "wasm")]
             file_request_future: None,
-        }
+        };
+
+        window.set_workbook(exercises, progress);
+
+        window
     }
 
     pub fn set_lang(&mut self, lang: &str) {
         self.lang = lang.to_string();
     }
 
-    pub fn show(&mut self, ctx: &egui::Context) -> (bool, Option<String>) {
+    /// Returns the identifier of the workbook and the exercise that is currently selected
+    pub fn current_exercise(&self) -> (String, (usize, usize)) {
+        (self.id.clone(), self.selected)
+    }
+
+    /// Returns the tests of the exercise that is currently selected
+    pub fn current_tests(&self) -> Vec<ExerciseTest> {
+        self.exercises
+            .get(self.selected.0)
+            .and_then(|(_, exercises)| exercises.get(self.selected.1))
+            .map(|ex| ex.tests.clone())
+            .unwrap_or_default()
+    }
+
+    /// Replaces the workbook and resumes from the exercise the student was last working on
+    fn set_workbook(&mut self, exercises: Workbook, progress: &Progress) {
+        self.id = workbook_id(&exercises);
+        // Students never get to see the solutions, even if the workbook contains them
+        self.exercises = if cfg!(feature = "teacher") {
+            exercises
+        } else {
+            student_edition(&exercises)
+        };
+        self.hints_shown = 0;
+        self.comparison = None;
+        self.comparing = None;
+
+        let (chapter, exercise) = progress.last_selected(&self.id);
+        self.selected = match self.exercises.get(chapter) {
+            Some((_, ex)) if exercise < ex.len() => (chapter, exercise),
+            _ => (0, 0),
+        };
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, progress: &mut Progress) -> (bool, Option<String>) {
         let mut active = true;
         let mut code = None;
+        let previous_selection = self.selected;
 
         #[cfg(target_family = "wasm")]
         if let Some(file_async) = &self.file_request_future {
             if let Some(file_result) = file_async.ready() {
                 if let Some(workbook) = file_result.clone() {
-                    self.exercises = workbook.to_vec();
-                    self.selected = (0, 0);
+                    self.set_workbook(workbook.to_vec(), progress);
                 }
 
                 self.file_request_future = None;
@@ -85,12 +156,35 @@ impl BookWindow {
 
                         egui::ScrollArea::vertical().show(ui, |ui| {
                             for (section, (title, exercises)) in self.exercises.iter().enumerate() {
+                                let solved = progress
+                                    .workbook(&self.id)
+                                    .map(|wb| wb.solved_in_chapter(section, exercises.len()))
+                                    .unwrap_or(0);
+
                                 ui.collapsing(title, |ui| {
+                                    ui.add(
+                                        egui::ProgressBar::new(
+                                            solved as f32 / exercises.len().max(1) as f32,
+                                        )
+                                        .desired_width(150.0)
+                                        .text(format!(
+                                            "{}/{}",
+                                            solved,
+                                            exercises.len()
+                                        )),
+                                    );
+
                                     for (i, exercise) in exercises.iter().enumerate() {
+                                        let status = progress.status(&self.id, (section, i));
+
                                         if ui
                                             .add_enabled(
                                                 self.selected.0 != section || self.selected.1 != i,
-                                                egui::Button::new(&exercise.title),
+                                                egui::Button::new(format!(
+                                                    "{} {}",
+                                                    status.icon(),
+                                                    exercise.title
+                                                )),
                                             )
                                             .clicked()
                                         {
@@ -111,8 +205,7 @@ impl BookWindow {
 
                             #[cfg(not(target_family = "wasm"))]
                             if let Some(new_exercises) = load_workbook() {
-                                self.exercises = new_exercises;
-                                self.selected = (0, 0);
+                                self.set_workbook(new_exercises, progress);
                             }
                         }
                     });
@@ -131,6 +224,47 @@ impl BookWindow {
                             img_width = img_size.width();
                         }
 
+                        let exercise = self.get_exercise(self.selected);
+                        if !exercise.statement.is_empty() || !exercise.hints.is_empty() {
+                            let statement = exercise.statement.clone();
+                            let hints = exercise.hints.clone();
+
+                            egui::ScrollArea::vertical()
+                                .id_source("statement_scroll")
+                                .max_height(MAX_IMG_SIZE.y)
+                                .max_width(img_width)
+                                .show(ui, |ui| {
+                                    show_markdown(ui, &statement);
+
+                                    for (i, hint) in hints.iter().take(self.hints_shown).enumerate()
+                                    {
+                                        ui.separator();
+                                        ui.label(
+                                            egui::RichText::new(t!(
+                                                "lbl.workbook.hint",
+                                                num: &(i + 1).to_string(),
+                                                self.lang
+                                            ))
+                                            .strong(),
+                                        );
+                                        show_markdown(ui, hint);
+                                    }
+
+                                    if self.hints_shown < hints.len()
+                                        && ui
+                                            .button(t!(
+                                                "btn.workbook.show_hint",
+                                                shown: &self.hints_shown.to_string(),
+                                                total: &hints.len().to_string(),
+                                                self.lang
+                                            ))
+                                            .clicked()
+                                    {
+                                        self.hints_shown += 1;
+                                    }
+                                });
+                        }
+
                         ui.horizontal(|ui| {
                             let prev_button = ui.add_enabled(
                                 self.selected.1 > 0,
@@ -158,18 +292,197 @@ impl BookWindow {
                             }
                         });
 
+                        let status = progress.status(&self.id, self.selected);
+                        let minutes = (progress.time_spent(&self.id, self.selected) / 60.0) as u64;
+
+                        ui.horizontal(|ui| {
+                            ui.label(match status {
+                                ExerciseStatus::NotStarted => {
+                                    t!("lbl.workbook.status.not_started", self.lang)
+                                }
+                                ExerciseStatus::Attempted => {
+                                    t!("lbl.workbook.status.attempted", self.lang)
+                                }
+                                ExerciseStatus::Solved => {
+                                    t!("lbl.workbook.status.solved", self.lang)
+                                }
+                            });
+
+                            if status != ExerciseStatus::NotStarted {
+                                ui.label(t!(
+                                    "lbl.workbook.time_spent",
+                                    time: &minutes.to_string(),
+                                    self.lang
+                                ));
+                            }
+                        });
+
                         ui.vertical_centered_justified(|ui| {
-                            if ui.button(t!("btn.workbook.use", self.lang)).clicked() {
+                            if let Some(saved) = progress.code(&self.id, self.selected).cloned() {
+                                if ui.button(t!("btn.workbook.resume", self.lang)).clicked() {
+                                    code = Some(saved);
+                                }
+
+                                if ui
+                                    .button(t!("btn.workbook.start_over", self.lang))
+                                    .clicked()
+                                {
+                                    progress.reset(&self.id, self.selected);
+                                    code = Some(self.get_exercise(self.selected).code.clone());
+                                }
+                            } else if ui.button(t!("btn.workbook.use", self.lang)).clicked() {
                                 code = Some(self.get_exercise(self.selected).code.clone());
                             }
                         });
+
+                        if cfg!(feature = "teacher") {
+                            if let Some(c) = self.teacher_ui(ui, progress) {
+                                code = Some(c);
+                            }
+                        }
                     });
                 });
             });
 
+        if self.selected != previous_selection {
+            self.hints_shown = 0;
+        }
+
+        progress.set_last_selected(&self.id, self.selected);
+
         (active, code)
     }
 
+    /// Shows the teacher notes and compares the student code with the reference solution.
+    /// Returns the solution if the teacher wants to load it.
+    fn teacher_ui(&mut self, ui: &mut egui::Ui, progress: &Progress) -> Option<String> {
+        let mut code = None;
+        let selected = self.selected;
+        let lang = self.lang.clone();
+
+        let exercise = self.get_exercise(selected).clone();
+        let student_code = progress
+            .code(&self.id, selected)
+            .cloned()
+            .unwrap_or_else(|| exercise.code.clone());
+
+        ui.collapsing(t!("heading.workbook.teacher", lang), |ui| {
+            if !exercise.notes.is_empty() {
+                show_markdown(ui, &exercise.notes);
+                ui.separator();
+            }
+
+            let solution = match &exercise.solution {
+                Some(s) => s,
+                None => {
+                    ui.label(t!("lbl.workbook.no_solution", lang));
+                    return;
+                }
+            };
+
+            ui.horizontal(|ui| {
+                if ui.button(t!("btn.workbook.load_solution", lang)).clicked() {
+                    code = Some(solution.clone());
+                }
+
+                if ui
+                    .add_enabled(
+                        !exercise.tests.is_empty() && self.comparing.is_none(),
+                        egui::Button::new(t!("btn.workbook.compare", lang)),
+                    )
+                    .clicked()
+                {
+                    self.comparing = Some((
+                        selected,
+                        ComparisonRun::start(
+                            student_code.clone(),
+                            solution.clone(),
+                            exercise.tests.clone(),
+                        ),
+                    ));
+                }
+
+                if let Some((exercise, run)) = self.comparing.as_mut() {
+                    match run.poll() {
+                        Some(comparison) => {
+                            self.comparison = Some((*exercise, comparison));
+                            self.comparing = None;
+                        }
+                        None => {
+                            ui.spinner();
+                            ui.ctx().request_repaint();
+                        }
+                    }
+                }
+            });
+
+            ui.columns(2, |columns| {
+                columns[0].label(RichText::new(t!("lbl.workbook.student_code", lang)).strong());
+                columns[1].label(RichText::new(t!("lbl.workbook.solution", lang)).strong());
+
+                for (i, c) in [&student_code, solution].into_iter().enumerate() {
+                    egui::ScrollArea::vertical()
+                        .id_source(format!("teacher_code_{}", i))
+                        .max_height(200.0)
+                        .show(&mut columns[i], |ui| {
+                            ui.label(RichText::new(c).monospace());
+                        });
+                }
+            });
+
+            let comparison = match &self.comparison {
+                Some((exercise, comparison)) if *exercise == selected => comparison,
+                _ => return,
+            };
+
+            let describe = |outcome: &Option<RunOutcome>| match outcome {
+                Some(RunOutcome::Halted { value, .. }) => value.to_string(),
+                Some(RunOutcome::Word { word, .. }) => word.clone(),
+                Some(RunOutcome::Accepted { .. }) => t!("lbl.workbook.accepted", lang),
+                Some(RunOutcome::Rejected { .. }) => t!("lbl.workbook.rejected", lang),
+                Some(RunOutcome::Undefined { .. }) => t!("lbl.workbook.undefined", lang),
+                Some(RunOutcome::StepLimit { steps }) => {
+                    t!("lbl.workbook.step_limit", steps: &steps.to_string(), lang)
+                }
+                None => t!("lbl.workbook.not_compiled", lang),
+            };
+
+            egui::Grid::new("solution_comparison")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong(t!("lbl.workbook.input", lang));
+                    ui.strong(t!("lbl.workbook.expected", lang));
+                    ui.strong(t!("lbl.workbook.student_code", lang));
+                    ui.strong(t!("lbl.workbook.solution", lang));
+                    ui.end_row();
+
+                    for row in comparison {
+                        ui.monospace(&row.test.input);
+                        ui.label(
+                            match (row.test.accept, &row.test.expected_word, row.test.expected) {
+                                (Some(true), _, _) => t!("lbl.workbook.accepted", lang),
+                                (Some(false), _, _) => t!("lbl.workbook.rejected", lang),
+                                (None, Some(word), _) => word.clone(),
+                                (None, None, Some(v)) => v.to_string(),
+                                (None, None, None) => t!("lbl.workbook.undefined", lang),
+                            },
+                        );
+
+                        let color = if row.matches() {
+                            egui::Color32::LIGHT_GREEN
+                        } else {
+                            egui::Color32::LIGHT_RED
+                        };
+                        ui.colored_label(color, describe(&row.student));
+                        ui.label(describe(&row.solution));
+                        ui.end_row();
+                    }
+                });
+        });
+
+        code
+    }
+
     fn get_exercise(&mut self, i: (usize, usize)) -> &mut Exercise {
         &mut self.exercises[i.0].1[i.1]
     }
@@ -3,6 +3,8 @@ use egui_extras::RetainedImage;
 use serde::{self, Deserialize, Serialize};
 use std::fmt::{self, Debug};
 
+use crate::runner::ExerciseTest;
+
 #[derive(Serialize, Deserialize)]
 pub struct Exercise {
     #[serde(skip)]
@@ -10,6 +12,39 @@ pub struct Exercise {
     original_image: Option<(usize, usize, Vec<u8>)>,
     pub title: String,
     pub code: String,
+
+    /// The statement of the exercise, written in markdown
+    pub statement: String,
+
+    /// Hints that the student can reveal one by one
+    pub hints: Vec<String>,
+
+    /// Inputs the solution is checked against
+    pub tests: Vec<ExerciseTest>,
+
+    /// The reference solution, only shown in teacher builds
+    pub solution: Option<String>,
+
+    /// Private notes for the teacher
+    pub notes: String,
+}
+
+/// An exercise as it was stored in the workbooks saved before statements and hints existed
+#[derive(Deserialize)]
+pub struct LegacyExercise {
+    original_image: Option<(usize, usize, Vec<u8>)>,
+    title: String,
+    code: String,
+}
+
+impl From<LegacyExercise> for Exercise {
+    fn from(legacy: LegacyExercise) -> Self {
+        let image = legacy.original_image.map(|(width, height, pixels)| {
+            ColorImage::from_rgba_unmultiplied([width, height], &pixels)
+        });
+
+        Self::new(&legacy.title, image, legacy.code)
+    }
 }
 
 impl Exercise {
@@ -24,6 +59,11 @@ impl Exercise {
                 )),
                 title: String::from(title),
                 code,
+                statement: String::new(),
+                hints: Vec::new(),
+                tests: Vec::new(),
+                solution: None,
+                notes: String::new(),
             }
         } else {
             Self {
@@ -31,6 +71,11 @@ impl Exercise {
                 original_image: None,
                 title: String::from(title),
                 code,
+                statement: String::new(),
+                hints: Vec::new(),
+                tests: Vec::new(),
+                solution: None,
+                notes: String::new(),
             }
         }
     }
@@ -44,9 +89,34 @@ impl Exercise {
         ));
     }
 
+    pub fn remove_cover(&mut self) {
+        self.image = None;
+        self.original_image = None;
+    }
+
     pub fn get_cover(&mut self) -> Option<&RetainedImage> {
         self.image.as_ref()
     }
+
+    pub fn with_statement(mut self, statement: &str, hints: Vec<String>) -> Self {
+        self.statement = String::from(statement);
+        self.hints = hints;
+        self
+    }
+
+    pub fn with_tests(mut self, tests: Vec<ExerciseTest>) -> Self {
+        self.tests = tests;
+        self
+    }
+
+    /// Returns a copy of the exercise without the solution and the teacher notes
+    pub fn student_edition(&self) -> Self {
+        Self {
+            solution: None,
+            notes: String::new(),
+            ..self.clone()
+        }
+    }
 }
 
 impl Debug for Exercise {
@@ -54,26 +124,48 @@ impl Debug for Exercise {
         f.debug_struct("Exercise")
             .field("title", &self.title)
             .field("code", &self.code)
+            .field("statement", &self.statement)
+            .field("hints", &self.hints)
+            .field("tests", &self.tests)
+            .field("solution", &self.solution)
+            .field("notes", &self.notes)
             .finish()
     }
 }
 
+/// The image is left out, since it is drawn from the original one
+impl PartialEq for Exercise {
+    fn eq(&self, other: &Self) -> bool {
+        self.original_image == other.original_image
+            && self.title == other.title
+            && self.code == other.code
+            && self.statement == other.statement
+            && self.hints == other.hints
+            && self.tests == other.tests
+            && self.solution == other.solution
+            && self.notes == other.notes
+    }
+}
+
 impl Clone for Exercise {
     fn clone(&self) -> Self {
         Self {
-            image: match &self.image {
-                Some(_) => match RetainedImage::from_image_bytes(
+            // The original image holds the raw pixels, not an encoded image file
+            image: match (&self.image, &self.original_image) {
+                (Some(_), Some((width, height, pixels))) => Some(RetainedImage::from_color_image(
                     &self.title,
-                    &self.original_image.clone().unwrap_or_default().2,
-                ) {
-                    Ok(img) => Some(img),
-                    Err(_) => None,
-                },
-                None => None,
+                    ColorImage::from_rgba_unmultiplied([*width, *height], pixels),
+                )),
+                _ => None,
             },
             original_image: self.original_image.clone(),
             title: self.title.clone(),
             code: self.code.clone(),
+            statement: self.statement.clone(),
+            hints: self.hints.clone(),
+            tests: self.tests.clone(),
+            solution: self.solution.clone(),
+            notes: self.notes.clone(),
         }
     }
 }
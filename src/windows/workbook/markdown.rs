@@ -0,0 +1,285 @@
+use eframe::egui::{self, text::LayoutJob, Color32, FontId, RichText, TextFormat};
+
+/// LaTeX commands supported inside `$...$` and their unicode replacement
+const LATEX_SYMBOLS: [(&str, &str); 32] = [
+    ("\\leq", "≤"),
+    ("\\le", "≤"),
+    ("\\geq", "≥"),
+    ("\\ge", "≥"),
+    ("\\neq", "≠"),
+    ("\\cdot", "·"),
+    ("\\times", "×"),
+    ("\\div", "÷"),
+    ("\\pm", "±"),
+    ("\\in", "∈"),
+    ("\\notin", "∉"),
+    ("\\emptyset", "∅"),
+    ("\\cup", "∪"),
+    ("\\cap", "∩"),
+    ("\\subseteq", "⊆"),
+    ("\\to", "→"),
+    ("\\rightarrow", "→"),
+    ("\\Rightarrow", "⇒"),
+    ("\\iff", "⇔"),
+    ("\\infty", "∞"),
+    ("\\forall", "∀"),
+    ("\\exists", "∃"),
+    ("\\land", "∧"),
+    ("\\lor", "∨"),
+    ("\\neg", "¬"),
+    ("\\Sigma", "Σ"),
+    ("\\sigma", "σ"),
+    ("\\delta", "δ"),
+    ("\\lambda", "λ"),
+    ("\\mathbb{N}", "ℕ"),
+    ("\\mathbb{Z}", "ℤ"),
+    ("\\ldots", "…"),
+];
+
+const SUPERSCRIPTS: [(char, char); 14] = [
+    ('0', '⁰'),
+    ('1', '¹'),
+    ('2', '²'),
+    ('3', '³'),
+    ('4', '⁴'),
+    ('5', '⁵'),
+    ('6', '⁶'),
+    ('7', '⁷'),
+    ('8', '⁸'),
+    ('9', '⁹'),
+    ('n', 'ⁿ'),
+    ('i', 'ⁱ'),
+    ('+', '⁺'),
+    ('-', '⁻'),
+];
+
+const SUBSCRIPTS: [(char, char); 14] = [
+    ('0', '₀'),
+    ('1', '₁'),
+    ('2', '₂'),
+    ('3', '₃'),
+    ('4', '₄'),
+    ('5', '₅'),
+    ('6', '₆'),
+    ('7', '₇'),
+    ('8', '₈'),
+    ('9', '₉'),
+    ('i', 'ᵢ'),
+    ('k', 'ₖ'),
+    ('n', 'ₙ'),
+    ('x', 'ₓ'),
+];
+
+/// Converts the (small) subset of LaTeX used in exercise statements to unicode text,
+/// e.g. `f(x_1, x_2) = x^2 \leq y` becomes `f(x₁, x₂) = x² ≤ y`
+pub fn latex_to_unicode(latex: &str) -> String {
+    let mut text = String::from(latex);
+
+    // Longest commands first, so that `\leq` is not replaced as `\le` + `q`
+    let mut symbols = LATEX_SYMBOLS.to_vec();
+    symbols.sort_by_key(|(cmd, _)| std::cmp::Reverse(cmd.len()));
+    for (cmd, symbol) in symbols {
+        text = text.replace(cmd, symbol);
+    }
+
+    let mut result = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let table: &[(char, char)] = match c {
+            '^' => &SUPERSCRIPTS,
+            '_' => &SUBSCRIPTS,
+            '\\' if chars.peek().is_some_and(|c| "{}$".contains(*c)) => {
+                result.extend(chars.next());
+                continue;
+            }
+            '{' | '}' => continue,
+            _ => {
+                result.push(c);
+                continue;
+            }
+        };
+
+        // Either a single character or a group between braces
+        let group: Vec<char> = if chars.peek() == Some(&'{') {
+            chars.next();
+            chars.by_ref().take_while(|c| *c != '}').collect()
+        } else {
+            chars.next().into_iter().collect()
+        };
+
+        for g in group {
+            match table.iter().find(|(from, _)| *from == g) {
+                Some((_, to)) => result.push(*to),
+                None => {
+                    result.push(c);
+                    result.push(g);
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// Lays out a single line of markdown text, handling `**bold**`, `*italic*`, `` `code` `` and `$math$`
+fn inline_job(ui: &egui::Ui, line: &str, font_id: FontId) -> LayoutJob {
+    let mut job = LayoutJob::default();
+
+    let normal = TextFormat {
+        font_id: font_id.clone(),
+        color: ui.visuals().text_color(),
+        ..Default::default()
+    };
+
+    let mut rest = line;
+    while !rest.is_empty() {
+        let next = rest
+            .char_indices()
+            .find(|(_, c)| matches!(c, '*' | '`' | '$'))
+            .map(|(i, _)| i);
+
+        let start = match next {
+            Some(i) => i,
+            None => {
+                job.append(rest, 0.0, normal.clone());
+                break;
+            }
+        };
+
+        job.append(&rest[..start], 0.0, normal.clone());
+        rest = &rest[start..];
+
+        let (delimiter, format) = if rest.starts_with("**") {
+            (
+                "**",
+                TextFormat {
+                    color: ui.visuals().strong_text_color(),
+                    ..normal.clone()
+                },
+            )
+        } else if rest.starts_with('*') {
+            (
+                "*",
+                TextFormat {
+                    italics: true,
+                    ..normal.clone()
+                },
+            )
+        } else if rest.starts_with('`') {
+            (
+                "`",
+                TextFormat {
+                    font_id: FontId::monospace(font_id.size),
+                    background: ui.visuals().code_bg_color,
+                    ..normal.clone()
+                },
+            )
+        } else {
+            (
+                "$",
+                TextFormat {
+                    italics: true,
+                    color: Color32::LIGHT_BLUE,
+                    ..normal.clone()
+                },
+            )
+        };
+
+        match rest[delimiter.len()..].find(delimiter) {
+            Some(end) => {
+                let content = &rest[delimiter.len()..delimiter.len() + end];
+
+                if delimiter == "$" {
+                    job.append(&latex_to_unicode(content), 0.0, format);
+                } else {
+                    job.append(content, 0.0, format);
+                }
+
+                rest = &rest[2 * delimiter.len() + end..];
+            }
+            None => {
+                // Unmatched delimiter, show it as it is
+                job.append(delimiter, 0.0, normal.clone());
+                rest = &rest[delimiter.len()..];
+            }
+        }
+    }
+
+    job
+}
+
+/// Renders a markdown text: headings (`#`, `##`, `###`), bullet and numbered lists,
+/// fenced code blocks, and inline formatting with math between dollar signs.
+pub fn show_markdown(ui: &mut egui::Ui, text: &str) {
+    let body_size = ui
+        .style()
+        .override_font_id
+        .as_ref()
+        .map(|f| f.size)
+        .unwrap_or(14.0);
+
+    let mut lines = text.lines();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+
+        if trimmed.starts_with("```") {
+            let code = lines
+                .by_ref()
+                .take_while(|l| !l.trim_start().starts_with("```"))
+                .collect::<Vec<&str>>()
+                .join("\n");
+
+            egui::Frame::none()
+                .fill(ui.visuals().code_bg_color)
+                .inner_margin(egui::style::Margin::same(5.0))
+                .rounding(5.0)
+                .show(ui, |ui| {
+                    ui.label(RichText::new(code).monospace());
+                });
+        } else if let Some(heading) = trimmed.strip_prefix("### ") {
+            ui.label(inline_job(
+                ui,
+                heading,
+                FontId::proportional(body_size * 1.2),
+            ));
+        } else if let Some(heading) = trimmed.strip_prefix("## ") {
+            ui.label(inline_job(
+                ui,
+                heading,
+                FontId::proportional(body_size * 1.45),
+            ));
+        } else if let Some(heading) = trimmed.strip_prefix("# ") {
+            ui.label(inline_job(
+                ui,
+                heading,
+                FontId::proportional(body_size * 1.7),
+            ));
+        } else if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+        {
+            let indent = (line.len() - trimmed.len()) as f32 * 5.0;
+            ui.horizontal_wrapped(|ui| {
+                ui.add_space(indent);
+                ui.label("•");
+                ui.label(inline_job(ui, item, FontId::proportional(body_size)));
+            });
+        } else if let Some((number, item)) = trimmed
+            .split_once(". ")
+            .filter(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        {
+            let indent = (line.len() - trimmed.len()) as f32 * 5.0;
+            ui.horizontal_wrapped(|ui| {
+                ui.add_space(indent);
+                ui.label(format!("{}.", number));
+                ui.label(inline_job(ui, item, FontId::proportional(body_size)));
+            });
+        } else if trimmed.is_empty() {
+            ui.add_space(body_size / 2.0);
+        } else {
+            ui.label(inline_job(ui, line, FontId::proportional(body_size)));
+        }
+    }
+}
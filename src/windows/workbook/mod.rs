@@ -1,17 +1,19 @@
 mod book;
 mod exercise;
+mod markdown;
 mod wb_editor;
 
 pub use book::BookWindow as WorkbookWindow;
+pub use exercise::Exercise;
 pub use wb_editor::WorkbookEditorWindow;
 
 use eframe::egui;
 
-use self::exercise::Exercise;
+use self::exercise::LegacyExercise;
 use eframe::epaint::ColorImage;
 
-type WorkbookChapter = (String, Vec<Exercise>);
-type Workbook = Vec<WorkbookChapter>;
+pub type WorkbookChapter = (String, Vec<Exercise>);
+pub type Workbook = Vec<WorkbookChapter>;
 
 use crate::{console_err, console_log};
 
@@ -21,7 +23,7 @@ use {
     std::{
         fs::File,
         io::{Read, Write},
-        path::PathBuf,
+        path::{Path, PathBuf},
     },
 };
 
@@ -29,6 +31,51 @@ use rfd;
 
 const MAX_IMG_SIZE: egui::Vec2 = egui::Vec2::new(600.0, 250.0);
 
+/// The first bytes of a workbook file, followed by the version of its format. Workbooks saved
+/// before the format had a version start directly with the chapters.
+const WORKBOOK_MAGIC: &[u8; 4] = b"TMWB";
+
+/// Version of the format of the workbooks. Bincode stores the fields of the exercises by their
+/// position, so adding a field changes the format: the version must go up, keeping a decoder for
+/// the previous one in [`deserialize_workbook`].
+const WORKBOOK_VERSION: u32 = 1;
+
+/// Computes an identifier for the workbook from the titles of its chapters and exercises,
+/// so that the progress of the student can be found again when the workbook is reopened.
+fn workbook_id(exercises: &Workbook) -> String {
+    // FNV-1a, which (unlike the std hasher) is stable across compiler versions
+    let mut hash: u64 = 0xcbf29ce484222325;
+
+    // Every title ends with a separator, so that moving characters from a title to the next one
+    // changes the identifier. Chapters end with a second one.
+    let titles = exercises.iter().flat_map(|(title, chapter)| {
+        std::iter::once(title)
+            .chain(chapter.iter().map(|e| &e.title))
+            .flat_map(|t| t.bytes().chain(std::iter::once(0)))
+            .chain(std::iter::once(0))
+    });
+
+    for byte in titles {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    format!("{:016x}", hash)
+}
+
+/// Returns a copy of the workbook without the solutions and the teacher notes of its exercises
+pub fn student_edition(exercises: &Workbook) -> Workbook {
+    exercises
+        .iter()
+        .map(|(title, chapter)| {
+            (
+                title.clone(),
+                chapter.iter().map(Exercise::student_edition).collect(),
+            )
+        })
+        .collect()
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 fn pick_image() -> Option<PathBuf> {
     let path = std::env::current_dir().unwrap();
@@ -148,6 +195,42 @@ fn load_image() -> Option<ColorImage> {
     }
 }
 
+/// Serializes a workbook with the header of the current version of the format
+pub fn serialize_workbook(exercises: &Workbook) -> Result<Vec<u8>, bincode::Error> {
+    let mut data = WORKBOOK_MAGIC.to_vec();
+    data.extend(WORKBOOK_VERSION.to_le_bytes());
+    data.extend(bincode::serialize(exercises)?);
+
+    Ok(data)
+}
+
+/// Deserializes a workbook with the decoder of the version in its header. Files without a header
+/// were saved before exercises had statements.
+fn deserialize_workbook(data: &[u8]) -> Result<Workbook, bincode::Error> {
+    let Some(data) = data.strip_prefix(WORKBOOK_MAGIC) else {
+        return bincode::deserialize::<Vec<(String, Vec<LegacyExercise>)>>(data).map(|legacy| {
+            legacy
+                .into_iter()
+                .map(|(title, exercises)| {
+                    (title, exercises.into_iter().map(Exercise::from).collect())
+                })
+                .collect()
+        });
+    };
+
+    let (version, data) = data.split_at(data.len().min(4));
+    match version.try_into().map(u32::from_le_bytes) {
+        Ok(WORKBOOK_VERSION) => bincode::deserialize::<Workbook>(data),
+        Ok(version) => Err(Box::new(bincode::ErrorKind::Custom(format!(
+            "the workbook was saved with a newer version of the format ({})",
+            version
+        )))),
+        Err(_) => Err(Box::new(bincode::ErrorKind::Custom(String::from(
+            "the workbook ends before the version of its format",
+        )))),
+    }
+}
+
 fn raw_data_to_image(img_size: (u32, u32), data: &[u8]) -> ColorImage {
     let image = image::load_from_memory(data).expect("Failed to load image");
 
@@ -182,7 +265,7 @@ pub fn save_workbook(exercises: &Workbook) {
         /*
         FIXME: Not working
 
-        let data = bincode::serialize(&exercises).unwrap();
+        let data = serialize_workbook(exercises).unwrap();
         let data_url = format!(
             "data:application/octet-stream;base64,{}",
             base64.encode(&data)
@@ -202,7 +285,7 @@ pub fn save_workbook(exercises: &Workbook) {
         if let Some(mut f) = file_path {
             f.set_extension("wb");
 
-            let data = bincode::serialize(&exercises).unwrap();
+            let data = serialize_workbook(exercises).unwrap();
             let mut file = File::create(&f).unwrap();
             file.write_all(&data).unwrap();
 
@@ -226,7 +309,7 @@ pub async fn load_workbook() -> Option<Workbook> {
         Some(f) => {
             let reader: Vec<u8> = f.read().await;
 
-            match bincode::deserialize::<Workbook>(&reader) {
+            match deserialize_workbook(&reader) {
                 Ok(exercises) => {
                     console_log!("Workbook loaded from {:?}", &f);
                     Some(exercises)
@@ -244,6 +327,30 @@ pub async fn load_workbook() -> Option<Workbook> {
     }
 }
 
+/// Reads a workbook from a file, without any user interaction
+#[cfg(not(target_family = "wasm"))]
+pub fn read_workbook(path: &Path) -> Option<Workbook> {
+    let mut reader: Vec<u8> = Vec::new();
+
+    if let Err(e) = File::open(path).and_then(|mut file| file.read_to_end(&mut reader)) {
+        console_err!("Cannot read workbook {:?}: {}", path, e);
+        return None;
+    }
+
+    console_log!("Read {} bytes", reader.len());
+
+    match deserialize_workbook(&reader) {
+        Ok(exercises) => {
+            console_log!("Workbook loaded from {:?}", path);
+            Some(exercises)
+        }
+        Err(e) => {
+            console_err!("Cannot load workbook: {}", e);
+            None
+        }
+    }
+}
+
 #[cfg(not(target_family = "wasm"))]
 pub fn load_workbook() -> Option<Workbook> {
     let path = std::env::current_dir().unwrap();
@@ -254,24 +361,7 @@ pub fn load_workbook() -> Option<Workbook> {
         .pick_file();
 
     match file_path {
-        Some(f) => {
-            let mut file = File::open(&f).expect("File not found");
-            let mut reader: Vec<u8> = Vec::new();
-            file.read_to_end(&mut reader).expect("Could not read file");
-
-            console_log!("Read {} bytes", reader.len());
-
-            match bincode::deserialize::<Workbook>(&reader) {
-                Ok(exercises) => {
-                    console_log!("Workbook loaded from {:?}", &f);
-                    Some(exercises)
-                }
-                Err(e) => {
-                    console_err!("Cannot load workbook: {}", e);
-                    None
-                }
-            }
-        }
+        Some(f) => read_workbook(&f),
         None => {
             console_log!("The path is not valid");
             None
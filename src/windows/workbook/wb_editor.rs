@@ -1,14 +1,79 @@
 use eframe::egui;
 use internationalization::t;
 
+use crate::{
+    machine::Simulator,
+    runner::{passes, run_test, ExerciseTest, DEFAULT_STEP_LIMIT},
+};
+
 use super::{
-    exercise::Exercise, load_image, save_workbook, Workbook, WorkbookChapter, MAX_IMG_SIZE,
+    exercise::Exercise, load_image, load_workbook, markdown::show_markdown, save_workbook,
+    student_edition, Workbook, WorkbookChapter, MAX_IMG_SIZE,
 };
 
+#[cfg(target_family = "wasm")]
+use poll_promise::Promise;
+
+/// Maximum number of states kept in the undo history
+const MAX_HISTORY: usize = 50;
+
+type Snapshot = (Workbook, (usize, usize));
+
+/// Structural changes requested while drawing the editor. They are applied after the UI pass,
+/// so that the workbook is not modified while it is being iterated.
+enum EditorAction {
+    AddChapter,
+    DeleteChapter(usize),
+    MoveChapter(usize, isize),
+    AddExercise(usize),
+    DeleteExercise((usize, usize)),
+    DuplicateExercise((usize, usize)),
+    MoveExercise((usize, usize), isize),
+    MoveToChapter((usize, usize), usize),
+    ReplaceCover((usize, usize)),
+    RemoveCover((usize, usize)),
+    /// Adds or removes the reference solution, which starts as a copy of the code
+    SetSolution((usize, usize), bool),
+    AddHint((usize, usize)),
+    RemoveHint((usize, usize), usize),
+    AddTests((usize, usize), Vec<ExerciseTest>),
+    RemoveTest((usize, usize), usize),
+    /// Changes whether a test checks the value or the decision of an acceptor
+    SetTestKind((usize, usize), usize, Option<bool>),
+    /// Changes whether a test expects the function to be defined
+    SetTestDefined((usize, usize), usize, bool),
+    /// Changes whether a test checks the word left on the tape instead of the value
+    SetTestWord((usize, usize), usize, bool),
+    RunTests((usize, usize)),
+    Load,
+    Undo,
+    Redo,
+    Save,
+    SaveStudentEdition,
+}
+
 pub struct WorkbookEditorWindow {
     lang: String,
     chapters: Workbook,
     selected: (usize, usize),
+
+    undo_stack: Vec<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+    /// The state when a text field started being edited, which goes to the undo history when
+    /// the field changes something
+    pending: Option<Snapshot>,
+
+    /// Exercises whose code does not compile, found the last time the user tried to save
+    validation_errors: Vec<String>,
+
+    /// Whether each test of the given exercise passed with the code of the exercise
+    test_results: Option<((usize, usize), Vec<bool>)>,
+
+    /// Words typed to add tests for acceptors in bulk
+    test_words: String,
+
+    #[cfg(target_family = "wasm")]
+    file_request_future: Option<Promise<Option<Workbook>>>,
 }
 
 impl WorkbookEditorWindow {
@@ -19,6 +84,17 @@ impl WorkbookEditorWindow {
             lang: String::from(lang),
             chapters: exercises,
             selected: (0, 0),
+
+            undo_stack: vec![],
+            redo_stack: vec![],
+            pending: None,
+
+            validation_errors: vec![],
+            test_results: None,
+            test_words: String::new(),
+
+            #[cfg(target_family = "wasm")]
+            file_request_future: None,
         }
     }
 
@@ -29,9 +105,25 @@ impl WorkbookEditorWindow {
     pub fn show(&mut self, ctx: &egui::Context) -> bool {
         // ctx.set_debug_on_hover(true);
         let mut active = true;
+        let mut action: Option<EditorAction> = None;
+        let mut edit_started = false;
 
         let lang = &self.lang.clone();
 
+        #[cfg(target_family = "wasm")]
+        if let Some(file_async) = &self.file_request_future {
+            if let Some(file_result) = file_async.ready() {
+                if let Some(workbook) = file_result.clone() {
+                    self.checkpoint();
+                    self.pending = None;
+                    self.chapters = workbook.to_vec();
+                    self.selected = (0, 0);
+                }
+
+                self.file_request_future = None;
+            }
+        }
+
         egui::Window::new(t!("title.workbook.editor", lang))
             .id(egui::Id::new("editor_window"))
             .resizable(true)
@@ -39,21 +131,89 @@ impl WorkbookEditorWindow {
             .auto_sized()
             .show(ctx, |ui| {
                 ui.vertical(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.add_enabled_ui(!self.undo_stack.is_empty(), |ui| {
+                            if ui.button(t!("btn.editor.undo", lang)).clicked() {
+                                action = Some(EditorAction::Undo);
+                            }
+                        });
+
+                        ui.add_enabled_ui(!self.redo_stack.is_empty(), |ui| {
+                            if ui.button(t!("btn.editor.redo", lang)).clicked() {
+                                action = Some(EditorAction::Redo);
+                            }
+                        });
+
+                        ui.separator();
+
+                        if ui.button(t!("btn.editor.load_workbook", lang)).clicked() {
+                            action = Some(EditorAction::Load);
+                        }
+                    });
+
+                    ui.separator();
+
                     ui.horizontal(|ui| {
                         ui.vertical(|ui| {
                             ui.heading(t!("heading.workbook.catalog", lang));
 
                             egui::ScrollArea::vertical()
                                 .id_source(egui::Id::new("scroll_list"))
-                                .max_width(150.0)
+                                .max_width(200.0)
                                 .min_scrolled_height(ctx.available_rect().height() - 150.0)
                                 .show(ui, |ui| {
                                     let mut old_selected = self.selected;
+                                    let n_chapters = self.chapters.len();
 
                                     for (section, (title, exercises)) in
-                                        self.chapters.iter_mut().enumerate()
+                                        self.chapters.iter().enumerate()
                                     {
                                         ui.collapsing(title.clone(), |ui| {
+                                            ui.horizontal(|ui| {
+                                                if ui
+                                                    .add_enabled(
+                                                        section > 0,
+                                                        egui::Button::new("⏶"),
+                                                    )
+                                                    .on_hover_text(t!(
+                                                        "tooltip.editor.move_up",
+                                                        lang
+                                                    ))
+                                                    .clicked()
+                                                {
+                                                    action = Some(EditorAction::MoveChapter(
+                                                        section, -1,
+                                                    ));
+                                                }
+
+                                                if ui
+                                                    .add_enabled(
+                                                        section + 1 < n_chapters,
+                                                        egui::Button::new("⏷"),
+                                                    )
+                                                    .on_hover_text(t!(
+                                                        "tooltip.editor.move_down",
+                                                        lang
+                                                    ))
+                                                    .clicked()
+                                                {
+                                                    action =
+                                                        Some(EditorAction::MoveChapter(section, 1));
+                                                }
+
+                                                if ui
+                                                    .button("🗑")
+                                                    .on_hover_text(t!(
+                                                        "tooltip.editor.delete_chapter",
+                                                        lang
+                                                    ))
+                                                    .clicked()
+                                                {
+                                                    action =
+                                                        Some(EditorAction::DeleteChapter(section));
+                                                }
+                                            });
+
                                             for (i, exercise) in exercises.iter().enumerate() {
                                                 if ui
                                                     .add_enabled(
@@ -74,10 +234,7 @@ impl WorkbookEditorWindow {
                                                 .button(t!("btn.editor.add_exercise", lang))
                                                 .clicked()
                                             {
-                                                exercises.push(WorkbookEditorWindow::new_exercise(
-                                                    exercises.len(),
-                                                    lang,
-                                                ));
+                                                action = Some(EditorAction::AddExercise(section));
                                             }
                                         });
                                     }
@@ -86,40 +243,107 @@ impl WorkbookEditorWindow {
                                     ui.separator();
 
                                     if ui.button(t!("btn.editor.add_chapter", lang)).clicked() {
-                                        self.chapters.push(WorkbookEditorWindow::new_chapter(
-                                            self.chapters.len(),
-                                            0,
-                                            lang,
-                                        ));
-                                        self.selected = (self.chapters.len() - 1, 0);
+                                        action = Some(EditorAction::AddChapter);
                                     }
                                 });
                         });
 
                         ui.separator();
 
+                        let chapter_titles: Vec<String> = self
+                            .chapters
+                            .iter()
+                            .map(|(title, _)| title.clone())
+                            .collect();
+                        let selected = self.selected;
+
                         ui.vertical_centered_justified(|ui| {
                             if let Some(ch) = self.chapters.get_mut(self.selected.0) {
-                                ui.add(
+                                let res = ui.add(
                                     egui::TextEdit::singleline(&mut ch.0)
                                         .hint_text(t!("tooltip.editor.chapter_title", lang))
                                         .desired_width(0.0)
                                         .font(egui::TextStyle::Heading),
                                 );
+                                edit_started |= res.gained_focus();
 
                                 ui.separator();
                             }
 
-                            if let Some(ex) = self.get_exercise(self.selected) {
-                                ui.add(
+                            let n_exercises =
+                                self.chapters.get(selected.0).map_or(0, |ch| ch.1.len());
+
+                            // Borrow only the exercise, the test results are also shown below
+                            if let Some(ex) = self
+                                .chapters
+                                .get_mut(selected.0)
+                                .and_then(|ch| ch.1.get_mut(selected.1))
+                            {
+                                ui.horizontal(|ui| {
+                                    if ui
+                                        .add_enabled(selected.1 > 0, egui::Button::new("⏶"))
+                                        .on_hover_text(t!("tooltip.editor.move_up", lang))
+                                        .clicked()
+                                    {
+                                        action = Some(EditorAction::MoveExercise(selected, -1));
+                                    }
+
+                                    if ui
+                                        .add_enabled(
+                                            selected.1 + 1 < n_exercises,
+                                            egui::Button::new("⏷"),
+                                        )
+                                        .on_hover_text(t!("tooltip.editor.move_down", lang))
+                                        .clicked()
+                                    {
+                                        action = Some(EditorAction::MoveExercise(selected, 1));
+                                    }
+
+                                    if ui.button(t!("btn.editor.duplicate", lang)).clicked() {
+                                        action = Some(EditorAction::DuplicateExercise(selected));
+                                    }
+
+                                    egui::ComboBox::from_id_source("move_to_chapter")
+                                        .selected_text(t!("btn.editor.move_to", lang))
+                                        .show_ui(ui, |ui| {
+                                            for (i, title) in chapter_titles.iter().enumerate() {
+                                                if i != selected.0
+                                                    && ui.selectable_label(false, title).clicked()
+                                                {
+                                                    action = Some(EditorAction::MoveToChapter(
+                                                        selected, i,
+                                                    ));
+                                                }
+                                            }
+                                        });
+
+                                    if ui.button(t!("btn.editor.delete_exercise", lang)).clicked() {
+                                        action = Some(EditorAction::DeleteExercise(selected));
+                                    }
+                                });
+
+                                let res = ui.add(
                                     egui::TextEdit::singleline(&mut ex.title)
                                         .hint_text(t!("tooltip.editor.chapter_title", lang))
                                         .desired_width(0.0)
                                         .font(egui::TextStyle::Heading),
                                 );
+                                edit_started |= res.gained_focus();
 
                                 if let Some(img) = &ex.image {
                                     img.show_max_size(ui, MAX_IMG_SIZE);
+
+                                    ui.horizontal(|ui| {
+                                        if ui.button(t!("btn.editor.replace_image", lang)).clicked()
+                                        {
+                                            action = Some(EditorAction::ReplaceCover(selected));
+                                        }
+
+                                        if ui.button(t!("btn.editor.remove_image", lang)).clicked()
+                                        {
+                                            action = Some(EditorAction::RemoveCover(selected));
+                                        }
+                                    });
                                 } else {
                                     let rect = egui::Rect::from_min_size(
                                         ui.cursor().left_top(),
@@ -137,9 +361,7 @@ impl WorkbookEditorWindow {
                                     ui.horizontal(|ui| {
                                         ui.add_space(15.0);
                                         if ui.button(t!("btn.editor.add_image", lang)).clicked() {
-                                            if let Some(img) = load_image() {
-                                                ex.set_cover(img);
-                                            }
+                                            action = Some(EditorAction::ReplaceCover(selected));
                                         }
                                     });
                                 }
@@ -157,25 +379,584 @@ impl WorkbookEditorWindow {
                                             .code_editor()
                                             .desired_width(0.0);
 
-                                        my_ui.add(editor);
+                                        edit_started |= my_ui.add(editor).gained_focus();
                                     });
 
                                 ex.code = code.clone();
+
+                                let mut has_solution = ex.solution.is_some();
+                                if ui
+                                    .checkbox(&mut has_solution, t!("lbl.editor.solution", lang))
+                                    .changed()
+                                {
+                                    action =
+                                        Some(EditorAction::SetSolution(selected, has_solution));
+                                }
+
+                                if let Some(solution) = ex.solution.as_mut() {
+                                    egui::ScrollArea::vertical()
+                                        .id_source(egui::Id::new("scroll_solution"))
+                                        .max_height(250.0)
+                                        .show(ui, |ui| {
+                                            let res = ui.add(
+                                                egui::TextEdit::multiline(solution)
+                                                    .code_editor()
+                                                    .desired_width(0.0),
+                                            );
+                                            edit_started |= res.gained_focus();
+                                        });
+                                }
+
+                                ui.label(t!("lbl.editor.notes", lang) + ":");
+                                let res = ui.add(
+                                    egui::TextEdit::multiline(&mut ex.notes)
+                                        .hint_text(t!("tooltip.editor.notes", lang))
+                                        .desired_width(f32::INFINITY)
+                                        .desired_rows(3),
+                                );
+                                edit_started |= res.gained_focus();
+
+                                ui.label(t!("lbl.editor.statement", lang) + ":");
+
+                                ui.columns(2, |columns| {
+                                    egui::ScrollArea::vertical()
+                                        .id_source(egui::Id::new("scroll_statement"))
+                                        .max_height(250.0)
+                                        .show(&mut columns[0], |ui| {
+                                            let res = ui.add(
+                                                egui::TextEdit::multiline(&mut ex.statement)
+                                                    .hint_text(t!("tooltip.editor.statement", lang))
+                                                    .desired_width(f32::INFINITY)
+                                                    .desired_rows(10),
+                                            );
+                                            edit_started |= res.gained_focus();
+                                        });
+
+                                    egui::ScrollArea::vertical()
+                                        .id_source(egui::Id::new("scroll_preview"))
+                                        .max_height(250.0)
+                                        .show(&mut columns[1], |ui| {
+                                            show_markdown(ui, &ex.statement);
+                                        });
+                                });
+
+                                ui.label(t!("lbl.editor.hints", lang) + ":");
+
+                                let mut removed = None;
+                                for (i, hint) in ex.hints.iter_mut().enumerate() {
+                                    ui.horizontal(|ui| {
+                                        let res = ui.add(
+                                            egui::TextEdit::multiline(hint)
+                                                .desired_rows(1)
+                                                .desired_width(ui.available_width() - 50.0),
+                                        );
+                                        edit_started |= res.gained_focus();
+
+                                        if ui.button("🗑").clicked() {
+                                            removed = Some(i);
+                                        }
+                                    });
+                                }
+
+                                if let Some(i) = removed {
+                                    action = Some(EditorAction::RemoveHint(selected, i));
+                                }
+
+                                if ui.button(t!("btn.editor.add_hint", lang)).clicked() {
+                                    action = Some(EditorAction::AddHint(selected));
+                                }
+
+                                ui.label(t!("lbl.editor.tests", lang) + ":");
+
+                                let results = self
+                                    .test_results
+                                    .as_ref()
+                                    .filter(|(exercise, _)| *exercise == selected)
+                                    .map(|(_, results)| results);
+
+                                let mut removed = None;
+                                for (i, test) in ex.tests.iter_mut().enumerate() {
+                                    ui.horizontal(|ui| {
+                                        let res = ui.add(
+                                            egui::TextEdit::singleline(&mut test.input)
+                                                .hint_text(t!("tooltip.editor.test_input", lang))
+                                                .code_editor()
+                                                .desired_width(200.0),
+                                        );
+                                        edit_started |= res.gained_focus();
+
+                                        ui.label("→");
+
+                                        // A test checks either the computed value or whether an
+                                        // acceptor accepts the input
+                                        let kind_label = |accept: Option<bool>| match accept {
+                                            None => t!("lbl.editor.test_value", lang),
+                                            Some(true) => t!("lbl.editor.test_accept", lang),
+                                            Some(false) => t!("lbl.editor.test_reject", lang),
+                                        };
+                                        let mut accept = test.accept;
+                                        egui::ComboBox::from_id_source(format!("test_kind_{}", i))
+                                            .selected_text(kind_label(accept))
+                                            .show_ui(ui, |ui| {
+                                                for kind in [None, Some(true), Some(false)] {
+                                                    ui.selectable_value(
+                                                        &mut accept,
+                                                        kind,
+                                                        kind_label(kind),
+                                                    );
+                                                }
+                                            });
+                                        if accept != test.accept {
+                                            action = Some(EditorAction::SetTestKind(
+                                                selected, i, accept,
+                                            ));
+                                        }
+
+                                        if test.accept.is_none() {
+                                            let mut word = test.expected_word.is_some();
+                                            if ui
+                                                .checkbox(
+                                                    &mut word,
+                                                    t!("lbl.editor.test_word", lang),
+                                                )
+                                                .on_hover_text(t!("tooltip.editor.test_word", lang))
+                                                .changed()
+                                            {
+                                                action = Some(EditorAction::SetTestWord(
+                                                    selected, i, word,
+                                                ));
+                                            }
+                                        }
+
+                                        if let (None, Some(expected)) =
+                                            (test.accept, test.expected_word.as_mut())
+                                        {
+                                            let res = ui.add(
+                                                egui::TextEdit::singleline(expected)
+                                                    .code_editor()
+                                                    .desired_width(100.0),
+                                            );
+                                            edit_started |= res.gained_focus();
+                                        } else if test.accept.is_none() {
+                                            let mut defined = test.expected.is_some();
+                                            if ui
+                                                .checkbox(
+                                                    &mut defined,
+                                                    t!("lbl.editor.test_defined", lang),
+                                                )
+                                                .changed()
+                                            {
+                                                action = Some(EditorAction::SetTestDefined(
+                                                    selected, i, defined,
+                                                ));
+                                            }
+
+                                            if let Some(expected) = test.expected.as_mut() {
+                                                let res = ui.add(egui::DragValue::new(expected));
+                                                edit_started |=
+                                                    res.gained_focus() || res.drag_started();
+                                            }
+                                        }
+
+                                        if let Some(passed) = results.and_then(|r| r.get(i)) {
+                                            if *passed {
+                                                ui.colored_label(egui::Color32::LIGHT_GREEN, "✔");
+                                            } else {
+                                                ui.colored_label(egui::Color32::LIGHT_RED, "✘");
+                                            }
+                                        }
+
+                                        if ui.button("🗑").clicked() {
+                                            removed = Some(i);
+                                        }
+                                    });
+                                }
+
+                                if let Some(i) = removed {
+                                    action = Some(EditorAction::RemoveTest(selected, i));
+                                }
+
+                                // Several words at once, for exercises about languages
+                                ui.horizontal(|ui| {
+                                    ui.add(
+                                        egui::TextEdit::singleline(&mut self.test_words)
+                                            .hint_text(t!("tooltip.editor.test_words", lang))
+                                            .code_editor()
+                                            .desired_width(200.0),
+                                    );
+
+                                    for (accept, label) in [
+                                        (true, t!("btn.editor.add_accepted", lang)),
+                                        (false, t!("btn.editor.add_rejected", lang)),
+                                    ] {
+                                        if ui.button(label).clicked()
+                                            && !self.test_words.trim().is_empty()
+                                        {
+                                            action = Some(EditorAction::AddTests(
+                                                selected,
+                                                self.test_words
+                                                    .split([',', ' '])
+                                                    .filter(|w| !w.is_empty())
+                                                    .map(|w| ExerciseTest::word(w, accept))
+                                                    .collect(),
+                                            ));
+                                            self.test_words.clear();
+                                        }
+                                    }
+                                });
+
+                                ui.horizontal(|ui| {
+                                    if ui.button(t!("btn.editor.add_test", lang)).clicked() {
+                                        action = Some(EditorAction::AddTests(
+                                            selected,
+                                            vec![ExerciseTest::default()],
+                                        ));
+                                    }
+
+                                    if !ex.tests.is_empty()
+                                        && ui.button(t!("btn.editor.run_tests", lang)).clicked()
+                                    {
+                                        action = Some(EditorAction::RunTests(selected));
+                                    }
+                                });
                             }
                         });
                     });
 
+                    if !self.validation_errors.is_empty() {
+                        ui.separator();
+                        ui.label(
+                            egui::RichText::new(t!("lbl.editor.validation_failed", lang))
+                                .color(egui::Color32::LIGHT_RED),
+                        );
+
+                        for error in &self.validation_errors {
+                            ui.label(
+                                egui::RichText::new(error)
+                                    .color(egui::Color32::LIGHT_RED)
+                                    .monospace(),
+                            );
+                        }
+                    }
+
                     ui.horizontal(|ui| {
                         if ui.button(t!("btn.editor.save_workbook", lang)).clicked() {
-                            save_workbook(&self.chapters);
+                            action = Some(EditorAction::Save);
+                        }
+
+                        if ui
+                            .button(t!("btn.editor.save_student_edition", lang))
+                            .on_hover_text(t!("tooltip.editor.student_edition", lang))
+                            .clicked()
+                        {
+                            action = Some(EditorAction::SaveStudentEdition);
                         }
                     });
                 });
             });
 
+        // Text fields change the workbook as the user types, so the state from before they were
+        // focused is only saved once they change it
+        self.flush_pending();
+        if edit_started {
+            self.pending = Some((self.chapters.clone(), self.selected));
+        }
+
+        if let Some(action) = action {
+            self.apply(action);
+        }
+
         active
     }
 
+    /// Saves the current state in the undo history
+    fn checkpoint(&mut self) {
+        self.flush_pending();
+        self.push_undo((self.chapters.clone(), self.selected));
+    }
+
+    fn push_undo(&mut self, snapshot: Snapshot) {
+        self.undo_stack.push(snapshot);
+        if self.undo_stack.len() > MAX_HISTORY {
+            self.undo_stack.remove(0);
+        }
+
+        self.redo_stack.clear();
+    }
+
+    /// Saves in the undo history the state from before the text field being edited, if the field
+    /// changed the workbook since then
+    fn flush_pending(&mut self) {
+        if self
+            .pending
+            .as_ref()
+            .is_some_and(|(chapters, _)| *chapters != self.chapters)
+        {
+            if let Some(snapshot) = self.pending.take() {
+                self.push_undo(snapshot);
+            }
+        }
+    }
+
+    fn apply(&mut self, action: EditorAction) {
+        let lang = self.lang.clone();
+
+        match action {
+            EditorAction::Undo => {
+                self.pending = None;
+                if let Some((chapters, selected)) = self.undo_stack.pop() {
+                    self.redo_stack.push((
+                        std::mem::replace(&mut self.chapters, chapters),
+                        self.selected,
+                    ));
+                    self.selected = selected;
+                }
+                return;
+            }
+            EditorAction::Redo => {
+                self.pending = None;
+                if let Some((chapters, selected)) = self.redo_stack.pop() {
+                    self.undo_stack.push((
+                        std::mem::replace(&mut self.chapters, chapters),
+                        self.selected,
+                    ));
+                    self.selected = selected;
+                }
+                return;
+            }
+            EditorAction::Save => {
+                self.validation_errors = self.validate();
+
+                if self.validation_errors.is_empty() {
+                    save_workbook(&self.chapters);
+                }
+                return;
+            }
+            EditorAction::SaveStudentEdition => {
+                self.validation_errors = self.validate();
+
+                if self.validation_errors.is_empty() {
+                    save_workbook(&student_edition(&self.chapters));
+                }
+                return;
+            }
+            EditorAction::RunTests(exercise) => {
+                if let Some(ex) = self.get_exercise(exercise) {
+                    let results = ex
+                        .tests
+                        .iter()
+                        .map(|test| {
+                            run_test(&ex.code, test, DEFAULT_STEP_LIMIT)
+                                .is_ok_and(|outcome| passes(test, &outcome))
+                        })
+                        .collect();
+
+                    self.test_results = Some((exercise, results));
+                }
+                return;
+            }
+            EditorAction::Load => {
+                #[cfg(target_family = "wasm")]
+                {
+                    self.file_request_future =
+                        Some(poll_promise::Promise::spawn_local(load_workbook()));
+                }
+
+                #[cfg(not(target_family = "wasm"))]
+                if let Some(workbook) = load_workbook() {
+                    self.checkpoint();
+                    self.pending = None;
+                    self.chapters = workbook;
+                    self.selected = (0, 0);
+                    self.validation_errors.clear();
+                }
+                return;
+            }
+            EditorAction::ReplaceCover(exercise) => {
+                // The image is picked first, so that cancelling the dialog keeps the history
+                if let Some(img) = load_image() {
+                    self.checkpoint();
+                    if let Some(ex) = self.get_exercise(exercise) {
+                        ex.set_cover(img);
+                    }
+                }
+                return;
+            }
+            EditorAction::MoveChapter(chapter, delta) => {
+                let target = chapter.saturating_add_signed(delta);
+                if target == chapter || target >= self.chapters.len() {
+                    return;
+                }
+            }
+            EditorAction::MoveExercise((chapter, exercise), delta) => {
+                let target = exercise.saturating_add_signed(delta);
+                if target == exercise || target >= self.chapters[chapter].1.len() {
+                    return;
+                }
+            }
+            _ => {}
+        }
+
+        self.checkpoint();
+
+        match action {
+            EditorAction::AddChapter => {
+                self.chapters.push(WorkbookEditorWindow::new_chapter(
+                    self.chapters.len(),
+                    0,
+                    &lang,
+                ));
+                self.selected = (self.chapters.len() - 1, 0);
+            }
+            EditorAction::DeleteChapter(chapter) => {
+                self.chapters.remove(chapter);
+
+                if self.selected.0 > chapter {
+                    self.selected.0 -= 1;
+                } else if self.selected.0 == chapter {
+                    self.selected = (chapter.saturating_sub(1), 0);
+                }
+            }
+            EditorAction::MoveChapter(chapter, delta) => {
+                let target = chapter.saturating_add_signed(delta);
+                if target < self.chapters.len() {
+                    self.chapters.swap(chapter, target);
+
+                    if self.selected.0 == chapter {
+                        self.selected.0 = target;
+                    } else if self.selected.0 == target {
+                        self.selected.0 = chapter;
+                    }
+                }
+            }
+            EditorAction::AddExercise(chapter) => {
+                let exercises = &mut self.chapters[chapter].1;
+                exercises.push(WorkbookEditorWindow::new_exercise(exercises.len(), &lang));
+                self.selected = (chapter, exercises.len() - 1);
+            }
+            EditorAction::DeleteExercise((chapter, exercise)) => {
+                let exercises = &mut self.chapters[chapter].1;
+                exercises.remove(exercise);
+                self.selected = (chapter, exercise.min(exercises.len().saturating_sub(1)));
+            }
+            EditorAction::DuplicateExercise((chapter, exercise)) => {
+                let exercises = &mut self.chapters[chapter].1;
+                let mut copy = exercises[exercise].clone();
+                copy.title = t!("lbl.editor.copy_of", title: &copy.title, &lang);
+
+                exercises.insert(exercise + 1, copy);
+                self.selected = (chapter, exercise + 1);
+            }
+            EditorAction::MoveExercise((chapter, exercise), delta) => {
+                let exercises = &mut self.chapters[chapter].1;
+                let target = exercise.saturating_add_signed(delta);
+
+                if target < exercises.len() {
+                    exercises.swap(exercise, target);
+                    self.selected = (chapter, target);
+                }
+            }
+            EditorAction::MoveToChapter((chapter, exercise), target) => {
+                let moved = self.chapters[chapter].1.remove(exercise);
+                self.chapters[target].1.push(moved);
+                self.selected = (target, self.chapters[target].1.len() - 1);
+            }
+            EditorAction::RemoveCover(exercise) => {
+                if let Some(ex) = self.get_exercise(exercise) {
+                    ex.remove_cover();
+                }
+            }
+            EditorAction::SetSolution(exercise, solution) => {
+                if let Some(ex) = self.get_exercise(exercise) {
+                    ex.solution = solution.then(|| ex.code.clone());
+                }
+            }
+            EditorAction::AddHint(exercise) => {
+                if let Some(ex) = self.get_exercise(exercise) {
+                    ex.hints.push(String::new());
+                }
+            }
+            EditorAction::RemoveHint(exercise, hint) => {
+                if let Some(ex) = self.get_exercise(exercise) {
+                    ex.hints.remove(hint);
+                }
+            }
+            EditorAction::AddTests(exercise, tests) => {
+                if let Some(ex) = self.get_exercise(exercise) {
+                    ex.tests.extend(tests);
+                }
+            }
+            EditorAction::RemoveTest(exercise, test) => {
+                if let Some(ex) = self.get_exercise(exercise) {
+                    ex.tests.remove(test);
+                }
+            }
+            EditorAction::SetTestKind(exercise, test, accept) => {
+                if let Some(test) = self
+                    .get_exercise(exercise)
+                    .and_then(|ex| ex.tests.get_mut(test))
+                {
+                    test.accept = accept;
+                }
+            }
+            EditorAction::SetTestDefined(exercise, test, defined) => {
+                if let Some(test) = self
+                    .get_exercise(exercise)
+                    .and_then(|ex| ex.tests.get_mut(test))
+                {
+                    test.expected = if defined { Some(0) } else { None };
+                }
+            }
+            EditorAction::SetTestWord(exercise, test, word) => {
+                if let Some(test) = self
+                    .get_exercise(exercise)
+                    .and_then(|ex| ex.tests.get_mut(test))
+                {
+                    test.expected_word = word.then(String::new);
+                }
+            }
+            EditorAction::Undo
+            | EditorAction::Redo
+            | EditorAction::Save
+            | EditorAction::SaveStudentEdition
+            | EditorAction::Load
+            | EditorAction::ReplaceCover(_)
+            | EditorAction::RunTests(_) => {}
+        }
+    }
+
+    /// Checks that the code of every exercise compiles, returning a description of each failure
+    fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+
+        for (chapter, exercises) in &self.chapters {
+            for exercise in exercises {
+                if let Err(e) = Simulator::new(&exercise.code) {
+                    errors.push(format!(
+                        "{} / {} (line {}): {}",
+                        chapter,
+                        exercise.title,
+                        e.position().start.0,
+                        e.get_message_expected()
+                    ));
+                }
+
+                if let Some(Err(e)) = exercise.solution.as_deref().map(Simulator::new) {
+                    errors.push(format!(
+                        "{} / {} ({}, line {}): {}",
+                        chapter,
+                        exercise.title,
+                        t!("lbl.editor.solution", self.lang),
+                        e.position().start.0,
+                        e.get_message_expected()
+                    ));
+                }
+            }
+        }
+
+        errors
+    }
+
     fn new_chapter(chapters_len: usize, exercises_len: usize, lang: &str) -> WorkbookChapter {
         (
             t!(
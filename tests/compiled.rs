@@ -0,0 +1,178 @@
+//! Differential tests of the compiled engine: it must reach the same configuration as the
+//! simulators (the extended one and `turing_lib` for binary programs) after every step, and
+//! compute the same result.
+
+use std::{fs, path::Path};
+
+use turing_machine::{
+    convert::{self, Format},
+    machine::{CompiledMachine, Machine, Program, Simulator},
+};
+
+/// Steps after which the machines are considered not to halt
+const STEP_LIMIT: usize = 100_000;
+
+/// Runs both engines in lockstep and checks that they agree on every step
+fn check(name: &str, program: Program) {
+    let mut machine = Machine::new(program.clone());
+    let mut compiled = CompiledMachine::new(&program)
+        .unwrap_or_else(|e| panic!("{}: cannot be compiled: {}", name, e));
+
+    loop {
+        assert_eq!(machine.steps(), compiled.steps(), "{}: steps", name);
+        assert_eq!(machine.state(), compiled.state(), "{}: state", name);
+        assert_eq!(machine.words(), compiled.words(), "{}: tapes", name);
+        assert_eq!(
+            machine.fell_off(),
+            compiled.fell_off(),
+            "{}: fell off",
+            name
+        );
+
+        let halted = machine.halted() || machine.current_transition().is_none();
+        assert_eq!(halted, compiled.halted(), "{}: halted", name);
+        if halted || machine.steps() >= STEP_LIMIT {
+            break;
+        }
+
+        machine.step();
+        compiled.run(1);
+    }
+
+    check_simulator(name, &program);
+}
+
+/// Runs the compiled engine in lockstep with the simulator that the GUI uses for the code, which
+/// is `turing_lib` for binary programs, and checks that both compute the same result
+fn check_simulator(name: &str, program: &Program) {
+    let (mut simulator, _) = Simulator::new(&program.code)
+        .unwrap_or_else(|e| panic!("{}: cannot be simulated: {}", name, e));
+    let mut compiled = CompiledMachine::new(program).unwrap();
+    let blank = simulator.blank();
+
+    loop {
+        let step = simulator.steps();
+        assert_eq!(step, compiled.steps(), "{}: steps", name);
+        assert_eq!(
+            simulator.current_state(),
+            compiled.state(),
+            "{}: state at step {}",
+            name,
+            step
+        );
+
+        for k in 0..simulator.tape_count() {
+            let cells: String = (0..simulator.tape_len(k))
+                .map(|i| simulator.symbol(k, i))
+                .collect();
+            let word = cells.trim_matches(blank);
+            assert_eq!(
+                word,
+                compiled.tape_word(k),
+                "{}: tape {} at step {}",
+                name,
+                k,
+                step
+            );
+
+            // The heads are on the same cell if they see the same cells around them, as far as
+            // the symbols on the tape go
+            let (head, compiled_head) = (simulator.tape_position(k) as i64, compiled.head(k));
+            let reach = word.chars().count() as i64 + 1;
+            for d in -reach..=reach {
+                let symbol = usize::try_from(head + d)
+                    .ok()
+                    .filter(|i| *i < simulator.tape_len(k))
+                    .map_or(blank, |i| simulator.symbol(k, i));
+                assert_eq!(
+                    symbol,
+                    compiled.symbol(k, compiled_head + d),
+                    "{}: head of tape {} at step {}",
+                    name,
+                    k,
+                    step
+                );
+            }
+        }
+
+        let halted = simulator.halted() || simulator.current_instruction().is_none();
+        assert_eq!(
+            halted,
+            compiled.halted(),
+            "{}: halted at step {}",
+            name,
+            step
+        );
+        if halted || step >= STEP_LIMIT {
+            break;
+        }
+
+        simulator.step();
+        compiled.run(1);
+    }
+
+    if compiled.halted() {
+        assert_eq!(
+            simulator.final_result(),
+            compiled.output(),
+            "{}: result",
+            name
+        );
+    }
+}
+
+#[test]
+fn examples() {
+    let mut checked = 0;
+
+    for entry in fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("Examples")).unwrap() {
+        let path = entry.unwrap().path();
+        let code = fs::read_to_string(&path).unwrap();
+
+        // Composition is only available in `turing_lib`
+        let program = match Program::parse(&code) {
+            Ok(p) if !p.nondeterministic => p,
+            _ => continue,
+        };
+
+        check(&path.display().to_string(), program);
+        checked += 1;
+    }
+
+    assert!(checked > 0, "no example was checked");
+}
+
+#[test]
+fn busy_beavers() {
+    for notation in [
+        "1RB1LB_1LA1RZ",
+        "1RB1RZ_0RC1RB_1LC1LA",
+        "1RB1LB_1LA0LC_1RZ1LD_1RD0RA",
+        "1RB2LB1RZ_2LA2RB1LB",
+    ] {
+        let code = convert::convert(notation, Format::BusyBeaver, Format::Tm).unwrap();
+        check(notation, Program::parse(&code).unwrap());
+    }
+}
+
+#[test]
+fn one_way_tapes() {
+    for one_way in ["stay", "error"] {
+        let code = format!(
+            "alphabet = {{a, b}};\nblank = _;\none_way = {};\n\n{{ab}};\n\nI = {{q0}};\nF = {{q1}};\n\n\
+             (q0, a, b, L, q0);\n(q0, b, a, R, q1);\n(q0, _, a, H, q0);\n",
+            one_way
+        );
+        check(one_way, Program::parse(&code).unwrap());
+    }
+}
+
+#[test]
+fn acceptors() {
+    let code = "alphabet = {a, b};\nblank = _;\n\n{aab};\n\nI = {q0};\naccept = {yes};\n\
+                reject = {no};\n\n(q0, a, a, R, q0);\n(q0, b, b, R, q1);\n(q1, _, _, H, yes);\n";
+    for input in ["aab", "aabb", "ba", ""] {
+        let code = turing_machine::runner::with_input(code, input);
+        check(input, Program::parse(&code).unwrap());
+    }
+}
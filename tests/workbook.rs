@@ -0,0 +1,132 @@
+//! Workbooks must be read back exactly as they were saved, and their tests must grade every kind
+//! of machine
+
+use std::fs;
+
+use turing_machine::{
+    runner::{self, ExerciseTest},
+    windows::{read_workbook, serialize_workbook, Exercise, Workbook},
+};
+
+/// Writes the data to a temporary file and reads it as a workbook
+fn read_back(name: &str, data: Vec<u8>) -> Option<Workbook> {
+    let path = std::env::temp_dir().join(format!("{}_{}.wb", name, std::process::id()));
+    fs::write(&path, data).unwrap();
+    let read = read_workbook(&path);
+    fs::remove_file(&path).unwrap();
+
+    read
+}
+
+#[test]
+fn mixed_tests_round_trip() {
+    let tests = vec![
+        ExerciseTest {
+            input: String::from("1110111"),
+            expected: Some(4),
+            accept: None,
+            expected_word: None,
+        },
+        ExerciseTest::word("aab", true),
+        ExerciseTest {
+            input: String::from("10"),
+            expected: None,
+            accept: None,
+            expected_word: None,
+        },
+        ExerciseTest::word("ba", false),
+        ExerciseTest::output_word("aab", "bbb"),
+    ];
+    let workbook: Workbook = vec![
+        (
+            String::from("Functions"),
+            vec![Exercise::new("Sum", None, String::from("{1110111};"))
+                .with_statement("Add both numbers", vec![String::from("Use q1")])
+                .with_tests(tests.clone())],
+        ),
+        (
+            String::from("Acceptors"),
+            vec![Exercise::new("Words", None, String::from("{aab};")).with_tests(tests.clone())],
+        ),
+    ];
+
+    let read = read_back("round_trip", serialize_workbook(&workbook).unwrap())
+        .expect("the workbook cannot be read back");
+    assert_eq!(read.len(), workbook.len());
+    for ((title, chapter), (read_title, read_chapter)) in workbook.iter().zip(&read) {
+        assert_eq!(title, read_title);
+        assert_eq!(chapter.len(), read_chapter.len());
+        for (exercise, read_exercise) in chapter.iter().zip(read_chapter) {
+            assert_eq!(exercise.title, read_exercise.title);
+            assert_eq!(exercise.code, read_exercise.code);
+            assert_eq!(exercise.statement, read_exercise.statement);
+            assert_eq!(exercise.hints, read_exercise.hints);
+            assert_eq!(exercise.tests, read_exercise.tests);
+        }
+    }
+}
+
+#[test]
+fn word_tests_grade_machines_with_their_own_alphabet() {
+    let code = "alphabet = {a, b};
+        blank = _;
+        {aab};
+        I = {q0};
+        F = {q1};
+        (q0, a, b, R, q0);
+        (q0, b, b, R, q0);
+        (q0, _, _, L, q1);";
+
+    for (test, expected) in [
+        (ExerciseTest::output_word("aba", "bbb"), true),
+        (ExerciseTest::output_word("aba", "aba"), false),
+        (
+            ExerciseTest {
+                input: String::from("aba"),
+                ..Default::default()
+            },
+            false,
+        ),
+    ] {
+        let outcome = runner::run_test(code, &test, runner::DEFAULT_STEP_LIMIT).unwrap();
+        assert_eq!(runner::passes(&test, &outcome), expected, "{:?}", test);
+    }
+}
+
+#[test]
+fn legacy_workbooks_load() {
+    // The format of the workbooks saved before exercises had statements: the cover, the title and
+    // the code of each exercise, without any header
+    type LegacyWorkbook = Vec<(
+        String,
+        Vec<(Option<(usize, usize, Vec<u8>)>, String, String)>,
+    )>;
+    let legacy: LegacyWorkbook = vec![
+        (
+            String::from("Chapter 1"),
+            vec![
+                (
+                    Some((1, 1, vec![255, 0, 0, 255])),
+                    String::from("Exercise 1"),
+                    String::from("{111};"),
+                ),
+                (None, String::from("Exercise 2"), String::from("{1};")),
+            ],
+        ),
+        (String::from("Chapter 2"), vec![]),
+    ];
+
+    let read = read_back("legacy", bincode::serialize(&legacy).unwrap())
+        .expect("the legacy workbook cannot be read");
+
+    assert_eq!(read.len(), 2);
+    assert_eq!(read[0].0, "Chapter 1");
+    assert_eq!(read[0].1.len(), 2);
+    assert_eq!(read[0].1[0].title, "Exercise 1");
+    assert_eq!(read[0].1[0].code, "{111};");
+    assert_eq!(read[0].1[1].title, "Exercise 2");
+    assert_eq!(read[0].1[1].code, "{1};");
+    assert!(read[0].1[1].tests.is_empty());
+    assert_eq!(read[1].0, "Chapter 2");
+    assert!(read[1].1.is_empty());
+}